@@ -0,0 +1,362 @@
+use crate::repo_types::{ArtifactCleanup, ArtifactKind, ArtifactKindInfo, BuildArtifactsInfo, CleanupMode, RemovedArtifact};
+use crate::trash::Trash;
+use chrono::{DateTime, Utc};
+use git2::Repository;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+// Deepest directory level, below the repository root, at which artifacts are looked for
+const MAX_DEPTH: usize = 4;
+
+// Third-party code checked into a repository; its artifacts belong to someone else's build
+const VENDORED_DIRS: &[&str] = &["vendor", "vendored", "third_party", "third-party"];
+
+const PACKAGE_JSON: &[&str] = &["package.json"];
+const GRADLE_BUILD_FILES: &[&str] = &["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"];
+
+/// Recognizes one kind of generated directory. A directory named like one of `dir_names`
+/// is an artifact when one of `markers` sits next to it (no markers: anywhere in the
+/// repository) and `is_artifact` accepts its contents.
+pub struct ArtifactDetector {
+    pub kind: ArtifactKind,
+    pub dir_names: &'static [&'static str],
+    pub markers: &'static [&'static str],
+    pub is_artifact: fn(&Path) -> bool,
+}
+
+/// All known detectors; the first one to accept a directory decides its kind
+pub const DETECTORS: &[ArtifactDetector] = &[
+    ArtifactDetector {
+        kind: ArtifactKind::NodeModules,
+        dir_names: &["node_modules"],
+        markers: PACKAGE_JSON,
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::CargoTarget,
+        dir_names: &["target"],
+        markers: &["Cargo.toml"],
+        is_artifact: cargo_target,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::MavenTarget,
+        dir_names: &["target"],
+        markers: &["pom.xml"],
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::GradleBuild,
+        dir_names: &["build"],
+        markers: GRADLE_BUILD_FILES,
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::GradleCache,
+        dir_names: &[".gradle"],
+        markers: GRADLE_BUILD_FILES,
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::PythonVenv,
+        dir_names: &[".venv", "venv"],
+        markers: &[],
+        is_artifact: python_venv,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::PythonCache,
+        dir_names: &["__pycache__", ".pytest_cache", ".mypy_cache", ".ruff_cache"],
+        markers: &[],
+        is_artifact: python_cache,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::NextBuild,
+        dir_names: &[".next"],
+        markers: &["next.config.js", "next.config.mjs", "next.config.cjs", "next.config.ts"],
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::NuxtBuild,
+        dir_names: &[".nuxt", ".output"],
+        markers: &["nuxt.config.js", "nuxt.config.mjs", "nuxt.config.ts"],
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::JsDist,
+        dir_names: &["dist"],
+        markers: PACKAGE_JSON,
+        is_artifact: any_contents,
+    },
+    ArtifactDetector {
+        kind: ArtifactKind::CmakeBuild,
+        dir_names: &["build", "cmake-build-debug", "cmake-build-release"],
+        markers: &["CMakeLists.txt"],
+        is_artifact: cmake_build,
+    },
+];
+
+fn any_contents(_dir: &Path) -> bool {
+    true
+}
+
+// Cargo tags its target directory; older toolchains only leave .rustc_info.json
+fn cargo_target(dir: &Path) -> bool {
+    dir.join("CACHEDIR.TAG").is_file() || dir.join(".rustc_info.json").is_file()
+}
+
+fn python_venv(dir: &Path) -> bool {
+    dir.join("pyvenv.cfg").is_file()
+}
+
+fn python_cache(dir: &Path) -> bool {
+    dir.file_name().is_some_and(|name| name == "__pycache__") || dir.join("CACHEDIR.TAG").is_file()
+}
+
+fn cmake_build(dir: &Path) -> bool {
+    dir.join("CMakeCache.txt").is_file()
+}
+
+/// Artifact directories found in a repository
+pub struct Detection {
+    pub artifacts: Vec<(ArtifactKind, PathBuf)>,
+    pub markers_modified: DateTime<Utc>, // Newest marker file, or artifact directory for marker-less kinds
+}
+
+/// Find the artifact directories in a repository's working tree. Directories containing
+/// files tracked by git are never reported, whatever they are named.
+pub fn detect(repo_path: &Path) -> Result<Detection, String> {
+    let tracked = tracked_dirs(repo_path)?;
+    let mut detection = Detection {
+        artifacts: Vec::new(),
+        markers_modified: DateTime::<Utc>::from(SystemTime::UNIX_EPOCH),
+    };
+    walk(repo_path, repo_path, 1, &tracked, &mut detection);
+    detection.artifacts.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(detection)
+}
+
+fn walk(root: &Path, dir: &Path, depth: usize, tracked: &HashSet<PathBuf>, detection: &mut Detection) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        // Symlinked directories are never followed or reported
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name();
+        // Nested repositories are scanned on their own
+        if name == ".git" || path.join(".git").exists() {
+            continue;
+        }
+        if VENDORED_DIRS.iter().any(|vendored| name == *vendored) {
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if tracked.contains(relative) {
+            if depth < MAX_DEPTH {
+                walk(root, &path, depth + 1, tracked, detection);
+            }
+            continue;
+        }
+
+        let detector = DETECTORS.iter().find(|detector| {
+            detector.dir_names.iter().any(|dir_name| name == *dir_name)
+                && (detector.markers.is_empty() || detector.markers.iter().any(|marker| dir.join(marker).is_file()))
+                && (detector.is_artifact)(&path)
+        });
+
+        match detector {
+            Some(detector) => {
+                let marker_paths: Vec<PathBuf> = if detector.markers.is_empty() {
+                    vec![path.clone()]
+                } else {
+                    detector.markers.iter().map(|marker| dir.join(marker)).collect()
+                };
+                for marker in marker_paths {
+                    if let Ok(modified) = fs::metadata(&marker).and_then(|m| m.modified()) {
+                        detection.markers_modified = detection.markers_modified.max(DateTime::<Utc>::from(modified));
+                    }
+                }
+                detection.artifacts.push((detector.kind, path));
+            }
+            None if depth < MAX_DEPTH => walk(root, &path, depth + 1, tracked, detection),
+            None => {}
+        }
+    }
+}
+
+// Every directory that contains a file in the index, relative to the repository root
+fn tracked_dirs(repo_path: &Path) -> Result<HashSet<PathBuf>, String> {
+    let repo = Repository::open(repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    let index = repo.index()
+        .map_err(|e| format!("Failed to read index: {}", e))?;
+
+    let mut dirs = HashSet::new();
+    for entry in index.iter() {
+        let path = PathBuf::from(String::from_utf8_lossy(&entry.path).to_string());
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() || !dirs.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Size up the build artifacts of a repository. Sizing is skipped and `existing` returned
+/// when the same directories were found and no marker file changed since it was measured.
+pub fn scan(repo_path: &Path, existing: Option<&BuildArtifactsInfo>, force: bool) -> Result<Option<BuildArtifactsInfo>, String> {
+    let detection = detect(repo_path)?;
+    if detection.artifacts.is_empty() {
+        return Ok(None);
+    }
+
+    if let (Some(existing), false) = (existing, force) {
+        let known: HashSet<&str> = existing.kinds.iter()
+            .flat_map(|kind| kind.paths.iter().map(String::as_str))
+            .collect();
+        let found: Vec<String> = detection.artifacts.iter()
+            .map(|(_, path)| path.to_string_lossy().to_string())
+            .collect();
+        let unchanged = found.len() == known.len() && found.iter().all(|path| known.contains(path.as_str()));
+        if unchanged && detection.markers_modified <= existing.markers_modified {
+            return Ok(Some(existing.clone()));
+        }
+    }
+
+    let mut kinds: HashMap<ArtifactKind, ArtifactKindInfo> = HashMap::new();
+    for (kind, path) in &detection.artifacts {
+        let info = kinds.entry(*kind).or_insert_with(|| ArtifactKindInfo {
+            kind: *kind,
+            size_mb: 0.0,
+            paths: Vec::new(),
+        });
+        info.size_mb += directory_size_mb(path);
+        info.paths.push(path.to_string_lossy().to_string());
+    }
+
+    let mut kinds: Vec<ArtifactKindInfo> = kinds.into_values().collect();
+    kinds.sort_by(|a, b| b.size_mb.total_cmp(&a.size_mb));
+    Ok(Some(BuildArtifactsInfo {
+        total_size_mb: kinds.iter().map(|kind| kind.size_mb).sum(),
+        kinds,
+        last_scanned: Utc::now(),
+        markers_modified: detection.markers_modified,
+    }))
+}
+
+/// Remove the build artifacts of a repository, only those of `kinds` if given, or with
+/// `CleanupMode::DryRun` just list them. Artifacts are detected again rather than taken
+/// from the cache, so stale paths are never touched. `trash` is only used in trash mode.
+pub fn clean(repo_path: &Path, kinds: Option<&[ArtifactKind]>, mode: CleanupMode, trash: Option<&Trash>) -> Result<ArtifactCleanup, String> {
+    let detection = detect(repo_path)?;
+    let mut cleanup = ArtifactCleanup { mode, ..ArtifactCleanup::default() };
+
+    for (kind, path) in detection.artifacts {
+        if kinds.is_some_and(|kinds| !kinds.contains(&kind)) {
+            continue;
+        }
+        let bytes = directory_bytes(&path);
+        let removed = match (mode, trash) {
+            (CleanupMode::DryRun, _) => Ok(None),
+            (CleanupMode::Trash, Some(trash)) => trash.put(&path).map(Some),
+            (CleanupMode::Trash, None) => Err(format!("No trash to move {} to", path.display())),
+            (CleanupMode::Delete, _) => fs::remove_dir_all(&path)
+                .map(|_| None)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e)),
+        };
+        match removed {
+            Ok(trashed_path) => {
+                cleanup.total_bytes += bytes;
+                cleanup.removed.push(RemovedArtifact {
+                    kind,
+                    path: path.to_string_lossy().to_string(),
+                    bytes,
+                    trashed_path: trashed_path.map(|path| path.to_string_lossy().to_string()),
+                });
+            }
+            Err(e) => cleanup.errors.push(e),
+        }
+    }
+
+    Ok(cleanup)
+}
+
+fn directory_bytes(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn directory_size_mb(path: &Path) -> f64 {
+    directory_bytes(path) as f64 / (1024.0 * 1024.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_detects_artifacts_next_to_markers_and_skips_tracked_dirs() {
+        let dir = std::env::temp_dir().join(format!("gitmap-artifacts-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&dir).unwrap();
+
+        write(&dir, "Cargo.toml", "[package]");
+        write(&dir, "target/CACHEDIR.TAG", "Signature: 8a477f597d28d172789f06886806bc55");
+        write(&dir, "target/debug/app", "0123456789");
+        write(&dir, "web/package.json", "{}");
+        write(&dir, "web/node_modules/left-pad/index.js", "module.exports = 1");
+        write(&dir, "web/dist/bundle.js", "bundle");
+        write(&dir, "tools/build/notes.md", "no marker, not an artifact");
+        write(&dir, "app/.venv/pyvenv.cfg", "home = /usr/bin");
+        write(&dir, "vendor/lib/package.json", "{}");
+        write(&dir, "vendor/lib/node_modules/dep/index.js", "vendored");
+        // A committed dist directory is source, not an artifact
+        write(&dir, "lib/package.json", "{}");
+        write(&dir, "lib/dist/index.js", "tracked");
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib/dist/index.js")).unwrap();
+        index.write().unwrap();
+
+        let info = scan(&dir, None, false).unwrap().unwrap();
+        let kinds: HashSet<ArtifactKind> = info.kinds.iter().map(|kind| kind.kind).collect();
+        assert_eq!(kinds, HashSet::from([
+            ArtifactKind::CargoTarget,
+            ArtifactKind::NodeModules,
+            ArtifactKind::JsDist,
+            ArtifactKind::PythonVenv,
+        ]));
+        assert!(info.kinds.iter().all(|kind| !kind.paths.iter().any(|path| path.contains("lib"))));
+
+        let cleanup = clean(
+            &dir,
+            Some(&[ArtifactKind::NodeModules, ArtifactKind::JsDist]),
+            CleanupMode::Delete,
+            None,
+        ).unwrap();
+        assert_eq!(cleanup.removed.len(), 2);
+        assert!(cleanup.errors.is_empty());
+        assert!(!dir.join("web/node_modules").exists());
+        assert!(dir.join("target").exists());
+        assert!(dir.join("lib/dist/index.js").exists());
+        assert!(dir.join("vendor/lib/node_modules").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
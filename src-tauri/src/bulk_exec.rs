@@ -0,0 +1,254 @@
+use crate::cancellation::CancellationToken;
+use crate::repo_types::{BulkCommandSummary, CommandOutput, CommandResult, CommandStatus, OutputStream};
+use crate::worker_pool;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a running command is checked for exit and cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Run `command_line` through the platform shell in every repository, at most `concurrency`
+/// at a time. Output lines are passed to `on_output` as they are printed and each finished
+/// repository to `on_result`. Cancelling kills running commands and skips the rest.
+pub fn run_in_repositories(
+    run_id: &str,
+    repo_paths: Vec<String>,
+    command_line: &str,
+    concurrency: usize,
+    cancel: &CancellationToken,
+    on_output: impl Fn(CommandOutput) + Sync,
+    mut on_result: impl FnMut(&CommandResult),
+) -> BulkCommandSummary {
+    let mut results: Vec<Option<CommandResult>> = vec![None; repo_paths.len()];
+    let items: Vec<(usize, String)> = repo_paths.iter().cloned().enumerate().collect();
+
+    worker_pool::run_bounded(
+        concurrency,
+        cancel,
+        items,
+        |(index, repo_path)| (index, run_one(run_id, &repo_path, command_line, cancel, &on_output)),
+        |(index, result)| {
+            on_result(&result);
+            results[index] = Some(result);
+        },
+    );
+
+    // Repositories the pool dropped after cancellation never ran
+    let results: Vec<CommandResult> = results.into_iter()
+        .zip(repo_paths)
+        .map(|(result, repo_path)| result.unwrap_or_else(|| {
+            let result = CommandResult {
+                run_id: run_id.to_string(),
+                repo_path,
+                status: CommandStatus::Cancelled,
+                exit_code: None,
+                duration_ms: 0,
+                error: None,
+            };
+            on_result(&result);
+            result
+        }))
+        .collect();
+
+    let count = |status| results.iter().filter(|result| result.status == status).count();
+    BulkCommandSummary {
+        run_id: run_id.to_string(),
+        succeeded: count(CommandStatus::Succeeded),
+        failed: count(CommandStatus::Failed) + count(CommandStatus::Error),
+        cancelled: count(CommandStatus::Cancelled),
+        results,
+    }
+}
+
+/// Run `command_line` in one repository, streaming its output to `on_output`. Cancelling
+/// kills the command.
+pub fn run_one(
+    run_id: &str,
+    repo_path: &str,
+    command_line: &str,
+    cancel: &CancellationToken,
+    on_output: &(impl Fn(CommandOutput) + Sync),
+) -> CommandResult {
+    let started = Instant::now();
+    let result = |status, exit_code, error| CommandResult {
+        run_id: run_id.to_string(),
+        repo_path: repo_path.to_string(),
+        status,
+        exit_code,
+        duration_ms: started.elapsed().as_millis(),
+        error,
+    };
+
+    if cancel.is_cancelled() {
+        return result(CommandStatus::Cancelled, None, None);
+    }
+
+    let mut child = match shell_command(command_line, Path::new(repo_path)).spawn() {
+        Ok(child) => child,
+        Err(e) => return result(CommandStatus::Error, None, Some(format!("Failed to start command: {}", e))),
+    };
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    thread::scope(|scope| {
+        let emit = |stream, line| on_output(CommandOutput {
+            run_id: run_id.to_string(),
+            repo_path: repo_path.to_string(),
+            stream,
+            line,
+        });
+        if let Some(stdout) = stdout {
+            scope.spawn(move || forward_lines(stdout, |line| emit(OutputStream::Stdout, line)));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(move || forward_lines(stderr, |line| emit(OutputStream::Stderr, line)));
+        }
+
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    let succeeded = status.success();
+                    return result(
+                        if succeeded { CommandStatus::Succeeded } else { CommandStatus::Failed },
+                        status.code(),
+                        None,
+                    );
+                }
+                Ok(None) if cancel.is_cancelled() => {
+                    kill_tree(&mut child);
+                    return result(CommandStatus::Cancelled, None, None);
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    kill_tree(&mut child);
+                    return result(CommandStatus::Error, None, Some(format!("Failed to wait for command: {}", e)));
+                }
+            }
+        }
+    })
+}
+
+fn forward_lines(reader: impl Read, mut emit: impl FnMut(String)) {
+    // Output is not necessarily UTF-8, so read raw lines and convert lossily
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    while let Ok(read) = reader.read_until(b'\n', &mut buffer) {
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        emit(line.trim_end_matches(['\r', '\n']).to_string());
+        buffer.clear();
+    }
+}
+
+fn shell_command(command_line: &str, dir: &Path) -> Command {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(command_line);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        use std::os::unix::process::CommandExt;
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        // Own process group, so cancelling also stops whatever the shell started
+        command.process_group(0);
+        command
+    };
+
+    command
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Nothing can answer a credential prompt
+        .env("GIT_TERMINAL_PROMPT", "0");
+    command
+}
+
+// Stop the shell and the processes it started
+fn kill_tree(child: &mut Child) {
+    #[cfg(windows)]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    #[cfg(not(windows))]
+    let _ = Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", child.id())])
+        .stderr(Stdio::null())
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::sync::Mutex;
+
+    fn temp_dirs(count: usize) -> Vec<String> {
+        (0..count).map(|_| temp_dir("exec").to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_streams_output_and_records_exit_codes() {
+        let dirs = temp_dirs(2);
+        std::fs::write(Path::new(&dirs[1]).join("fail"), "").unwrap();
+        let output = Mutex::new(Vec::new());
+
+        let summary = run_in_repositories(
+            "run",
+            dirs.clone(),
+            "echo out; echo err >&2; test ! -e fail || exit 3",
+            2,
+            &CancellationToken::new(),
+            |line| output.lock().unwrap().push((line.repo_path, line.stream, line.line)),
+            |_| {},
+        );
+
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.results[0].exit_code, Some(0));
+        assert_eq!(summary.results[1].exit_code, Some(3));
+        let output = output.into_inner().unwrap();
+        assert!(output.contains(&(dirs[0].clone(), OutputStream::Stdout, "out".to_string())));
+        assert!(output.contains(&(dirs[1].clone(), OutputStream::Stderr, "err".to_string())));
+
+        for dir in dirs {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cancel_kills_running_commands_and_skips_the_rest() {
+        let dirs = temp_dirs(3);
+        let cancel = CancellationToken::new();
+        let started = Instant::now();
+
+        let summary = thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(200));
+                cancel.cancel();
+            });
+            run_in_repositories("run", dirs.clone(), "sleep 30", 1, &cancel, |_| {}, |_| {})
+        });
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(summary.cancelled, 3);
+        assert_eq!(summary.results.len(), 3);
+
+        for dir in dirs {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
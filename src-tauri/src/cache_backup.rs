@@ -0,0 +1,167 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const BACKUP_PREFIX: &str = "repositories_cache-";
+const BACKUP_SUFFIX: &str = ".json";
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheBackup {
+    pub file_name: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64, // Bytes
+}
+
+/// Replace `path` with `content` so that readers see either the old or the new file, never a
+/// partial one: write a temp file next to it, fsync it, then rename it over the original.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let file_name = path.file_name()
+        .ok_or_else(|| format!("Invalid cache file path: {:?}", path))?
+        .to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Failed to write {:?}: {}", path, e));
+    }
+
+    // Persist the rename itself; not supported on every platform, so failures are ignored
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the current cache file into `backup_dir` unless the newest backup is younger than
+/// `min_interval`, then delete all but the `keep` newest backups. Returns the new backup, if any.
+pub fn create_backup(cache_path: &Path, backup_dir: &Path, min_interval: Duration, keep: usize) -> Result<Option<CacheBackup>, String> {
+    if !cache_path.exists() {
+        return Ok(None);
+    }
+
+    let existing = list_backups(backup_dir)?;
+    let now = Utc::now();
+    if let Some(newest) = existing.first() {
+        let age = now.signed_duration_since(newest.created_at).to_std().unwrap_or_default();
+        if age < min_interval {
+            return Ok(None);
+        }
+    }
+
+    fs::create_dir_all(backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let file_name = format!("{}{}{}", BACKUP_PREFIX, now.format(BACKUP_TIME_FORMAT), BACKUP_SUFFIX);
+    let content = fs::read(cache_path)
+        .map_err(|e| format!("Failed to read cache file for backup: {}", e))?;
+    write_atomic(&backup_dir.join(&file_name), &content)?;
+
+    for old in list_backups(backup_dir)?.iter().skip(keep) {
+        if let Err(e) = fs::remove_file(backup_dir.join(&old.file_name)) {
+            eprintln!("Failed to remove old cache backup {}: {}", old.file_name, e);
+        }
+    }
+
+    Ok(Some(CacheBackup {
+        file_name,
+        created_at: now,
+        size: content.len() as u64,
+    }))
+}
+
+/// Backups in `backup_dir`, newest first
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<CacheBackup>, String> {
+    let entries = match fs::read_dir(backup_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read backup directory: {}", e)),
+    };
+
+    let mut backups: Vec<CacheBackup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = file_name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_SUFFIX)?;
+            let created_at = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIME_FORMAT).ok()?.and_utc();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(CacheBackup { file_name, created_at, size })
+        })
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// Resolve a backup file name from `list_backups`, refusing anything outside the backup directory
+pub fn backup_path(backup_dir: &Path, file_name: &str) -> Result<PathBuf, String> {
+    let is_backup_name = file_name.starts_with(BACKUP_PREFIX)
+        && file_name.ends_with(BACKUP_SUFFIX)
+        && !file_name.contains(['/', '\\']);
+    let path = backup_dir.join(file_name);
+
+    if !is_backup_name || !path.is_file() {
+        return Err(format!("Cache backup not found: {}", file_name));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gitmap-backup-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_and_leaves_no_temp_file() {
+        let dir = temp_dir();
+        let path = dir.join("cache.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_are_throttled_and_rotated() {
+        let dir = temp_dir();
+        let cache_path = dir.join("cache.json");
+        let backup_dir = dir.join("backups");
+        fs::write(&cache_path, b"{}").unwrap();
+
+        assert!(create_backup(&cache_path, &backup_dir, Duration::from_secs(3600), 3).unwrap().is_some());
+        // A recent backup exists, so this one is skipped
+        assert!(create_backup(&cache_path, &backup_dir, Duration::from_secs(3600), 3).unwrap().is_none());
+
+        for _ in 0..4 {
+            std::thread::sleep(Duration::from_millis(5));
+            create_backup(&cache_path, &backup_dir, Duration::ZERO, 3).unwrap();
+        }
+
+        let backups = list_backups(&backup_dir).unwrap();
+        assert_eq!(backups.len(), 3);
+        assert!(backups[0].created_at > backups[2].created_at);
+        assert!(backup_path(&backup_dir, &backups[0].file_name).is_ok());
+        assert!(backup_path(&backup_dir, "../cache.json").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use crate::data_store::CACHE_VERSION;
+use crate::repo_types::DEFAULT_EXCLUDE_PATTERNS;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+
+// Color given to collections saved before colors were required
+const DEFAULT_COLLECTION_COLOR: &str = "#e5e7eb";
+
+// Default exclude patterns before 1.7, which skipped every dot directory
+const LEGACY_EXCLUDE_PATTERNS: &[&str] = &[".*", "node_modules", "vendor", "target", "build", "dist"];
+
+struct Migration {
+    to_version: &'static str,
+    apply: fn(&mut Map<String, Value>) -> Result<(), String>,
+}
+
+/// Every step upgrades the raw cache JSON to `to_version`, in order. A cache runs all steps
+/// newer than its own `cache_version`; the last step must produce `CACHE_VERSION`.
+const MIGRATIONS: &[Migration] = &[
+    Migration { to_version: "1.4", apply: add_pins_and_collections },
+    Migration { to_version: "1.5", apply: branch_tracking_and_collection_colors },
+    Migration { to_version: "1.6", apply: node_modules_to_build_artifacts },
+    Migration { to_version: "1.7", apply: explicit_dot_directory_excludes },
+];
+
+/// Bring a parsed cache file up to `CACHE_VERSION`. Returns whether anything changed.
+/// Caches written by a newer version of the app are refused so they are never overwritten.
+pub fn migrate(value: &mut Value) -> Result<bool, String> {
+    let cache = value.as_object_mut()
+        .ok_or("Cache file does not contain a JSON object")?;
+
+    // Caches from before versioning was introduced have no version at all
+    let version = cache.get("cache_version")
+        .and_then(Value::as_str)
+        .unwrap_or("0")
+        .to_string();
+
+    if is_newer_version(&version) {
+        return Err(format!(
+            "Cache version {} is newer than the supported version {}; update the app to use this cache",
+            version, CACHE_VERSION
+        ));
+    }
+
+    let mut migrated = false;
+    for migration in MIGRATIONS {
+        if compare_versions(&version, migration.to_version) == Ordering::Less {
+            (migration.apply)(cache)
+                .map_err(|e| format!("Failed to migrate cache to version {}: {}", migration.to_version, e))?;
+            cache.insert("cache_version".to_string(), json!(migration.to_version));
+            migrated = true;
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// Whether a cache was written by a newer version of the app. `migrate` refuses such caches,
+/// and they must never be overwritten.
+pub fn is_newer_version(version: &str) -> bool {
+    compare_versions(version, CACHE_VERSION) == Ordering::Greater
+}
+
+/// Compare dotted version strings numerically, so "1.10" sorts after "1.9"
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u32> {
+        version.split('.').map(|part| part.trim().parse().unwrap_or(0)).collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn repositories_mut(cache: &mut Map<String, Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    cache.get_mut("repositories")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flat_map(|repositories| repositories.values_mut())
+        .filter_map(Value::as_object_mut)
+}
+
+/// 1.4: collections and pinning were added
+fn add_pins_and_collections(cache: &mut Map<String, Value>) -> Result<(), String> {
+    cache.entry("collections").or_insert_with(|| json!({}));
+    cache.entry("scan_paths").or_insert_with(|| json!({}));
+
+    for repo in repositories_mut(cache) {
+        repo.entry("is_pinned").or_insert(json!(false));
+        repo.entry("pinned_at").or_insert(Value::Null);
+        repo.entry("node_modules_info").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// 1.5: branches became objects with upstream tracking info, and collections must have a color
+fn branch_tracking_and_collection_colors(cache: &mut Map<String, Value>) -> Result<(), String> {
+    for repo in repositories_mut(cache) {
+        let Some(branches) = repo.get_mut("branches").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for branch in branches.iter_mut() {
+            if let Some(name) = branch.as_str() {
+                *branch = json!({
+                    "name": name,
+                    "is_remote": false,
+                    "is_head": false,
+                    "upstream": null,
+                    "ahead": 0,
+                    "behind": 0,
+                });
+            }
+        }
+    }
+
+    if let Some(collections) = cache.get_mut("collections").and_then(Value::as_object_mut) {
+        for collection in collections.values_mut().filter_map(Value::as_object_mut) {
+            let has_color = collection.get("color")
+                .and_then(Value::as_str)
+                .is_some_and(|color| !color.is_empty());
+            if !has_color {
+                collection.insert("color".to_string(), json!(DEFAULT_COLLECTION_COLOR));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 1.6: node_modules info became one kind of build artifact
+fn node_modules_to_build_artifacts(cache: &mut Map<String, Value>) -> Result<(), String> {
+    for repo in repositories_mut(cache) {
+        let artifacts = match repo.remove("node_modules_info") {
+            Some(Value::Object(info)) => json!({
+                "total_size_mb": info.get("total_size_mb").cloned().unwrap_or(json!(0.0)),
+                "kinds": [{
+                    "kind": "node_modules",
+                    "size_mb": info.get("total_size_mb").cloned().unwrap_or(json!(0.0)),
+                    "paths": info.get("paths").cloned().unwrap_or(json!([])),
+                }],
+                "last_scanned": info.get("last_scanned").cloned().unwrap_or(Value::Null),
+                "markers_modified": info.get("package_json_modified").cloned().unwrap_or(Value::Null),
+            }),
+            _ => Value::Null,
+        };
+        repo.insert("build_artifacts".to_string(), artifacts);
+    }
+    Ok(())
+}
+
+/// 1.7: the default rules list cache directories instead of skipping every dot directory.
+/// Configs still on the old defaults move to the new ones; edited rules are left alone.
+fn explicit_dot_directory_excludes(cache: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(patterns) = cache.get_mut("scan_config").and_then(|config| config.get_mut("exclude_patterns")) else {
+        return Ok(());
+    };
+    if *patterns == json!(LEGACY_EXCLUDE_PATTERNS) {
+        *patterns = json!(DEFAULT_EXCLUDE_PATTERNS);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_store::RepositoryCache;
+
+    fn legacy_repo() -> Value {
+        json!({
+            "name": "app",
+            "path": "/src/app",
+            "size_mb": 2.0,
+            "file_types": { "rs": 1 },
+            "last_commit_date": null,
+            "current_branch": "main",
+            "branches": ["main", "dev"],
+            "remote_url": null,
+            "commit_count": 3,
+            "last_analyzed": "2024-01-01T00:00:00Z",
+            "is_valid": true,
+        })
+    }
+
+    #[test]
+    fn test_steps_end_at_current_version() {
+        assert_eq!(MIGRATIONS.last().unwrap().to_version, CACHE_VERSION);
+        assert!(MIGRATIONS.windows(2).all(|w| compare_versions(w[0].to_version, w[1].to_version) == Ordering::Less));
+    }
+
+    #[test]
+    fn test_add_pins_and_collections() {
+        let mut cache = json!({ "repositories": { "/src/app": legacy_repo() } });
+        add_pins_and_collections(cache.as_object_mut().unwrap()).unwrap();
+
+        assert_eq!(cache["collections"], json!({}));
+        assert_eq!(cache["scan_paths"], json!({}));
+        assert_eq!(cache["repositories"]["/src/app"]["is_pinned"], json!(false));
+        assert!(cache["repositories"]["/src/app"]["pinned_at"].is_null());
+    }
+
+    #[test]
+    fn test_branch_tracking_and_collection_colors() {
+        let mut cache = json!({
+            "repositories": { "/src/app": legacy_repo() },
+            "collections": {
+                "a": { "id": "a", "name": "No color", "repository_paths": [] },
+                "b": { "id": "b", "name": "Empty", "color": "", "repository_paths": [] },
+                "c": { "id": "c", "name": "Red", "color": "#ff0000", "repository_paths": [] },
+            },
+        });
+        branch_tracking_and_collection_colors(cache.as_object_mut().unwrap()).unwrap();
+
+        let branches = &cache["repositories"]["/src/app"]["branches"];
+        assert_eq!(branches[1]["name"], json!("dev"));
+        assert_eq!(branches[1]["ahead"], json!(0));
+        assert_eq!(cache["collections"]["a"]["color"], json!(DEFAULT_COLLECTION_COLOR));
+        assert_eq!(cache["collections"]["b"]["color"], json!(DEFAULT_COLLECTION_COLOR));
+        assert_eq!(cache["collections"]["c"]["color"], json!("#ff0000"));
+    }
+
+    #[test]
+    fn test_node_modules_to_build_artifacts() {
+        let mut with_info = legacy_repo();
+        with_info["node_modules_info"] = json!({
+            "total_size_mb": 120.5,
+            "count": 1,
+            "paths": ["/src/app/node_modules"],
+            "last_scanned": "2024-01-02T00:00:00Z",
+            "package_json_modified": "2024-01-01T00:00:00Z",
+        });
+        let mut without_info = legacy_repo();
+        without_info["node_modules_info"] = Value::Null;
+        let mut cache = json!({ "repositories": { "/src/app": with_info, "/src/lib": without_info } });
+        node_modules_to_build_artifacts(cache.as_object_mut().unwrap()).unwrap();
+
+        let artifacts = &cache["repositories"]["/src/app"]["build_artifacts"];
+        assert_eq!(artifacts["total_size_mb"], json!(120.5));
+        assert_eq!(artifacts["kinds"][0]["kind"], json!("node_modules"));
+        assert_eq!(artifacts["kinds"][0]["paths"], json!(["/src/app/node_modules"]));
+        assert_eq!(artifacts["markers_modified"], json!("2024-01-01T00:00:00Z"));
+        assert!(cache["repositories"]["/src/app"].get("node_modules_info").is_none());
+        assert!(cache["repositories"]["/src/lib"]["build_artifacts"].is_null());
+    }
+
+    #[test]
+    fn test_explicit_dot_directory_excludes_only_replaces_old_defaults() {
+        let mut defaults = json!({ "scan_config": { "exclude_patterns": LEGACY_EXCLUDE_PATTERNS } });
+        explicit_dot_directory_excludes(defaults.as_object_mut().unwrap()).unwrap();
+        assert_eq!(defaults["scan_config"]["exclude_patterns"], json!(DEFAULT_EXCLUDE_PATTERNS));
+
+        let mut edited = json!({ "scan_config": { "exclude_patterns": [".*", "node_modules"] } });
+        explicit_dot_directory_excludes(edited.as_object_mut().unwrap()).unwrap();
+        assert_eq!(edited["scan_config"]["exclude_patterns"], json!([".*", "node_modules"]));
+    }
+
+    #[test]
+    fn test_legacy_cache_migrates_to_current_format() {
+        let mut value = json!({
+            "repositories": { "/src/app": legacy_repo() },
+            "scan_paths": {},
+            "last_updated": "2024-01-01T00:00:00Z",
+            "cache_version": "1.0",
+        });
+
+        assert!(migrate(&mut value).unwrap());
+        let cache: RepositoryCache = serde_json::from_value(value).unwrap();
+        assert_eq!(cache.cache_version, CACHE_VERSION);
+        assert_eq!(cache.repositories["/src/app"].branches[0].name, "main");
+    }
+
+    #[test]
+    fn test_current_version_is_untouched_and_newer_is_refused() {
+        let mut current = json!({ "cache_version": CACHE_VERSION, "repositories": {} });
+        assert!(!migrate(&mut current).unwrap());
+
+        let mut newer = json!({ "cache_version": "99.0", "repositories": {} });
+        assert!(migrate(&mut newer).is_err());
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    }
+}
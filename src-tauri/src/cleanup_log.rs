@@ -0,0 +1,163 @@
+use crate::cache_backup;
+use crate::file_lock::FileLock;
+use crate::repo_types::{ArtifactCleanup, CleanupOperation, UndoCleanupResult};
+use crate::trash::Trash;
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Oldest operations are dropped beyond this; their trashed data is left to the trash itself
+const MAX_OPERATIONS: usize = 200;
+
+/// Log of trash-mode artifact cleanups, kept so they can be undone while the trashed
+/// directories still exist. Stored as a JSON file shared by all app instances.
+pub struct CleanupLog {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl CleanupLog {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            path: dir.join("cleanup_log.json"),
+            lock_path: dir.join("cleanup_log.lock"),
+        }
+    }
+
+    /// Record a trash-mode cleanup and return the id to undo it with. Cleanups that trashed
+    /// nothing are not logged.
+    pub fn record(&self, repo_path: &str, cleanup: &ArtifactCleanup) -> Result<Option<String>, String> {
+        let artifacts: Vec<_> = cleanup.removed.iter()
+            .filter(|artifact| artifact.trashed_path.is_some())
+            .cloned()
+            .collect();
+        if artifacts.is_empty() {
+            return Ok(None);
+        }
+
+        let operation = CleanupOperation {
+            id: uuid::Uuid::new_v4().to_string(),
+            repo_path: repo_path.to_string(),
+            performed_at: Utc::now(),
+            artifacts,
+            undone_at: None,
+            restorable: false,
+        };
+        let id = operation.id.clone();
+
+        let _lock = FileLock::exclusive(&self.lock_path)?;
+        let mut operations = self.read()?;
+        operations.push(operation);
+        let excess = operations.len().saturating_sub(MAX_OPERATIONS);
+        operations.drain(..excess);
+        self.write(&operations)?;
+        Ok(Some(id))
+    }
+
+    /// Logged operations, newest first
+    pub fn operations(&self) -> Result<Vec<CleanupOperation>, String> {
+        let _lock = FileLock::exclusive(&self.lock_path)?;
+        let mut operations = self.read()?;
+        for operation in &mut operations {
+            operation.restorable = operation.undone_at.is_none()
+                && operation.artifacts.iter().all(|artifact| {
+                    artifact.trashed_path.as_ref().is_some_and(|path| Path::new(path).exists())
+                });
+        }
+        operations.reverse();
+        Ok(operations)
+    }
+
+    /// Move the directories of an operation back out of the trash. Directories that were
+    /// purged from the trash or recreated since are reported and left alone; the operation
+    /// then keeps only those, so undoing it can be retried once the problem is fixed.
+    pub fn undo(&self, operation_id: &str, trash: &Trash) -> Result<UndoCleanupResult, String> {
+        let _lock = FileLock::exclusive(&self.lock_path)?;
+        let mut operations = self.read()?;
+        let operation = operations.iter_mut()
+            .find(|operation| operation.id == operation_id)
+            .ok_or_else(|| format!("Cleanup operation {} not found", operation_id))?;
+        if let Some(undone_at) = operation.undone_at {
+            return Err(format!("Cleanup operation was already undone at {}", undone_at));
+        }
+
+        let mut result = UndoCleanupResult {
+            operation_id: operation_id.to_string(),
+            ..UndoCleanupResult::default()
+        };
+        for artifact in &operation.artifacts {
+            let Some(trashed_path) = &artifact.trashed_path else { continue };
+            match trash.restore(Path::new(trashed_path), Path::new(&artifact.path)) {
+                Ok(()) => result.restored.push(artifact.path.clone()),
+                Err(e) => result.errors.push(e),
+            }
+        }
+
+        if result.errors.is_empty() {
+            operation.undone_at = Some(Utc::now());
+        } else {
+            operation.artifacts.retain(|artifact| !result.restored.contains(&artifact.path));
+        }
+        self.write(&operations)?;
+        Ok(result)
+    }
+
+    fn read(&self) -> Result<Vec<CleanupOperation>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read cleanup log: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse cleanup log: {}", e))
+    }
+
+    fn write(&self, operations: &[CleanupOperation]) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(operations)
+            .map_err(|e| format!("Failed to serialize cleanup log: {}", e))?;
+        cache_backup::write_atomic(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_artifacts;
+    use crate::repo_types::{ArtifactKind, CleanupMode};
+
+    #[test]
+    fn test_trashed_cleanup_undo_can_be_retried_and_only_succeeds_once() {
+        let dir = std::env::temp_dir().join(format!("gitmap-cleanup-{}", uuid::Uuid::new_v4()));
+        let repo_path = dir.join("app");
+        git2::Repository::init(&repo_path).unwrap();
+        fs::write(repo_path.join("package.json"), "{}").unwrap();
+        fs::create_dir_all(repo_path.join("node_modules/pkg")).unwrap();
+        fs::write(repo_path.join("node_modules/pkg/index.js"), "12345").unwrap();
+        let trash = Trash::at(dir.join("Trash"));
+        let log = CleanupLog::new(&dir);
+
+        let preview = build_artifacts::clean(&repo_path, None, CleanupMode::DryRun, None).unwrap();
+        assert_eq!(preview.total_bytes, 5);
+        assert_eq!(preview.removed[0].kind, ArtifactKind::NodeModules);
+        assert!(repo_path.join("node_modules").exists());
+
+        let cleanup = build_artifacts::clean(&repo_path, None, CleanupMode::Trash, Some(&trash)).unwrap();
+        assert!(!repo_path.join("node_modules").exists());
+        let id = log.record(&repo_path.to_string_lossy(), &cleanup).unwrap().unwrap();
+        assert!(log.operations().unwrap()[0].restorable);
+
+        // A directory recreated in the meantime is not overwritten, and the undo can be retried
+        fs::create_dir_all(repo_path.join("node_modules")).unwrap();
+        let result = log.undo(&id, &trash).unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(log.operations().unwrap()[0].restorable);
+        fs::remove_dir(repo_path.join("node_modules")).unwrap();
+
+        let result = log.undo(&id, &trash).unwrap();
+        assert!(result.errors.is_empty());
+        assert!(repo_path.join("node_modules/pkg/index.js").exists());
+        assert!(!log.operations().unwrap()[0].restorable);
+        assert!(log.undo(&id, &trash).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+use crate::build_artifacts;
+use crate::cancellation::CancellationToken;
+use crate::cleanup_log::CleanupLog;
+use crate::data_store::{DataStore, RepositoryCache};
+use crate::repo_types::{
+    CleanupPolicy, PolicyMatch, PolicyPreview, PolicyRepositoryCleanup, PolicyRunReport, PolicyTrigger,
+};
+use crate::trash::Trash;
+use crate::worker_pool;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// How often the scheduler looks for policies that are due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Repositories `policy` would clean right now and how much space that frees
+pub fn preview(store: &DataStore, policy: &CleanupPolicy) -> Result<PolicyPreview, String> {
+    let now = Utc::now();
+    let candidates = store.read(|memory| candidates(policy, memory.cache(), now))?;
+    let matches = filter_inactive(policy, candidates, now);
+
+    Ok(PolicyPreview {
+        policy_id: policy.id.clone(),
+        evaluated_at: now,
+        estimated_bytes: matches.iter().map(|candidate| candidate.estimated_bytes).sum(),
+        matches,
+    })
+}
+
+/// Apply `policy`: evaluate it afresh, clean every matching repository and record the
+/// report. Trash-mode cleanups are logged so each repository can be restored on its own.
+pub fn execute(
+    store: &DataStore,
+    log: &CleanupLog,
+    policy: &CleanupPolicy,
+    trigger: PolicyTrigger,
+) -> Result<PolicyRunReport, String> {
+    let started_at = Utc::now();
+    let matches = preview(store, policy)?.matches;
+    let trash = Trash::for_mode(policy.settings.mode)?;
+    let kinds = policy.settings.kinds.as_deref();
+
+    let mut report = PolicyRunReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        policy_id: policy.id.clone(),
+        policy_name: policy.settings.name.clone(),
+        trigger,
+        mode: policy.settings.mode,
+        started_at,
+        finished_at: started_at,
+        repositories: Vec::new(),
+        total_bytes: 0,
+        errors: Vec::new(),
+    };
+
+    for candidate in matches {
+        let cleaned = build_artifacts::clean(Path::new(&candidate.repo_path), kinds, policy.settings.mode, trash.as_ref())
+            .and_then(|mut cleanup| {
+                cleanup.operation_id = log.record(&candidate.repo_path, &cleanup)?;
+                Ok(cleanup)
+            });
+        match cleaned {
+            Ok(cleanup) => {
+                report.total_bytes += cleanup.total_bytes;
+                report.repositories.push(PolicyRepositoryCleanup {
+                    repo_path: candidate.repo_path,
+                    cleanup,
+                });
+            }
+            Err(e) => report.errors.push(format!("{}: {}", candidate.repo_path, e)),
+        }
+    }
+
+    report.finished_at = Utc::now();
+    store.record_cleanup_report(report.clone())?;
+    Ok(report)
+}
+
+/// Whether an enabled, scheduled policy is due to run again. Only scheduled runs count, so
+/// running a policy by hand does not postpone its schedule.
+pub fn is_due(policy: &CleanupPolicy, now: DateTime<Utc>) -> bool {
+    let Some(interval_hours) = policy.settings.interval_hours.filter(|_| policy.settings.enabled) else {
+        return false;
+    };
+    policy.last_scheduled_run.is_none_or(|last_run| now - last_run >= chrono::Duration::hours(interval_hours.into()))
+}
+
+/// Runs scheduled policies in the background. Each run emits `cleanup-policy-finished`
+/// with its report and `repository-updated` for every repository it cleaned.
+pub struct CleanupScheduler;
+
+impl CleanupScheduler {
+    pub fn start(app: AppHandle) -> Result<Self, String> {
+        thread::Builder::new()
+            .name("cleanup-scheduler".to_string())
+            .spawn(move || loop {
+                thread::sleep(SCHEDULE_CHECK_INTERVAL);
+                run_due_policies(&app);
+            })
+            .map_err(|e| format!("Failed to start cleanup scheduler: {}", e))?;
+        Ok(Self)
+    }
+}
+
+fn run_due_policies(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = Utc::now();
+    let due: Vec<CleanupPolicy> = match state.data_store.get_cleanup_policies() {
+        Ok(policies) => policies.into_iter().filter(|policy| is_due(policy, now)).collect(),
+        Err(e) => {
+            eprintln!("Failed to read cleanup policies: {}", e);
+            return;
+        }
+    };
+
+    for policy in due {
+        let report = match execute(&state.data_store, &state.cleanup_log, &policy, PolicyTrigger::Scheduled) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Cleanup policy '{}' failed: {}", policy.settings.name, e);
+                continue;
+            }
+        };
+
+        // Refresh on a detached scanner, so scans and commands are not held up meanwhile
+        let mut scanner = state.scanner.blocking_lock().detached();
+        for cleaned in report.repositories.iter().filter(|cleaned| !cleaned.cleanup.removed.is_empty()) {
+            match scanner.update_repository(&cleaned.repo_path) {
+                Ok(repo) => {
+                    let _ = app.emit("repository-updated", repo);
+                }
+                Err(e) => eprintln!("Failed to refresh {} after cleanup: {}", cleaned.repo_path, e),
+            }
+        }
+        let _ = app.emit("cleanup-policy-finished", report);
+    }
+}
+
+// Cached repositories the policy applies to, judged on cached data only
+fn candidates(policy: &CleanupPolicy, cache: &RepositoryCache, now: DateTime<Utc>) -> Vec<PolicyMatch> {
+    let settings = &policy.settings;
+    let cutoff = cutoff(policy, now);
+    let excluded: HashSet<&str> = settings.exclude_collections.iter()
+        .filter_map(|id| cache.collections.get(id))
+        .flat_map(|collection| collection.repository_paths.iter().map(String::as_str))
+        .collect();
+
+    let mut candidates: Vec<PolicyMatch> = cache.repositories.values()
+        .filter(|repo| repo.is_valid && !(settings.exclude_pinned && repo.is_pinned))
+        .filter(|repo| !excluded.contains(repo.path.as_str()))
+        .filter(|repo| repo.last_commit_date.is_none_or(|date| date < cutoff))
+        .filter_map(|repo| {
+            let artifacts = repo.build_artifacts.as_ref()?;
+            let kinds: Vec<_> = artifacts.kinds.iter()
+                .filter(|info| settings.kinds.as_ref().is_none_or(|kinds| kinds.contains(&info.kind)))
+                .collect();
+            if kinds.is_empty() {
+                return None;
+            }
+            Some(PolicyMatch {
+                repo_path: repo.path.clone(),
+                name: repo.name.clone(),
+                last_commit_date: repo.last_commit_date,
+                last_modified: None,
+                estimated_bytes: kinds.iter().map(|info| (info.size_mb * 1024.0 * 1024.0) as u64).sum(),
+                kinds: kinds.iter().map(|info| info.kind).collect(),
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    candidates
+}
+
+// Keep the candidates whose working tree was not modified since the cutoff either
+fn filter_inactive(policy: &CleanupPolicy, candidates: Vec<PolicyMatch>, now: DateTime<Utc>) -> Vec<PolicyMatch> {
+    let cutoff = cutoff(policy, now);
+    let mut inactive = Vec::new();
+
+    worker_pool::run_bounded(
+        worker_pool::default_concurrency(),
+        &CancellationToken::new(),
+        candidates,
+        |mut candidate| {
+            candidate.last_modified = last_modified(Path::new(&candidate.repo_path), cutoff);
+            candidate
+        },
+        |candidate| {
+            if candidate.last_modified.is_none_or(|modified| modified < cutoff) {
+                inactive.push(candidate);
+            }
+        },
+    );
+
+    inactive.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    inactive
+}
+
+fn cutoff(policy: &CleanupPolicy, now: DateTime<Utc>) -> DateTime<Utc> {
+    now - chrono::Duration::days(policy.settings.inactive_days.into())
+}
+
+/// Newest modification time of the files in a working tree, leaving out `.git`, ignored
+/// files and build artifacts. Stops looking once a file newer than `stop_after` is seen.
+fn last_modified(repo_path: &Path, stop_after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let artifacts: HashSet<PathBuf> = build_artifacts::detect(repo_path)
+        .map(|detection| detection.artifacts.into_iter().map(|(_, path)| path).collect())
+        .unwrap_or_default();
+
+    let walker = WalkBuilder::new(repo_path)
+        .hidden(false)
+        .filter_entry(move |entry| entry.file_name() != ".git" && !artifacts.contains(entry.path()))
+        .build();
+
+    let mut newest: Option<DateTime<Utc>> = None;
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+            continue;
+        }
+        let Some(modified) = entry.metadata().ok().and_then(|metadata| metadata.modified().ok()) else {
+            continue;
+        };
+        let modified = DateTime::<Utc>::from(modified);
+        newest = Some(newest.map_or(modified, |newest| newest.max(modified)));
+        if modified > stop_after {
+            break;
+        }
+    }
+    newest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo_types::{
+        ArtifactKind, ArtifactKindInfo, BuildArtifactsInfo, CleanupMode, CleanupPolicySettings, Collection, GitRepository,
+    };
+
+    fn policy(exclude_collections: Vec<String>) -> CleanupPolicy {
+        CleanupPolicy {
+            id: "p1".to_string(),
+            settings: CleanupPolicySettings {
+                name: "Inactive".to_string(),
+                enabled: true,
+                inactive_days: 30,
+                kinds: None,
+                exclude_pinned: true,
+                exclude_collections,
+                mode: CleanupMode::Trash,
+                interval_hours: Some(24),
+            },
+            last_scheduled_run: None,
+            last_manual_run: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn repo(path: &str, last_commit: &str, is_pinned: bool) -> GitRepository {
+        let fixture = GitRepository::fixture(path);
+        GitRepository {
+            last_commit_date: Some(last_commit.parse().unwrap()),
+            is_pinned,
+            build_artifacts: Some(BuildArtifactsInfo {
+                total_size_mb: 2.0,
+                kinds: vec![ArtifactKindInfo {
+                    kind: ArtifactKind::NodeModules,
+                    size_mb: 2.0,
+                    paths: vec![format!("{}/node_modules", path)],
+                }],
+                last_scanned: fixture.last_analyzed,
+                markers_modified: fixture.last_analyzed,
+            }),
+            ..fixture
+        }
+    }
+
+    #[test]
+    fn test_candidates_skip_recent_pinned_and_excluded_repositories() {
+        let mut cache = RepositoryCache::default();
+        for repo in [
+            repo("/src/old", "2020-01-01T00:00:00Z", false),
+            repo("/src/recent", &Utc::now().to_rfc3339(), false),
+            repo("/src/pinned", "2020-01-01T00:00:00Z", true),
+            repo("/src/kept", "2020-01-01T00:00:00Z", false),
+        ] {
+            cache.repositories.insert(repo.path.clone(), repo);
+        }
+        cache.collections.insert("c1".to_string(), Collection {
+            id: "c1".to_string(),
+            name: "Keep".to_string(),
+            color: "#fff".to_string(),
+            repository_paths: vec!["/src/kept".to_string()],
+            created_at: Utc::now(),
+        });
+
+        let matches = candidates(&policy(vec!["c1".to_string()]), &cache, Utc::now());
+        let paths: Vec<&str> = matches.iter().map(|candidate| candidate.repo_path.as_str()).collect();
+        assert_eq!(paths, vec!["/src/old"]);
+        assert_eq!(matches[0].estimated_bytes, 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_is_due_respects_interval_and_enabled() {
+        let now = Utc::now();
+        let mut scheduled = policy(Vec::new());
+        assert!(is_due(&scheduled, now));
+        scheduled.last_manual_run = Some(now);
+        assert!(is_due(&scheduled, now));
+
+        scheduled.last_scheduled_run = Some(now - chrono::Duration::hours(2));
+        assert!(!is_due(&scheduled, now));
+        scheduled.last_scheduled_run = Some(now - chrono::Duration::hours(25));
+        assert!(is_due(&scheduled, now));
+
+        scheduled.settings.enabled = false;
+        assert!(!is_due(&scheduled, now));
+    }
+}
@@ -1,16 +1,45 @@
-use crate::repo_types::{GitRepository, ScanPath, Collection};
-use crate::optimizations::{PathTrie, RepositoryIndex, RepositoryCache as LruRepositoryCache, create_repository_cache};
+use crate::repo_types::{GitRepository, ScanPath, ScanConfig, Collection, RepositorySelection, CleanupPolicy,
+    CleanupPolicySettings, PolicyRunReport, PolicyTrigger};
+use crate::scan_rules::DiscoveryFilter;
+use crate::sqlite_store::SqliteStore;
+use crate::cache_backup::{self, CacheBackup};
+use crate::cache_migrations;
+use crate::file_lock::FileLock;
+use crate::memory_store::{CacheChanges, MemoryStore};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+pub const CACHE_VERSION: &str = "1.7"; // default scan rules no longer skip every dot directory
+
+// Rotating copies of the JSON cache taken before it is overwritten
+const BACKUP_KEEP: usize = 10;
+// Policy run reports kept per policy; older ones are dropped when a new one is recorded
+const CLEANUP_REPORTS_KEEP: usize = 20;
+const BACKUP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+// Changes are written once no new ones arrived for FLUSH_DELAY, but at most FLUSH_MAX_DELAY
+// after the first; without changes the cache file is checked for writes by other instances
+const FLUSH_DELAY: Duration = Duration::from_millis(500);
+const FLUSH_MAX_DELAY: Duration = Duration::from_secs(5);
+const EXTERNAL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RepositoryCache {
     pub repositories: HashMap<String, GitRepository>,
     pub scan_paths: HashMap<String, ScanPath>,
     pub collections: HashMap<String, Collection>,
+    #[serde(default)]
+    pub scan_config: ScanConfig,
+    #[serde(default)]
+    pub cleanup_policies: HashMap<String, CleanupPolicy>,
+    #[serde(default)]
+    pub cleanup_reports: HashMap<String, PolicyRunReport>, // Most recent runs, see CLEANUP_REPORTS_KEEP
     pub last_updated: DateTime<Utc>,
     pub cache_version: String,
 }
@@ -21,12 +50,30 @@ impl Default for RepositoryCache {
             repositories: HashMap::new(),
             scan_paths: HashMap::new(),
             collections: HashMap::new(),
+            scan_config: ScanConfig::default(),
+            cleanup_policies: HashMap::new(),
+            cleanup_reports: HashMap::new(),
             last_updated: Utc::now(),
-            cache_version: "1.4".to_string(), // Updated version for collections feature
+            cache_version: CACHE_VERSION.to_string(),
         }
     }
 }
 
+/// Parse cache file contents, running them through the migration chain first.
+/// Returns the cache and whether it was migrated from an older version.
+fn parse_cache(content: &str) -> Result<(RepositoryCache, bool), String> {
+    let mut value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse cache file: {}", e))?;
+    let migrated = cache_migrations::migrate(&mut value)?;
+
+    let mut cache: RepositoryCache = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse cache file (version {}): {}", CACHE_VERSION, e))?;
+    if migrated {
+        cache.last_updated = Utc::now();
+    }
+    Ok((cache, migrated))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CacheInfo {
     pub total_repositories: usize,
@@ -34,176 +81,415 @@ pub struct CacheInfo {
     pub cache_file_size: u64,
     pub valid_repositories: usize,
     pub invalid_repositories: usize,
+    pub load_state: CacheLoadState,
+}
+
+/// How the cache was loaded at startup; anything but `Loaded` is shown to the user
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheLoadState {
+    Loaded,
+    RestoredFromBackup { backup: String, error: String }, // The cache file was unreadable and moved aside
+    Empty { error: String },    // The cache file was unreadable and moved aside, and no backup was readable either
+    ReadOnly { error: String }, // The cache could not be loaded and is left untouched; changes are not saved
+    NewerVersion { error: String }, // Written by a newer version of the app; nothing is saved and backups cannot be restored
+}
+
+impl CacheLoadState {
+    // Nothing is written to disk in these states, so the cache that failed to load stays as it is
+    fn is_read_only(&self) -> bool {
+        matches!(self, CacheLoadState::ReadOnly { .. } | CacheLoadState::NewerVersion { .. })
+    }
 }
 
 pub struct DataStore {
     cache_file_path: PathBuf,
-    // Optimizations
-    path_trie: PathTrie,
-    lru_cache: LruRepositoryCache,
-    repo_index: RepositoryIndex,
+    lock_path: PathBuf,
+    backup_dir: PathBuf,
+    database_path: PathBuf,
+    // JSON is the default engine; once the JSON cache was imported with `migrate_to_sqlite`
+    // (by this or another instance, detected by the database file existing) SQLite is used
+    sqlite: OnceLock<SqliteStore>,
+    // All reads are answered from here; disk is only written by `flush`
+    memory: RwLock<MemoryStore>,
+    // State of the cache file as last read or written by this instance
+    stamp: Mutex<Option<CacheStamp>>,
+    flush_signal: OnceLock<mpsc::Sender<()>>,
+    load_state: Mutex<CacheLoadState>,
+}
+
+// Modification time and size of the cache file; changes whenever any instance writes it
+type CacheStamp = (SystemTime, u64);
+
+/// Directory holding the cache and the app's other files, created if missing
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_dir()
+        .ok_or("Could not find app data directory")?
+        .join("github-repo-manager");
+    
+    // Create the directory if it doesn't exist
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    Ok(app_data_dir)
 }
 
 impl DataStore {
-    pub fn new() -> Result<Self, String> {
-        let app_data_dir = dirs::data_dir()
-            .ok_or("Could not find app data directory")?
-            .join("github-repo-manager");
-        
-        // Create the directory if it doesn't exist
-        if !app_data_dir.exists() {
-            fs::create_dir_all(&app_data_dir)
-                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    /// Open the store in `dir`. A cache that cannot be loaded does not stop the app: it is
+    /// replaced by the newest readable backup or an empty cache, as reported by `load_state`.
+    pub fn open(dir: &Path) -> Self {
+        let mut store = Self { 
+            cache_file_path: dir.join("repositories_cache.json"),
+            lock_path: dir.join("repositories_cache.lock"),
+            backup_dir: dir.join("backups"),
+            database_path: dir.join("repositories.db"),
+            sqlite: OnceLock::new(),
+            memory: RwLock::new(MemoryStore::new(RepositoryCache::default())),
+            stamp: Mutex::new(None),
+            flush_signal: OnceLock::new(),
+            load_state: Mutex::new(CacheLoadState::Loaded),
+        };
+        
+        let (cache, load_state) = store.load_from_disk().unwrap_or_else(|error| {
+            eprintln!("Failed to load repository cache: {}", error);
+            (RepositoryCache::default(), CacheLoadState::ReadOnly { error })
+        });
+        store.memory = RwLock::new(MemoryStore::new(cache));
+        store.load_state = Mutex::new(load_state);
+        store
+    }
+    
+    /// Write changes in the background shortly after they are made, and pick up changes
+    /// other app instances write. The thread stops when the store is dropped.
+    pub fn start_write_behind(self: &Arc<Self>) {
+        // A newer version's cache stays read-only for the whole session
+        if matches!(self.load_state(), Ok(CacheLoadState::NewerVersion { .. })) {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        if self.flush_signal.set(sender).is_err() {
+            return; // Already running
         }
         
-        let cache_file_path = app_data_dir.join("repositories_cache.json");
+        let store = Arc::downgrade(self);
+        thread::spawn(move || loop {
+            let changed = match receiver.recv_timeout(EXTERNAL_CHECK_INTERVAL) {
+                Ok(()) => true,
+                Err(mpsc::RecvTimeoutError::Timeout) => false,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            
+            if changed {
+                let first_change = Instant::now();
+                while first_change.elapsed() < FLUSH_MAX_DELAY && receiver.recv_timeout(FLUSH_DELAY).is_ok() {}
+            }
+            
+            let Some(store) = store.upgrade() else { break };
+            if let Err(e) = store.flush() {
+                eprintln!("Failed to write repository cache: {}", e);
+            }
+        });
+    }
+    
+    fn sqlite(&self) -> Option<&SqliteStore> {
+        if self.sqlite.get().is_none() && self.database_path.exists() {
+            // Another instance imported the cache into SQLite; follow it there
+            match SqliteStore::open(&self.database_path) {
+                Ok(db) => {
+                    let _ = self.sqlite.set(db);
+                }
+                Err(e) => eprintln!("Failed to open database: {}", e),
+            }
+        }
+        self.sqlite.get()
+    }
+    
+    fn memory(&self) -> Result<RwLockReadGuard<'_, MemoryStore>, String> {
+        self.memory.read().map_err(|_| "Repository store lock poisoned".to_string())
+    }
+    
+    fn memory_mut(&self) -> Result<RwLockWriteGuard<'_, MemoryStore>, String> {
+        self.memory.write().map_err(|_| "Repository store lock poisoned".to_string())
+    }
+    
+    fn stamp(&self) -> Result<MutexGuard<'_, Option<CacheStamp>>, String> {
+        self.stamp.lock().map_err(|_| "Cache stamp lock poisoned".to_string())
+    }
+    
+    pub fn load_state(&self) -> Result<CacheLoadState, String> {
+        self.load_state.lock()
+            .map(|state| state.clone())
+            .map_err(|_| "Cache load state lock poisoned".to_string())
+    }
+    
+    fn set_load_state(&self, state: CacheLoadState) -> Result<(), String> {
+        *self.load_state.lock().map_err(|_| "Cache load state lock poisoned".to_string())? = state;
+        Ok(())
+    }
+    
+    fn cache_stamp(&self) -> Option<CacheStamp> {
+        let metadata = fs::metadata(self.get_cache_file_path()).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+    
+    /// Read from the in-memory cache
+    pub fn read<T>(&self, f: impl FnOnce(&MemoryStore) -> T) -> Result<T, String> {
+        Ok(f(&*self.memory()?))
+    }
+    
+    /// Change the in-memory cache; the changes are written to disk shortly after
+    pub fn edit<T>(&self, f: impl FnOnce(&mut MemoryStore) -> Result<T, String>) -> Result<T, String> {
+        let result = f(&mut *self.memory_mut()?)?;
+        if let Some(signal) = self.flush_signal.get() {
+            let _ = signal.send(());
+        }
+        Ok(result)
+    }
+    
+    /// Snapshot of the whole cache
+    pub fn load_cache(&self) -> Result<RepositoryCache, String> {
+        self.read(|memory| memory.cache().clone())
+    }
+    
+    /// Replace the whole cache, e.g. to clear it or restore a backup
+    pub fn replace_cache(&self, cache: RepositoryCache) -> Result<(), String> {
+        self.edit(|memory| {
+            memory.replace(cache);
+            Ok(())
+        })
+    }
+    
+    // Initial load, upgrading an old JSON cache file in place. Errors leave every file untouched.
+    fn load_from_disk(&self) -> Result<(RepositoryCache, CacheLoadState), String> {
+        let _lock = FileLock::exclusive(&self.lock_path)?;
         
-        let mut store = Self { 
-            cache_file_path,
-            path_trie: PathTrie::new(),
-            lru_cache: create_repository_cache(1000), // Cache last 1000 accessed repos
-            repo_index: RepositoryIndex::new(),
+        let loaded = if self.database_path.exists() {
+            let db = SqliteStore::open(&self.database_path)?;
+            match db.load_cache() {
+                Ok(cache) => {
+                    let _ = self.sqlite.set(db);
+                    (cache, CacheLoadState::Loaded)
+                }
+                Err(error) if db.cache_version()?.is_some_and(|version| cache_migrations::is_newer_version(&version)) => {
+                    (RepositoryCache::default(), CacheLoadState::NewerVersion { error })
+                }
+                Err(error) => return Err(error),
+            }
+        } else {
+            match self.read_json_cache() {
+                Ok((cache, migrated)) => {
+                    if migrated {
+                        // Keep the pre-migration file around in case a step loses data
+                        cache_backup::create_backup(&self.cache_file_path, &self.backup_dir, Duration::ZERO, BACKUP_KEEP)?;
+                        self.write_json_cache(&cache)?;
+                    }
+                    (cache, CacheLoadState::Loaded)
+                }
+                Err(error) if self.json_cache_is_newer() => {
+                    (RepositoryCache::default(), CacheLoadState::NewerVersion { error })
+                }
+                Err(error) => self.recover_json_cache(error)?,
+            }
         };
         
-        // Initialize optimizations with existing data
-        store.rebuild_optimizations()?;
+        *self.stamp()? = self.cache_stamp();
+        Ok(loaded)
+    }
+    
+    // Callers hold the lock file
+    fn json_cache_is_newer(&self) -> bool {
+        fs::read_to_string(&self.cache_file_path).ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|value| value.get("cache_version")?.as_str().map(cache_migrations::is_newer_version))
+            .unwrap_or(false)
+    }
+    
+    // Callers hold the lock file exclusively. Moves the unreadable cache file aside, so it is
+    // never overwritten, and continues from the newest backup that still parses.
+    fn recover_json_cache(&self, error: String) -> Result<(RepositoryCache, CacheLoadState), String> {
+        let broken_path = self.cache_file_path.with_extension("json.broken");
+        fs::rename(&self.cache_file_path, &broken_path)
+            .map_err(|e| format!("{}; failed to move the cache file aside: {}", error, e))?;
+        eprintln!("Moved unreadable cache file to {:?}: {}", broken_path, error);
         
-        Ok(store)
+        for backup in cache_backup::list_backups(&self.backup_dir)? {
+            let parsed = fs::read_to_string(self.backup_dir.join(&backup.file_name))
+                .map_err(|e| format!("Failed to read cache backup: {}", e))
+                .and_then(|content| parse_cache(&content));
+            match parsed {
+                Ok((cache, _)) => {
+                    if let Err(e) = self.write_json_cache(&cache) {
+                        eprintln!("Failed to write restored cache: {}", e);
+                    }
+                    return Ok((cache, CacheLoadState::RestoredFromBackup { backup: backup.file_name, error }));
+                }
+                Err(e) => eprintln!("Skipping cache backup {}: {}", backup.file_name, e),
+            }
+        }
+        Ok((RepositoryCache::default(), CacheLoadState::Empty { error }))
     }
     
-    pub fn load_cache(&self) -> Result<RepositoryCache, String> {
-        if !self.cache_file_path.exists() {
-            return Ok(RepositoryCache::default());
+    /// Write unflushed changes to disk. If another instance wrote the cache since we last read
+    /// or wrote it, our changes are applied on top of its version and that result is adopted.
+    pub fn flush(&self) -> Result<(), String> {
+        if self.load_state()?.is_read_only() {
+            return Ok(());
+        }
+        if !self.memory()?.has_changes() && self.cache_stamp() == *self.stamp()? {
+            return Ok(());
         }
         
-        let content = fs::read_to_string(&self.cache_file_path)
-            .map_err(|e| format!("Failed to read cache file: {}", e))?;
+        let _lock = FileLock::exclusive(&self.lock_path)?;
+        let external_change = self.cache_stamp() != *self.stamp()?;
+        let changes = self.memory_mut()?.take_changes();
         
-        // Try to parse as the new format first
-        match serde_json::from_str::<RepositoryCache>(&content) {
-            Ok(mut cache) => {
-                // Check if any collections are missing colors (for migration)
-                let mut needs_update = false;
-                for collection in cache.collections.values_mut() {
-                    if collection.color.is_empty() {
-                        collection.color = "#e5e7eb".to_string(); // Default light gray color
-                        needs_update = true;
-                    }
+        match self.persist(changes.as_ref(), external_change) {
+            Ok(merged) => {
+                if let Some(merged) = merged {
+                    self.memory_mut()?.rebase(merged);
                 }
-                
-                if needs_update {
-                    cache.last_updated = Utc::now();
-                    self.save_cache(&cache)?;
+                *self.stamp()? = self.cache_stamp();
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(changes) = &changes {
+                    self.memory_mut()?.restore_changes(changes);
                 }
-                
-                Ok(cache)
-            },
-            Err(_) => {
-                // If parsing fails, try to migrate from old format
-                self.migrate_cache_format(&content)
+                Err(e)
             }
         }
     }
     
-    fn migrate_cache_format(&self, content: &str) -> Result<RepositoryCache, String> {
-        // Define old cache format for migration
-        #[derive(Deserialize)]
-        struct OldRepositoryCache {
-            repositories: HashMap<String, OldGitRepository>,
-            scan_paths: HashMap<String, ScanPath>,
-            last_updated: DateTime<Utc>,
-            cache_version: String,
+    // Callers hold the lock file exclusively. Returns the merged cache if the one on disk had
+    // changed underneath us.
+    fn persist(&self, changes: Option<&CacheChanges>, external_change: bool) -> Result<Option<RepositoryCache>, String> {
+        if let Some(db) = self.sqlite() {
+            if let Some(changes) = changes {
+                db.apply_changes(changes)?;
+            }
+            return if external_change { db.load_cache().map(Some) } else { Ok(None) };
         }
         
-        #[derive(Deserialize)]
-        struct OldGitRepository {
-            name: String,
-            path: String,
-            size_mb: f64,
-            file_types: HashMap<String, u32>,
-            last_commit_date: Option<DateTime<Utc>>,
-            current_branch: Option<String>,
-            branches: Vec<String>,
-            remote_url: Option<String>,
-            commit_count: u32,
-            last_analyzed: DateTime<Utc>,
-            is_valid: bool,
+        if !external_change {
+            // The file holds what we last flushed, so memory is exactly the new file
+            let cache = self.load_cache()?;
+            return self.write_json_cache(&cache).map(|_| None);
         }
         
-        // Try to parse as old format
-        let old_cache: OldRepositoryCache = serde_json::from_str(content)
-            .map_err(|e| format!("Failed to parse cache file (old format): {}", e))?;
-        
-        // Migrate to new format
-        let mut new_repositories = HashMap::new();
-        for (path, old_repo) in old_cache.repositories {
-            let new_repo = GitRepository {
-                name: old_repo.name,
-                path: old_repo.path,
-                size_mb: old_repo.size_mb,
-                file_types: old_repo.file_types,
-                last_commit_date: old_repo.last_commit_date,
-                current_branch: old_repo.current_branch,
-                branches: old_repo.branches,
-                remote_url: old_repo.remote_url,
-                commit_count: old_repo.commit_count,
-                last_analyzed: old_repo.last_analyzed,
-                is_valid: old_repo.is_valid,
-                is_pinned: false, // Default to unpinned
-                pinned_at: None,
-                node_modules_info: None, // Default to no node_modules info for migrated repos
-            };
-            new_repositories.insert(path, new_repo);
+        let (mut cache, _) = self.read_json_cache()?;
+        if let Some(changes) = changes {
+            changes.apply_to(&mut cache);
+            cache.last_updated = Utc::now();
+            self.write_json_cache(&cache)?;
+        }
+        Ok(Some(cache))
+    }
+    
+    // Callers hold the lock file
+    fn read_json_cache(&self) -> Result<(RepositoryCache, bool), String> {
+        if !self.cache_file_path.exists() {
+            return Ok((RepositoryCache::default(), false));
         }
         
-        let migrated_cache = RepositoryCache {
-            repositories: new_repositories,
-            scan_paths: old_cache.scan_paths,
-            collections: HashMap::new(), // Initialize empty collections
-            last_updated: old_cache.last_updated,
-            cache_version: "1.4".to_string(), // Update to new version with collections
-        };
-        
-        // Save the migrated cache
-        self.save_cache(&migrated_cache)?;
-        
-        Ok(migrated_cache)
+        let content = fs::read_to_string(&self.cache_file_path)
+            .map_err(|e| format!("Failed to read cache file: {}", e))?;
+        parse_cache(&content)
     }
     
-    pub fn save_cache(&self, cache: &RepositoryCache) -> Result<(), String> {
+    // Callers hold the lock file exclusively
+    fn write_json_cache(&self, cache: &RepositoryCache) -> Result<(), String> {
         let content = serde_json::to_string_pretty(cache)
             .map_err(|e| format!("Failed to serialize cache: {}", e))?;
         
-        fs::write(&self.cache_file_path, content)
-            .map_err(|e| format!("Failed to write cache file: {}", e))?;
+        // A failed backup must not block saving
+        if let Err(e) = cache_backup::create_backup(&self.cache_file_path, &self.backup_dir, BACKUP_INTERVAL, BACKUP_KEEP) {
+            eprintln!("Failed to back up cache: {}", e);
+        }
         
-        Ok(())
+        cache_backup::write_atomic(&self.cache_file_path, content.as_bytes())
+    }
+    
+    pub fn list_cache_backups(&self) -> Result<Vec<CacheBackup>, String> {
+        cache_backup::list_backups(&self.backup_dir)
+    }
+    
+    /// Replace the current cache with a backup. The current cache is backed up first,
+    /// so a restore can itself be undone. A cache that was not loaded is saved again afterwards.
+    pub fn restore_cache_backup(&self, file_name: &str) -> Result<RepositoryCache, String> {
+        if let CacheLoadState::NewerVersion { error } = self.load_state()? {
+            return Err(format!("Restoring a backup would overwrite the newer cache: {}", error));
+        }
+        let backup_path = cache_backup::backup_path(&self.backup_dir, file_name)?;
+        let content = fs::read_to_string(&backup_path)
+            .map_err(|e| format!("Failed to read cache backup: {}", e))?;
+        
+        let (cache, _) = parse_cache(&content)
+            .map_err(|e| format!("Cache backup {} is not a valid cache file: {}", file_name, e))?;
+        
+        if self.sqlite().is_none() {
+            let _lock = FileLock::exclusive(&self.lock_path)?;
+            cache_backup::create_backup(&self.cache_file_path, &self.backup_dir, Duration::ZERO, BACKUP_KEEP)?;
+        }
+        self.set_load_state(CacheLoadState::Loaded)?;
+        self.replace_cache(cache)?;
+        self.flush()?;
+        
+        self.load_cache()
+    }
+    
+    /// One-time import of the JSON cache (including legacy formats) into a SQLite database,
+    /// which is used from then on. The JSON file is left in place untouched.
+    pub fn migrate_to_sqlite(&self) -> Result<usize, String> {
+        if self.load_state()?.is_read_only() {
+            return Err("The cache was not loaded, so it cannot be imported into SQLite".to_string());
+        }
+        if self.sqlite().is_some() {
+            return Err("The cache is already stored in SQLite".to_string());
+        }
+        self.flush()?;
+        
+        // Other instances wait on the lock and then follow the cache into the database
+        let _lock = FileLock::exclusive(&self.lock_path)?;
+        if self.sqlite().is_some() {
+            return Err("The cache is already stored in SQLite".to_string());
+        }
+        let cache = self.load_cache()?;
+        
+        // Import into a side file first so an interrupted import is never picked up as the database
+        let importing_path = self.database_path.with_extension("db.importing");
+        let _ = fs::remove_file(&importing_path);
+        SqliteStore::open(&importing_path)?.replace_all(&cache)?;
+        fs::rename(&importing_path, &self.database_path)
+            .map_err(|e| format!("Failed to move imported database into place: {}", e))?;
+        
+        if self.sqlite.get().is_none() {
+            let _ = self.sqlite.set(SqliteStore::open(&self.database_path)?);
+        }
+        *self.stamp()? = self.cache_stamp();
+        Ok(cache.repositories.len())
     }
     
     pub fn add_repository(&self, repo: GitRepository) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        cache.repositories.insert(repo.path.clone(), repo);
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+        self.edit(|memory| {
+            memory.put_repository(repo);
+            Ok(())
+        })
     }
     
-
-    //      already handled in functions with simple for loop. 
-    //
-    // pub fn remove_repository(&self, repo_path: &str) -> Result<(), String> {
-    //     let mut cache = self.load_cache()?;
-    //     cache.repositories.remove(repo_path);
-    //     cache.last_updated = Utc::now();
-    //     self.save_cache(&cache)
-    // }
-    
-    // pub fn update_repository(&self, repo: GitRepository) -> Result<(), String> {
-    //     self.add_repository(repo)
-    // }
+    pub fn remove_repository(&self, repo_path: &str) -> Result<(), String> {
+        self.edit(|memory| {
+            memory.remove_repository(repo_path);
+            Ok(())
+        })
+    }
     
     pub fn clear_cache(&self) -> Result<(), String> {
-        let cache = RepositoryCache::default();
-        self.save_cache(&cache)
+        self.replace_cache(RepositoryCache::default())
+    }
+    
+    pub fn mark_repository_invalid(&self, repo_path: &str) -> Result<Option<GitRepository>, String> {
+        self.edit(|memory| Ok(memory.update_repository(repo_path, |repo| repo.is_valid = false)))
     }
     
     pub fn validate_repositories(&self) -> Result<(Vec<GitRepository>, Vec<String>), String> {
@@ -212,7 +498,7 @@ impl DataStore {
         let mut invalid_paths = Vec::new();
         
         for (path, mut repo) in cache.repositories {
-            if std::path::Path::new(&path).join(".git").exists() {
+            if crate::repo_kind::detect_kind(std::path::Path::new(&path)).is_some() {
                 repo.is_valid = true;
                 valid_repos.push(repo);
             } else {
@@ -224,11 +510,14 @@ impl DataStore {
     }
     
     pub fn get_cache_info(&self) -> Result<CacheInfo, String> {
-        let cache = self.load_cache()?;
+        let (total_repositories, last_updated) = self.read(|memory| {
+            (memory.cache().repositories.len(), memory.cache().last_updated)
+        })?;
         let (valid_repos, invalid_repos) = self.validate_repositories()?;
         
-        let cache_file_size = if self.cache_file_path.exists() {
-            fs::metadata(&self.cache_file_path)
+        let cache_file_path = self.get_cache_file_path();
+        let cache_file_size = if cache_file_path.exists() {
+            fs::metadata(&cache_file_path)
                 .map(|m| m.len())
                 .unwrap_or(0)
         } else {
@@ -236,266 +525,367 @@ impl DataStore {
         };
         
         Ok(CacheInfo {
-            total_repositories: cache.repositories.len(),
-            last_updated: cache.last_updated,
+            total_repositories,
+            last_updated,
             cache_file_size,
             valid_repositories: valid_repos.len(),
             invalid_repositories: invalid_repos.len(),
+            load_state: self.load_state()?,
         })
     }
     
     pub fn cleanup_invalid_repositories(&self) -> Result<usize, String> {
         let (valid_repos, invalid_paths) = self.validate_repositories()?;
-        let removed_count = invalid_paths.len();
         
-        if removed_count > 0 {
-            let mut cache = RepositoryCache::default();
-            for repo in valid_repos {
-                cache.repositories.insert(repo.path.clone(), repo);
+        self.edit(|memory| {
+            for path in &invalid_paths {
+                memory.remove_repository(path);
             }
-            cache.last_updated = Utc::now();
-            self.save_cache(&cache)?;
-        }
-        
-        Ok(removed_count)
+            // Repositories marked invalid earlier that are back on disk
+            let restored: Vec<GitRepository> = valid_repos
+                .into_iter()
+                .filter(|repo| memory.cache().repositories.get(&repo.path).is_some_and(|cached| !cached.is_valid))
+                .collect();
+            for repo in restored {
+                memory.put_repository(repo);
+            }
+            Ok(invalid_paths.len())
+        })
     }
     
     pub fn add_scan_path(&self, path: String) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        
-        // Count repositories in this path
-        let repository_count = cache.repositories
-            .values()
-            .filter(|repo| repo.path.starts_with(&path))
-            .count();
-        
-        let scan_path = ScanPath {
-            path: path.clone(),
-            last_scanned: Some(Utc::now()),
-            repository_count,
-        };
-        
-        cache.scan_paths.insert(path, scan_path);
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+        self.edit(|memory| {
+            let cache = memory.cache();
+            
+            // Count repositories in this path
+            let repository_count = cache.repositories
+                .values()
+                .filter(|repo| repo.path.starts_with(&path))
+                .count();
+            
+            // Keep any rules configured for this path when it is re-added
+            let overrides = cache.scan_paths
+                .get(&path)
+                .and_then(|existing| existing.overrides.clone());
+            
+            memory.put_scan_path(ScanPath {
+                path,
+                last_scanned: Some(Utc::now()),
+                repository_count,
+                overrides,
+            });
+            Ok(())
+        })
     }
     
     pub fn remove_scan_path(&self, path: &str) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        cache.scan_paths.remove(path);
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+        self.edit(|memory| {
+            memory.remove_scan_path(path);
+            Ok(())
+        })
     }
     
     pub fn update_scan_path_last_scanned(&self, path: &str) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        
-        if let Some(scan_path) = cache.scan_paths.get_mut(path) {
-            scan_path.last_scanned = Some(Utc::now());
-            
+        self.edit(|memory| {
             // Update repository count
-            scan_path.repository_count = cache.repositories
+            let repository_count = memory.cache().repositories
                 .values()
                 .filter(|repo| repo.path.starts_with(path))
                 .count();
-        }
-        
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+            
+            memory.update_scan_path(path, |scan_path| {
+                scan_path.last_scanned = Some(Utc::now());
+                scan_path.repository_count = repository_count;
+            });
+            Ok(())
+        })
     }
     
     pub fn get_scan_paths(&self) -> Result<Vec<ScanPath>, String> {
-        let cache = self.load_cache()?;
-        Ok(cache.scan_paths.values().cloned().collect())
+        self.read(|memory| memory.cache().scan_paths.values().cloned().collect())
     }
     
-    // Pin-related methods
-    pub fn toggle_repository_pin(&self, repo_path: &str) -> Result<GitRepository, String> {
-        let mut cache = self.load_cache()?;
+    pub fn get_scan_config(&self) -> Result<ScanConfig, String> {
+        self.read(|memory| memory.cache().scan_config.clone())
+    }
+    
+    pub fn set_scan_config(&self, config: ScanConfig) -> Result<(), String> {
+        // Reject patterns that do not compile before persisting them
+        DiscoveryFilter::new(std::path::Path::new("/"), &config, None)?;
         
-        if let Some(repo) = cache.repositories.get_mut(repo_path) {
-            repo.is_pinned = !repo.is_pinned;
-            
-            if repo.is_pinned {
-                repo.pinned_at = Some(Utc::now());
-            } else {
-                repo.pinned_at = None;
+        self.edit(|memory| {
+            memory.set_scan_config(config);
+            Ok(())
+        })
+    }
+    
+    pub fn set_scan_concurrency(&self, concurrency: usize) -> Result<(), String> {
+        if concurrency == 0 {
+            return Err("Scan concurrency must be at least 1".to_string());
+        }
+        self.edit(|memory| {
+            let config = ScanConfig { scan_concurrency: Some(concurrency), ..memory.cache().scan_config.clone() };
+            memory.set_scan_config(config);
+            Ok(())
+        })
+    }
+    
+    pub fn set_scan_path_overrides(&self, path: &str, overrides: Option<ScanConfig>) -> Result<(), String> {
+        self.edit(|memory| {
+            if let Some(overrides) = &overrides {
+                DiscoveryFilter::new(std::path::Path::new(path), &memory.cache().scan_config, Some(overrides))?;
             }
             
-            let updated_repo = repo.clone(); // Clone before saving
-            cache.last_updated = Utc::now();
-            self.save_cache(&cache)?;
-            Ok(updated_repo)
-        } else {
-            Err(format!("Repository not found: {}", repo_path))
-        }
+            memory.update_scan_path(path, |scan_path| scan_path.overrides = overrides)
+                .map(|_| ())
+                .ok_or_else(|| format!("Scan path not found: {}", path))
+        })
+    }
+    
+    // Pin-related methods
+    pub fn toggle_repository_pin(&self, repo_path: &str) -> Result<GitRepository, String> {
+        self.edit(|memory| {
+            memory.update_repository(repo_path, |repo| {
+                repo.is_pinned = !repo.is_pinned;
+                
+                if repo.is_pinned {
+                    repo.pinned_at = Some(Utc::now());
+                } else {
+                    repo.pinned_at = None;
+                }
+            }).ok_or_else(|| format!("Repository not found: {}", repo_path))
+        })
     }
     
     pub fn get_pinned_repositories(&self) -> Result<Vec<GitRepository>, String> {
-        let cache = self.load_cache()?;
-        let pinned_repos: Vec<GitRepository> = cache.repositories
-            .values()
-            .filter(|repo| repo.is_pinned)
-            .cloned()
-            .collect();
-        
-        Ok(pinned_repos)
+        self.read(|memory| {
+            memory.cache().repositories
+                .values()
+                .filter(|repo| repo.is_pinned)
+                .cloned()
+                .collect()
+        })
     }
     
     // Collection-related methods
     pub fn create_collection(&self, name: String, color: String) -> Result<Collection, String> {
-        let mut cache = self.load_cache()?;
-        
-        // Check if collection name already exists
-        if cache.collections.values().any(|c| c.name == name) {
-            return Err(format!("Collection with name '{}' already exists", name));
-        }
-        
-        let collection_id = uuid::Uuid::new_v4().to_string();
-        let collection = Collection {
-            id: collection_id.clone(),
-            name,
-            color,
-            repository_paths: Vec::new(),
-            created_at: Utc::now(),
-        };
-        
-        cache.collections.insert(collection_id, collection.clone());
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)?;
-        
-        Ok(collection)
+        self.edit(|memory| {
+            // Check if collection name already exists
+            if memory.cache().collections.values().any(|c| c.name == name) {
+                return Err(format!("Collection with name '{}' already exists", name));
+            }
+            
+            let collection = Collection {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                color,
+                repository_paths: Vec::new(),
+                created_at: Utc::now(),
+            };
+            
+            memory.put_collection(collection.clone());
+            Ok(collection)
+        })
     }
     
     pub fn get_collections(&self) -> Result<Vec<Collection>, String> {
-        let cache = self.load_cache()?;
-        Ok(cache.collections.values().cloned().collect())
+        self.read(|memory| memory.cache().collections.values().cloned().collect())
     }
     
     pub fn add_repository_to_collection(&self, collection_id: &str, repo_path: &str) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        
-        // Check if repository exists
-        if !cache.repositories.contains_key(repo_path) {
-            return Err(format!("Repository not found: {}", repo_path));
-        }
-        
-        if let Some(collection) = cache.collections.get_mut(collection_id) {
-            if !collection.repository_paths.contains(&repo_path.to_string()) {
-                collection.repository_paths.push(repo_path.to_string());
+        self.edit(|memory| {
+            // Check if repository exists
+            if !memory.cache().repositories.contains_key(repo_path) {
+                return Err(format!("Repository not found: {}", repo_path));
             }
-        } else {
-            return Err(format!("Collection not found: {}", collection_id));
-        }
-        
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+            
+            memory.update_collection(collection_id, |collection| {
+                if !collection.repository_paths.contains(&repo_path.to_string()) {
+                    collection.repository_paths.push(repo_path.to_string());
+                }
+            }).map(|_| ()).ok_or_else(|| format!("Collection not found: {}", collection_id))
+        })
     }
     
     pub fn remove_repository_from_collection(&self, collection_id: &str, repo_path: &str) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        
-        if let Some(collection) = cache.collections.get_mut(collection_id) {
-            collection.repository_paths.retain(|path| path != repo_path);
-        } else {
-            return Err(format!("Collection not found: {}", collection_id));
-        }
-        
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+        self.edit(|memory| {
+            memory.update_collection(collection_id, |collection| {
+                collection.repository_paths.retain(|path| path != repo_path);
+            }).map(|_| ()).ok_or_else(|| format!("Collection not found: {}", collection_id))
+        })
     }
     
     pub fn delete_collection(&self, collection_id: &str) -> Result<(), String> {
-        let mut cache = self.load_cache()?;
-        
-        if cache.collections.remove(collection_id).is_none() {
-            return Err(format!("Collection not found: {}", collection_id));
-        }
-        
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)
+        self.edit(|memory| {
+            memory.remove_collection(collection_id)
+                .map(|_| ())
+                .ok_or_else(|| format!("Collection not found: {}", collection_id))
+        })
     }
     
     pub fn get_repositories_in_collection(&self, collection_id: &str) -> Result<Vec<GitRepository>, String> {
-        let cache = self.load_cache()?;
-        
-        if let Some(collection) = cache.collections.get(collection_id) {
-            let repos: Vec<GitRepository> = collection.repository_paths
+        self.read(|memory| {
+            let cache = memory.cache();
+            let collection = cache.collections.get(collection_id)
+                .ok_or_else(|| format!("Collection not found: {}", collection_id))?;
+            
+            Ok(collection.repository_paths
                 .iter()
                 .filter_map(|path| cache.repositories.get(path))
                 .cloned()
-                .collect();
-            Ok(repos)
-        } else {
-            Err(format!("Collection not found: {}", collection_id))
-        }
+                .collect())
+        })?
     }
     
-    pub fn get_cache_file_path(&self) -> PathBuf {
-        self.cache_file_path.clone()
+    pub fn create_cleanup_policy(&self, settings: CleanupPolicySettings) -> Result<CleanupPolicy, String> {
+        self.edit(|memory| {
+            let policy = CleanupPolicy {
+                id: uuid::Uuid::new_v4().to_string(),
+                settings,
+                last_scheduled_run: None,
+                last_manual_run: None,
+                created_at: Utc::now(),
+            };
+            memory.put_cleanup_policy(policy.clone());
+            Ok(policy)
+        })
     }
     
-    pub fn get_cache_file_path_string(&self) -> String {
-        self.cache_file_path.to_string_lossy().to_string()
+    pub fn update_cleanup_policy(&self, policy_id: &str, settings: CleanupPolicySettings) -> Result<CleanupPolicy, String> {
+        self.edit(|memory| {
+            memory.update_cleanup_policy(policy_id, |policy| policy.settings = settings)
+                .ok_or_else(|| format!("Cleanup policy not found: {}", policy_id))
+        })
     }
     
-    // === OPTIMIZATION METHODS ===
+    /// Delete a policy together with its reports
+    pub fn delete_cleanup_policy(&self, policy_id: &str) -> Result<(), String> {
+        self.edit(|memory| {
+            memory.remove_cleanup_policy(policy_id)
+                .ok_or_else(|| format!("Cleanup policy not found: {}", policy_id))?;
+            let report_ids: Vec<String> = memory.cache().cleanup_reports.values()
+                .filter(|report| report.policy_id == policy_id)
+                .map(|report| report.id.clone())
+                .collect();
+            for id in report_ids {
+                memory.remove_cleanup_report(&id);
+            }
+            Ok(())
+        })
+    }
     
-    /// Rebuild all optimization data structures from current cache
-    pub fn rebuild_optimizations(&mut self) -> Result<(), String> {
-        let cache = self.load_cache()?;
-        
-        // Clear existing optimizations
-        self.path_trie.clear();
-        self.repo_index.clear();
-        
-        // Rebuild from cache
-        for (path, repo) in &cache.repositories {
-            self.path_trie.insert_repository(path);
-            self.repo_index.insert_repository(repo);
+    pub fn get_cleanup_policy(&self, policy_id: &str) -> Result<CleanupPolicy, String> {
+        self.read(|memory| memory.cache().cleanup_policies.get(policy_id).cloned())?
+            .ok_or_else(|| format!("Cleanup policy not found: {}", policy_id))
+    }
+    
+    pub fn get_cleanup_policies(&self) -> Result<Vec<CleanupPolicy>, String> {
+        self.read(|memory| {
+            let mut policies: Vec<CleanupPolicy> = memory.cache().cleanup_policies.values().cloned().collect();
+            policies.sort_by_key(|policy| policy.created_at);
+            policies
+        })
+    }
+    
+    /// Store the report of a policy run and record its start as the last scheduled or manual run
+    pub fn record_cleanup_report(&self, report: PolicyRunReport) -> Result<(), String> {
+        self.edit(|memory| {
+            memory.update_cleanup_policy(&report.policy_id, |policy| match report.trigger {
+                PolicyTrigger::Scheduled => policy.last_scheduled_run = Some(report.started_at),
+                PolicyTrigger::Manual => policy.last_manual_run = Some(report.started_at),
+            });
             
-            // Also populate LRU cache with frequently accessed repos
-            if repo.is_pinned {
-                if let Ok(mut lru) = self.lru_cache.lock() {
-                    lru.put(path.clone(), repo.clone());
-                }
+            let mut older: Vec<(DateTime<Utc>, String)> = memory.cache().cleanup_reports.values()
+                .filter(|existing| existing.policy_id == report.policy_id)
+                .map(|existing| (existing.started_at, existing.id.clone()))
+                .collect();
+            older.sort();
+            let excess = (older.len() + 1).saturating_sub(CLEANUP_REPORTS_KEEP);
+            for (_, id) in older.into_iter().take(excess) {
+                memory.remove_cleanup_report(&id);
             }
-        }
-        
-        Ok(())
+            
+            memory.put_cleanup_report(report);
+            Ok(())
+        })
     }
     
-    /// Fast repository access with LRU caching - O(1) average case
-    pub fn get_repository_fast(&self, repo_path: &str) -> Result<Option<GitRepository>, String> {
-        // Try LRU cache first
-        if let Ok(mut lru) = self.lru_cache.lock() {
-            if let Some(repo) = lru.get(repo_path) {
-                return Ok(Some(repo.clone()));
+    /// Reports of policy runs, newest first, optionally only those of one policy
+    pub fn get_cleanup_reports(&self, policy_id: Option<&str>) -> Result<Vec<PolicyRunReport>, String> {
+        self.read(|memory| {
+            let mut reports: Vec<PolicyRunReport> = memory.cache().cleanup_reports.values()
+                .filter(|report| policy_id.is_none_or(|id| report.policy_id == id))
+                .cloned()
+                .collect();
+            reports.sort_by_key(|report| std::cmp::Reverse(report.started_at));
+            reports
+        })
+    }
+    
+    /// Paths of the cached repositories a selection refers to, sorted
+    pub fn select_repositories(&self, selection: &RepositorySelection) -> Result<Vec<String>, String> {
+        let mut paths = match selection {
+            RepositorySelection::Paths { paths } => {
+                let cached = self.read(|memory| {
+                    paths.iter()
+                        .find(|path| !memory.cache().repositories.contains_key(*path))
+                        .cloned()
+                })?;
+                if let Some(missing) = cached {
+                    return Err(format!("Repository not found: {}", missing));
+                }
+                paths.clone()
             }
-        }
+            RepositorySelection::Collection { collection_id } => self.get_repositories_in_collection(collection_id)?
+                .into_iter()
+                .map(|repo| repo.path)
+                .collect(),
+            RepositorySelection::Search { filter } => self.advanced_search(
+                filter.name_prefix.as_deref(),
+                filter.min_size_mb,
+                filter.max_size_mb,
+                filter.file_type.as_deref(),
+                filter.remote_host.as_deref(),
+                filter.remote_owner.as_deref(),
+            )?
+                .into_iter()
+                .map(|repo| repo.path)
+                .collect(),
+        };
         
-        // Fall back to disk cache
-        let cache = self.load_cache()?;
-        if let Some(repo) = cache.repositories.get(repo_path) {
-            // Update LRU cache
-            if let Ok(mut lru) = self.lru_cache.lock() {
-                lru.put(repo_path.to_string(), repo.clone());
-            }
-            Ok(Some(repo.clone()))
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+    
+    pub fn get_cache_file_path(&self) -> PathBuf {
+        if self.sqlite().is_some() {
+            self.database_path.clone()
         } else {
-            Ok(None)
+            self.cache_file_path.clone()
         }
     }
     
+    pub fn get_cache_file_path_string(&self) -> String {
+        self.get_cache_file_path().to_string_lossy().to_string()
+    }
+    
+    // === OPTIMIZATION METHODS ===
+    
+    /// Repository lookup by path - O(1)
+    pub fn get_repository_fast(&self, repo_path: &str) -> Result<Option<GitRepository>, String> {
+        self.read(|memory| memory.cache().repositories.get(repo_path).cloned())
+    }
+    
     /// Fast path-based repository search - O(m) where m is path depth
     pub fn find_repositories_under_path_optimized(&self, path: &str) -> Result<Vec<GitRepository>, String> {
-        let repo_paths = self.path_trie.find_repositories_under_path(path);
-        let cache = self.load_cache()?;
-        
-        Ok(repo_paths.into_iter()
-            .filter_map(|path| cache.repositories.get(&path))
-            .cloned()
-            .collect())
+        self.read(|memory| {
+            memory.path_trie().find_repositories_under_path(path)
+                .into_iter()
+                .filter_map(|path| memory.cache().repositories.get(&path))
+                .cloned()
+                .collect()
+        })
     }
     
     /// Advanced search using multiple indices
@@ -503,15 +893,18 @@ impl DataStore {
         name_prefix: Option<&str>,
         min_size_mb: Option<f64>,
         max_size_mb: Option<f64>,
-        file_type: Option<&str>
+        file_type: Option<&str>,
+        remote_host: Option<&str>,
+        remote_owner: Option<&str>
     ) -> Result<Vec<GitRepository>, String> {
-        let cache = self.load_cache()?;
+        let memory = self.memory()?;
+        let (cache, repo_index) = (memory.cache(), memory.repo_index());
         let mut candidate_paths = std::collections::HashSet::new();
         let mut is_first_filter = true;
         
         // Use indices for efficient filtering
         if let Some(prefix) = name_prefix {
-            let paths = self.repo_index.find_repositories_by_name_prefix(prefix);
+            let paths = repo_index.find_repositories_by_name_prefix(prefix);
             if is_first_filter {
                 candidate_paths.extend(paths);
                 is_first_filter = false;
@@ -521,7 +914,7 @@ impl DataStore {
         }
         
         if let (Some(min), Some(max)) = (min_size_mb, max_size_mb) {
-            let paths = self.repo_index.find_repositories_by_size_range(min, max);
+            let paths = repo_index.find_repositories_by_size_range(min, max);
             if is_first_filter {
                 candidate_paths.extend(paths);
                 is_first_filter = false;
@@ -531,7 +924,27 @@ impl DataStore {
         }
         
         if let Some(file_ext) = file_type {
-            let paths = self.repo_index.find_repositories_by_file_type(file_ext);
+            let paths = repo_index.find_repositories_by_file_type(file_ext);
+            if is_first_filter {
+                candidate_paths.extend(paths);
+                is_first_filter = false;
+            } else {
+                candidate_paths.retain(|path| paths.contains(path));
+            }
+        }
+        
+        if let Some(host) = remote_host {
+            let paths = repo_index.find_repositories_by_remote_host(host);
+            if is_first_filter {
+                candidate_paths.extend(paths);
+                is_first_filter = false;
+            } else {
+                candidate_paths.retain(|path| paths.contains(path));
+            }
+        }
+        
+        if let Some(owner) = remote_owner {
+            let paths = repo_index.find_repositories_by_remote_owner(owner);
             if is_first_filter {
                 candidate_paths.extend(paths);
                 is_first_filter = false;
@@ -554,68 +967,78 @@ impl DataStore {
         Ok(results)
     }
     
-    /// Override add_repository to update optimizations
-    pub fn add_repository_optimized(&mut self, repo: GitRepository) -> Result<(), String> {
-        // Update disk cache
-        let mut cache = self.load_cache()?;
-        cache.repositories.insert(repo.path.clone(), repo.clone());
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)?;
-        
-        // Update optimizations
-        self.path_trie.insert_repository(&repo.path);
-        self.repo_index.insert_repository(&repo);
-        
-        // Update LRU cache
-        if let Ok(mut lru) = self.lru_cache.lock() {
-            lru.put(repo.path.clone(), repo);
-        }
-        
-        Ok(())
-    }
-    
-    /// Override remove repository to update optimizations
-    pub fn remove_repository_optimized(&mut self, repo_path: &str) -> Result<(), String> {
-        // Get repository before removing for optimization cleanup
-        let cache = self.load_cache()?;
-        let repo = cache.repositories.get(repo_path).cloned();
-        
-        // Update disk cache
-        let mut cache = cache;
-        cache.repositories.remove(repo_path);
-        cache.last_updated = Utc::now();
-        self.save_cache(&cache)?;
-        
-        // Update optimizations
-        self.path_trie.remove_repository(repo_path);
-        if let Some(repo) = repo {
-            self.repo_index.remove_repository(&repo);
-        }
-        
-        // Update LRU cache
-        if let Ok(mut lru) = self.lru_cache.lock() {
-            lru.pop(repo_path);
-        }
-        
-        Ok(())
-    }
-    
     /// Get cache statistics including optimization info
     pub fn get_optimization_stats(&self) -> Result<serde_json::Value, String> {
-        let cache = self.load_cache()?;
-        let lru_size = if let Ok(lru) = self.lru_cache.lock() {
-            lru.len()
-        } else {
-            0
-        };
+        let memory = self.memory()?;
+        let repo_index = memory.repo_index();
         
         Ok(serde_json::json!({
-            "total_repositories": cache.repositories.len(),
-            "lru_cache_size": lru_size,
-            "lru_cache_capacity": 1000,
-            "index_name_entries": self.repo_index.by_name.len(),
-            "index_size_ranges": self.repo_index.by_size_range.len(),
-            "index_file_types": self.repo_index.by_file_type.len()
+            "total_repositories": memory.cache().repositories.len(),
+            "index_name_entries": repo_index.by_name.len(),
+            "index_size_ranges": repo_index.by_size_range.len(),
+            "index_file_types": repo_index.by_file_type.len(),
+            "index_remote_hosts": repo_index.by_remote_host.len(),
+            "index_remote_owners": repo_index.by_remote_owner.len()
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_corrupt_cache_falls_back_to_newest_readable_backup() {
+        let dir = temp_dir("data-store");
+        let backup_dir = dir.join("backups");
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(dir.join("repositories_cache.json"), "{ \"repositories\": ").unwrap();
+
+        let mut backup = RepositoryCache::default();
+        backup.repositories.insert("/src/app".to_string(), GitRepository::fixture("/src/app"));
+        fs::write(backup_dir.join("repositories_cache-20240501-100000-000.json"), serde_json::to_string(&backup).unwrap()).unwrap();
+        fs::write(backup_dir.join("repositories_cache-20240502-100000-000.json"), "truncated").unwrap();
+
+        let store = DataStore::open(&dir);
+        assert!(matches!(
+            store.load_state().unwrap(),
+            CacheLoadState::RestoredFromBackup { backup, .. } if backup == "repositories_cache-20240501-100000-000.json"
+        ));
+        assert!(store.get_repository_fast("/src/app").unwrap().is_some());
+        // The broken file is kept, and the restored cache is written in its place
+        assert_eq!(fs::read_to_string(dir.join("repositories_cache.json.broken")).unwrap(), "{ \"repositories\": ");
+        assert!(parse_cache(&fs::read_to_string(dir.join("repositories_cache.json")).unwrap()).is_ok());
+
+        // Without a readable backup the app starts from an empty cache
+        fs::remove_dir_all(&backup_dir).unwrap();
+        fs::write(dir.join("repositories_cache.json"), "not json").unwrap();
+        let store = DataStore::open(&dir);
+        assert!(matches!(store.load_state().unwrap(), CacheLoadState::Empty { .. }));
+        assert!(store.load_cache().unwrap().repositories.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_newer_cache_is_opened_read_only_and_never_written() {
+        let dir = temp_dir("data-store");
+        let newer = r#"{ "cache_version": "99.0", "repositories": {}, "future_field": true }"#;
+        fs::write(dir.join("repositories_cache.json"), newer).unwrap();
+        let backup_dir = dir.join("backups");
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("repositories_cache-20240501-100000-000.json"), serde_json::to_string(&RepositoryCache::default()).unwrap()).unwrap();
+
+        let store = Arc::new(DataStore::open(&dir));
+        store.start_write_behind();
+        assert!(matches!(store.load_state().unwrap(), CacheLoadState::NewerVersion { .. }));
+
+        store.add_repository(GitRepository::fixture("/src/app")).unwrap();
+        store.flush().unwrap();
+        assert!(store.restore_cache_backup("repositories_cache-20240501-100000-000.json").is_err());
+        assert!(store.migrate_to_sqlite().is_err());
+        assert_eq!(fs::read_to_string(dir.join("repositories_cache.json")).unwrap(), newer);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use crate::build_artifacts;
+use crate::cancellation::CancellationToken;
+use crate::repo_types::{DiskUsage, DiskUsageFailure, WorkspaceDiskUsage};
+use crate::worker_pool;
+use git2::{Repository, Status, StatusOptions};
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Clone, Copy)]
+enum Category {
+    WorkingTree,
+    GitObjects,
+    GitOther,
+    Lfs,
+    BuildArtifacts,
+    Ignored,
+}
+
+// Device and inode of a file with more than one link
+type FileId = (u64, u64);
+
+/// A repository's usage plus the files in it that have further hard links, so a workspace
+/// report can also count files shared between repositories only once
+struct Analysis {
+    usage: DiskUsage,
+    linked: HashMap<FileId, (u64, Category)>,
+}
+
+/// Break down the disk usage of one repository. Sizes are allocated blocks rather than file
+/// lengths, hard-linked files count once, and symlinks are never followed. Repositories
+/// nested in the working tree are left out; they are analyzed on their own. Cancelling
+/// stops the walk and returns an error.
+pub fn analyze_repository(repo_path: &Path, cancel: &CancellationToken) -> Result<DiskUsage, String> {
+    analyze(repo_path, cancel).map(|analysis| analysis.usage)
+}
+
+/// Analyze many repositories, `concurrency` at a time, and report totals and the `top_n`
+/// largest. Files hard-linked between repositories (e.g. from a pnpm store) count once in
+/// the totals. Cancelling skips the remaining repositories and reports what finished.
+pub fn analyze_workspace(
+    repo_paths: Vec<String>,
+    top_n: usize,
+    concurrency: usize,
+    cancel: &CancellationToken,
+    mut on_result: impl FnMut(&DiskUsage),
+) -> WorkspaceDiskUsage {
+    let mut report = WorkspaceDiskUsage::default();
+    let mut seen: HashSet<FileId> = HashSet::new();
+    let mut repositories = Vec::new();
+
+    worker_pool::run_bounded(
+        concurrency,
+        cancel,
+        repo_paths,
+        |repo_path| {
+            let analysis = analyze(Path::new(&repo_path), cancel);
+            (repo_path, analysis)
+        },
+        |(repo_path, analysis)| match analysis {
+            Ok(Analysis { usage, linked }) => {
+                on_result(&usage);
+                add(&mut report.totals, &usage);
+                for (id, (bytes, category)) in linked {
+                    if !seen.insert(id) {
+                        *category_bytes(&mut report.totals, category) -= bytes;
+                        report.totals.total_bytes -= bytes;
+                        report.totals.hardlinked_bytes += bytes;
+                    }
+                }
+                repositories.push(usage);
+            }
+            // Repositories interrupted by cancellation are neither results nor failures
+            Err(_) if cancel.is_cancelled() => {}
+            Err(error) => report.failures.push(DiskUsageFailure { repo_path, error }),
+        },
+    );
+
+    report.repositories_analyzed = repositories.len();
+    report.cancelled = cancel.is_cancelled();
+    repositories.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes).then_with(|| a.repo_path.cmp(&b.repo_path)));
+    repositories.truncate(top_n);
+    report.top_repositories = repositories;
+    report.failures.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    report
+}
+
+/// Allocated size of everything under `path`, counted like `analyze_repository` does
+pub fn allocated_size(path: &Path) -> u64 {
+    let mut counter = Counter::default();
+    counter.walk(path, |_| Category::WorkingTree);
+    counter.usage.total_bytes
+}
+
+fn analyze(repo_path: &Path, cancel: &CancellationToken) -> Result<Analysis, String> {
+    let repo = Repository::open(repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    let mut counter = Counter { cancel: cancel.clone(), ..Counter::default() };
+
+    let git_dir = repo.path().to_path_buf();
+    counter.walk(&git_dir, |path| {
+        if path.starts_with(git_dir.join("objects")) {
+            Category::GitObjects
+        } else if path.starts_with(git_dir.join("lfs")) {
+            Category::Lfs
+        } else {
+            Category::GitOther
+        }
+    });
+
+    if let Some(workdir) = repo.workdir().map(Path::to_path_buf) {
+        let artifacts: HashSet<PathBuf> = build_artifacts::detect(&workdir)?
+            .artifacts
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        let ignored = ignored_paths(&repo, &workdir)?;
+        counter.walk_working_tree(&workdir, &git_dir, &artifacts, &ignored);
+    }
+    if cancel.is_cancelled() {
+        return Err("Disk usage analysis was cancelled".to_string());
+    }
+
+    let mut usage = counter.usage;
+    usage.repo_path = repo_path.to_string_lossy().to_string();
+    Ok(Analysis { usage, linked: counter.linked })
+}
+
+// Ignored files and directories (as one entry each), as absolute paths
+fn ignored_paths(repo: &Repository, workdir: &Path) -> Result<HashSet<PathBuf>, String> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(false)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false)
+        .exclude_submodules(true);
+    let statuses = repo.statuses(Some(&mut options))
+        .map_err(|e| format!("Failed to read status: {}", e))?;
+
+    Ok(statuses.iter()
+        .filter(|entry| entry.status().contains(Status::IGNORED))
+        .filter_map(|entry| entry.path().map(|path| workdir.join(path.trim_end_matches('/'))))
+        .collect())
+}
+
+#[derive(Default)]
+struct Counter {
+    usage: DiskUsage,
+    linked: HashMap<FileId, (u64, Category)>,
+    cancel: CancellationToken,
+}
+
+impl Counter {
+    fn walk(&mut self, root: &Path, category: impl Fn(&Path) -> Category) {
+        for entry in WalkDir::new(root).into_iter().filter_map(|entry| entry.ok()) {
+            if self.cancel.is_cancelled() {
+                return;
+            }
+            if entry.file_type().is_file() {
+                if let Ok(metadata) = entry.metadata() {
+                    self.count(category(entry.path()), &metadata);
+                }
+            }
+        }
+    }
+
+    fn walk_working_tree(&mut self, workdir: &Path, git_dir: &Path, artifacts: &HashSet<PathBuf>, ignored: &HashSet<PathBuf>) {
+        let mut entries = WalkDir::new(workdir).into_iter();
+        while let Some(entry) = entries.next() {
+            if self.cancel.is_cancelled() {
+                return;
+            }
+            let Ok(entry) = entry else { continue };
+            let path = entry.path();
+
+            if entry.file_type().is_dir() && path != workdir {
+                // The git directory was counted already; nested repositories count on their own
+                if path == git_dir || path.join(".git").exists() {
+                    entries.skip_current_dir();
+                    continue;
+                }
+                if artifacts.contains(path) {
+                    self.walk(path, |_| Category::BuildArtifacts);
+                    entries.skip_current_dir();
+                    continue;
+                }
+                if ignored.contains(path) {
+                    self.walk(path, |_| Category::Ignored);
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+
+            // A linked worktree's .git is a file pointing at its git directory
+            if entry.file_type().is_file() && path.parent() == Some(workdir) && entry.file_name() == ".git" {
+                continue;
+            }
+            if entry.file_type().is_file() {
+                if let Ok(metadata) = entry.metadata() {
+                    let category = if ignored.contains(path) { Category::Ignored } else { Category::WorkingTree };
+                    self.count(category, &metadata);
+                }
+            }
+        }
+    }
+
+    fn count(&mut self, category: Category, metadata: &Metadata) {
+        let bytes = allocated_bytes(metadata);
+        if let Some(id) = linked_file_id(metadata) {
+            if self.linked.contains_key(&id) {
+                self.usage.hardlinked_bytes += bytes;
+                return;
+            }
+            self.linked.insert(id, (bytes, category));
+        }
+        *category_bytes(&mut self.usage, category) += bytes;
+        self.usage.total_bytes += bytes;
+        self.usage.file_count += 1;
+    }
+}
+
+fn category_bytes(usage: &mut DiskUsage, category: Category) -> &mut u64 {
+    match category {
+        Category::WorkingTree => &mut usage.working_tree_bytes,
+        Category::GitObjects => &mut usage.git_objects_bytes,
+        Category::GitOther => &mut usage.git_other_bytes,
+        Category::Lfs => &mut usage.lfs_bytes,
+        Category::BuildArtifacts => &mut usage.build_artifacts_bytes,
+        Category::Ignored => &mut usage.ignored_bytes,
+    }
+}
+
+fn add(totals: &mut DiskUsage, usage: &DiskUsage) {
+    totals.working_tree_bytes += usage.working_tree_bytes;
+    totals.git_objects_bytes += usage.git_objects_bytes;
+    totals.git_other_bytes += usage.git_other_bytes;
+    totals.lfs_bytes += usage.lfs_bytes;
+    totals.build_artifacts_bytes += usage.build_artifacts_bytes;
+    totals.ignored_bytes += usage.ignored_bytes;
+    totals.total_bytes += usage.total_bytes;
+    totals.hardlinked_bytes += usage.hardlinked_bytes;
+    totals.file_count += usage.file_count;
+}
+
+#[cfg(unix)]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // st_blocks is always in 512-byte units, whatever the filesystem block size
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+fn linked_file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn linked_file_id(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(root: &Path, relative: &str, size: usize) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; size]).unwrap();
+    }
+
+    #[test]
+    fn test_breakdown_dedupes_hardlinks_and_ignores_symlink_cycles() {
+        let dir = std::env::temp_dir().join(format!("gitmap-du-{}", uuid::Uuid::new_v4()));
+        let (one, two) = (dir.join("one"), dir.join("two"));
+        for root in [&one, &two] {
+            Repository::init(root).unwrap();
+            write(root, ".gitignore", 10);
+            write(root, "package.json", 10);
+            write(root, "src/main.js", 64 * 1024);
+        }
+        fs::write(one.join(".gitignore"), "*.log\n").unwrap();
+        write(&one, "debug.log", 64 * 1024);
+        write(&one, "node_modules/dep/index.js", 64 * 1024);
+        // pnpm-style: the same file linked into both repositories and twice into one
+        fs::create_dir_all(two.join("node_modules/dep")).unwrap();
+        fs::hard_link(one.join("node_modules/dep/index.js"), two.join("node_modules/dep/index.js")).unwrap();
+        fs::hard_link(one.join("node_modules/dep/index.js"), one.join("node_modules/dep/copy.js")).unwrap();
+        std::os::unix::fs::symlink(&one, one.join("src/loop")).unwrap();
+
+        let usage = analyze_repository(&one, &CancellationToken::new()).unwrap();
+        assert!(usage.working_tree_bytes >= 64 * 1024);
+        assert!(usage.ignored_bytes >= 64 * 1024);
+        assert!(usage.build_artifacts_bytes >= 64 * 1024);
+        assert!(usage.git_other_bytes > 0);
+        assert_eq!(usage.hardlinked_bytes, usage.build_artifacts_bytes);
+
+        let paths = vec![one.to_string_lossy().to_string(), two.to_string_lossy().to_string()];
+        let workspace = analyze_workspace(paths.clone(), 1, 2, &CancellationToken::new(), |_| {});
+        assert_eq!(workspace.repositories_analyzed, 2);
+        assert_eq!(workspace.top_repositories.len(), 1);
+        assert_eq!(workspace.top_repositories[0].repo_path, one.to_string_lossy());
+        // The file shared with the second repository only counts once
+        assert_eq!(workspace.totals.build_artifacts_bytes, usage.build_artifacts_bytes);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(analyze_repository(&one, &cancel).is_err());
+        let cancelled = analyze_workspace(paths, 1, 2, &cancel, |_| {});
+        assert!(cancelled.cancelled);
+        assert_eq!(cancelled.repositories_analyzed, 0);
+        assert!(cancelled.failures.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
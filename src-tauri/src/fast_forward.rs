@@ -0,0 +1,166 @@
+use crate::cancellation::CancellationToken;
+use crate::repo_types::{FastForwardFailure, FastForwardReport, FastForwardSkip, FastForwardSkipReason, FastForwardUpdate};
+use crate::worker_pool;
+use git2::build::CheckoutBuilder;
+use git2::{Branch, ErrorCode, Repository, StatusOptions};
+
+enum Outcome {
+    Updated(FastForwardUpdate),
+    Skipped(FastForwardSkipReason),
+}
+
+/// Fast-forward the checked out branch of every repository to its upstream, `concurrency`
+/// repositories at a time. Only clean fast-forwards are done; anything that would need a
+/// merge, or could lose local changes, is skipped with the reason.
+pub fn fast_forward_repositories(repo_paths: Vec<String>, concurrency: usize) -> FastForwardReport {
+    let mut report = FastForwardReport::default();
+
+    worker_pool::run_bounded(
+        concurrency,
+        &CancellationToken::new(),
+        repo_paths,
+        |repo_path| {
+            let outcome = fast_forward(&repo_path);
+            (repo_path, outcome)
+        },
+        |(repo_path, outcome)| match outcome {
+            Ok(Outcome::Updated(update)) => report.updated.push(update),
+            Ok(Outcome::Skipped(reason)) => report.skipped.push(FastForwardSkip { repo_path, reason }),
+            Err(error) => report.failed.push(FastForwardFailure { repo_path, error }),
+        },
+    );
+
+    report.updated.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    report.skipped.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    report.failed.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+    report
+}
+
+fn fast_forward(repo_path: &str) -> Result<Outcome, String> {
+    let repo = Repository::open(repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    if repo.is_bare() {
+        return Ok(Outcome::Skipped(FastForwardSkipReason::Bare));
+    }
+
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(e) if e.code() == ErrorCode::UnbornBranch => return Ok(Outcome::Skipped(FastForwardSkipReason::Unborn)),
+        Err(e) => return Err(format!("Failed to read HEAD: {}", e)),
+    };
+    if !head.is_branch() {
+        return Ok(Outcome::Skipped(FastForwardSkipReason::Detached));
+    }
+
+    let branch = Branch::wrap(head);
+    let branch_name = branch.name().ok().flatten().unwrap_or_default().to_string();
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(Outcome::Skipped(FastForwardSkipReason::NoUpstream)),
+        Err(e) => return Err(format!("Failed to read upstream of {}: {}", branch_name, e)),
+    };
+    let upstream_name = upstream.name().ok().flatten().unwrap_or_default().to_string();
+
+    if is_dirty(&repo)? {
+        return Ok(Outcome::Skipped(FastForwardSkipReason::Dirty));
+    }
+
+    let upstream_commit = repo.reference_to_annotated_commit(upstream.get())
+        .map_err(|e| format!("Failed to resolve {}: {}", upstream_name, e))?;
+    let (analysis, _) = repo.merge_analysis(&[&upstream_commit])
+        .map_err(|e| format!("Failed to compare with {}: {}", upstream_name, e))?;
+
+    if analysis.is_up_to_date() {
+        return Ok(Outcome::Skipped(FastForwardSkipReason::UpToDate));
+    }
+    if !analysis.is_fast_forward() {
+        return Ok(Outcome::Skipped(FastForwardSkipReason::Diverged));
+    }
+
+    let from = branch.get().target().ok_or("Branch does not point to a commit")?;
+    let to = upstream_commit.id();
+    let target = repo.find_object(to, None)
+        .map_err(|e| format!("Failed to read {}: {}", to, e))?;
+
+    // Safe checkout refuses to overwrite anything changed since the status check; the branch
+    // only moves once the working tree was updated
+    repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| format!("Failed to update working tree: {}", e))?;
+    let mut reference = branch.into_reference();
+    reference.set_target(to, &format!("gitmap: fast-forward to {}", upstream_name))
+        .map_err(|e| format!("Failed to move {}: {}", branch_name, e))?;
+
+    let (commits, _) = repo.graph_ahead_behind(to, from).unwrap_or((0, 0));
+    Ok(Outcome::Updated(FastForwardUpdate {
+        repo_path: repo_path.to_string(),
+        branch: branch_name,
+        upstream: upstream_name,
+        from: from.to_string(),
+        to: to.to_string(),
+        commits,
+    }))
+}
+
+// Untracked files do not count: a safe checkout fails rather than overwrite them
+fn is_dirty(repo: &Repository) -> Result<bool, String> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(false)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    let statuses = repo.statuses(Some(&mut options))
+        .map_err(|e| format!("Failed to read status: {}", e))?;
+    Ok(!statuses.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+    use std::fs;
+
+    fn fetch(repo: &Repository) {
+        repo.find_remote("origin").unwrap().fetch(&[] as &[&str], None, None).unwrap();
+    }
+
+    #[test]
+    fn test_only_clean_fast_forwards_are_applied() {
+        let dir = temp_dir("ff");
+        let upstream = Repository::init(dir.join("upstream")).unwrap();
+        commit_file(&upstream, "a.txt", "one");
+        let upstream_url = dir.join("upstream").to_string_lossy().to_string();
+
+        let clean = Repository::clone(&upstream_url, dir.join("clean")).unwrap();
+        let dirty = Repository::clone(&upstream_url, dir.join("dirty")).unwrap();
+        let diverged = Repository::clone(&upstream_url, dir.join("diverged")).unwrap();
+        Repository::init(dir.join("local-only")).unwrap();
+
+        commit_file(&upstream, "a.txt", "two");
+        fs::write(dir.join("dirty/a.txt"), "local edit").unwrap();
+        commit_file(&diverged, "b.txt", "local commit");
+        for repo in [&clean, &dirty, &diverged] {
+            fetch(repo);
+        }
+
+        let paths = ["clean", "dirty", "diverged", "local-only"]
+            .iter()
+            .map(|name| dir.join(name).to_string_lossy().to_string())
+            .collect();
+        let report = fast_forward_repositories(paths, 2);
+
+        assert!(report.failed.is_empty());
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.updated[0].commits, 1);
+        assert_eq!(fs::read_to_string(dir.join("clean/a.txt")).unwrap(), "two");
+
+        let reasons: Vec<_> = report.skipped.iter().map(|skip| skip.reason).collect();
+        assert_eq!(reasons, vec![
+            FastForwardSkipReason::Dirty,
+            FastForwardSkipReason::Diverged,
+            FastForwardSkipReason::Unborn,
+        ]);
+        assert_eq!(fs::read_to_string(dir.join("dirty/a.txt")).unwrap(), "local edit");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,228 @@
+use crate::cancellation::CancellationToken;
+use crate::git_auth;
+use crate::repo_types::{FetchProgress, FetchResult, FetchStatus};
+use crate::worker_pool;
+use git2::{FetchOptions, Repository};
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Fetching waits on the network rather than the CPU, so run more at once than there are cores
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 8;
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Transfer progress is reported at most this often per remote
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// libgit2 otherwise waits on a stalled server forever. With these limits a fetch thread that
+// was abandoned after the overall timeout still finishes on its own.
+const SERVER_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const SERVER_TIMEOUT: Duration = Duration::from_secs(60); // Longest wait for a single read or write
+
+// git_libgit2_opt_t values from libgit2 1.7, which git2 0.18 has no wrappers for
+const GIT_OPT_SET_SERVER_CONNECT_TIMEOUT: c_int = 39;
+const GIT_OPT_SET_SERVER_TIMEOUT: c_int = 41;
+
+enum FetchMessage {
+    Progress(FetchProgress),
+    Fetched(String), // Remote name
+    Failed(String),  // Error, prefixed with the remote name
+    Done,
+}
+
+/// Fetch all remotes of every repository, `concurrency` repositories at a time.
+/// Each repository gets `timeout` for all of its remotes. Results are in input order.
+pub fn fetch_repositories(
+    repo_paths: Vec<String>,
+    concurrency: usize,
+    timeout: Duration,
+    on_progress: impl Fn(FetchProgress) + Sync,
+    mut on_result: impl FnMut(&FetchResult),
+) -> Vec<FetchResult> {
+    set_server_timeouts();
+    let mut results: Vec<Option<FetchResult>> = vec![None; repo_paths.len()];
+
+    worker_pool::run_bounded(
+        concurrency,
+        &CancellationToken::new(),
+        repo_paths.into_iter().enumerate().collect(),
+        |(index, repo_path)| (index, fetch_with_timeout(repo_path, timeout, &on_progress)),
+        |(index, result)| {
+            on_result(&result);
+            results[index] = Some(result);
+        },
+    );
+
+    results.into_iter().flatten().collect()
+}
+
+fn set_server_timeouts() {
+    static SET: Once = Once::new();
+    SET.call_once(|| {
+        libgit2_sys::init();
+        for (option, timeout) in [
+            (GIT_OPT_SET_SERVER_CONNECT_TIMEOUT, SERVER_CONNECT_TIMEOUT),
+            (GIT_OPT_SET_SERVER_TIMEOUT, SERVER_TIMEOUT),
+        ] {
+            // Both options take the timeout in milliseconds as a single int
+            let status = unsafe { libgit2_sys::git_libgit2_opts(option, timeout.as_millis() as c_int) };
+            if status < 0 {
+                eprintln!("libgit2 does not support server timeouts; stalled fetches keep running in the background");
+            }
+        }
+    });
+}
+
+// libgit2 has no overall timeout, so the fetch runs on its own thread and is abandoned when
+// it takes too long. Its callbacks then abort the transfer as soon as they are called again,
+// and the server timeouts end it when the server stops responding. Remotes are reported as
+// they finish, so those fetched before the timeout are still in the result.
+fn fetch_with_timeout(repo_path: String, timeout: Duration, on_progress: &impl Fn(FetchProgress)) -> FetchResult {
+    let started = Instant::now();
+    let abort = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    {
+        let repo_path = repo_path.clone();
+        let abort = abort.clone();
+        thread::spawn(move || {
+            fetch_all_remotes(&repo_path, &abort, &|message| {
+                let _ = sender.send(message);
+            });
+            let _ = sender.send(FetchMessage::Done);
+        });
+    }
+
+    let mut fetched_remotes = Vec::new();
+    let mut errors = Vec::new();
+    let deadline = started + timeout;
+    let status = loop {
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(FetchMessage::Progress(progress)) => on_progress(progress),
+            Ok(FetchMessage::Fetched(remote)) => fetched_remotes.push(remote),
+            Ok(FetchMessage::Failed(error)) => errors.push(error),
+            Ok(FetchMessage::Done) => {
+                break if errors.is_empty() { FetchStatus::Fetched } else { FetchStatus::Failed };
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                abort.store(true, Ordering::SeqCst);
+                errors.push(format!("Timed out after {}s", timeout.as_secs()));
+                break FetchStatus::TimedOut;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                errors.push("Fetch stopped unexpectedly".to_string());
+                break FetchStatus::Failed;
+            }
+        }
+    };
+
+    FetchResult {
+        repo_path,
+        status,
+        fetched_remotes,
+        errors,
+        duration_ms: started.elapsed().as_millis(),
+    }
+}
+
+fn fetch_all_remotes(repo_path: &str, abort: &AtomicBool, send: &dyn Fn(FetchMessage)) {
+    let repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => return send(FetchMessage::Failed(format!("Failed to open repository: {}", e))),
+    };
+    let remote_names = match repo.remotes() {
+        Ok(names) => names.iter().flatten().map(str::to_string).collect::<Vec<_>>(),
+        Err(e) => return send(FetchMessage::Failed(format!("Failed to list remotes: {}", e))),
+    };
+
+    for name in remote_names {
+        if abort.load(Ordering::SeqCst) {
+            break;
+        }
+        match fetch_remote(&repo, repo_path, &name, abort, &|progress| send(FetchMessage::Progress(progress))) {
+            Ok(()) => send(FetchMessage::Fetched(name)),
+            Err(e) => send(FetchMessage::Failed(format!("{}: {}", name, e))),
+        }
+    }
+}
+
+fn fetch_remote(
+    repo: &Repository,
+    repo_path: &str,
+    name: &str,
+    abort: &AtomicBool,
+    on_progress: &dyn Fn(FetchProgress),
+) -> Result<(), String> {
+    let mut remote = repo.find_remote(name).map_err(|e| e.message().to_string())?;
+
+    let mut last_report = Instant::now();
+    let mut callbacks = git_auth::remote_callbacks();
+    callbacks.transfer_progress(|stats| {
+        let done = stats.received_objects() == stats.total_objects();
+        if done || last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            on_progress(FetchProgress {
+                repo_path: repo_path.to_string(),
+                remote: name.to_string(),
+                received_objects: stats.received_objects(),
+                total_objects: stats.total_objects(),
+                received_bytes: stats.received_bytes(),
+            });
+        }
+        !abort.load(Ordering::SeqCst)
+    });
+    callbacks.sideband_progress(|_| !abort.load(Ordering::SeqCst));
+
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+
+    // No refspecs: use the ones configured for the remote, like `git fetch <remote>`
+    remote.fetch(&[] as &[&str], Some(&mut options), None)
+        .map_err(|e| e.message().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+    use std::fs;
+    use std::path::Path;
+
+    fn file_url(path: &Path) -> String {
+        format!("file://{}", path.display())
+    }
+
+    #[test]
+    fn test_fetches_new_commits_and_reports_broken_remotes() {
+        let dir = temp_dir("fetch");
+        let upstream = Repository::init(dir.join("upstream")).unwrap();
+        commit_file(&upstream, "file.txt", "first");
+
+        let local_path = dir.join("local");
+        let local = Repository::clone(&file_url(&dir.join("upstream")), &local_path).unwrap();
+        local.remote("broken", &file_url(&dir.join("missing"))).unwrap();
+        let new_head = commit_file(&upstream, "file.txt", "second");
+
+        let mut finished = 0;
+        let results = fetch_repositories(
+            vec![local_path.to_string_lossy().to_string()],
+            2,
+            DEFAULT_FETCH_TIMEOUT,
+            |_| {},
+            |_| finished += 1,
+        );
+
+        assert_eq!(finished, 1);
+        assert_eq!(results[0].status, FetchStatus::Failed);
+        assert_eq!(results[0].fetched_remotes, vec!["origin"]);
+        assert!(results[0].errors[0].starts_with("broken: "));
+
+        let branch = upstream.head().unwrap().shorthand().unwrap().to_string();
+        let tracking = local.find_reference(&format!("refs/remotes/origin/{}", branch)).unwrap();
+        assert_eq!(tracking.target(), Some(new_head));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// Advisory lock on a lock file, shared between app instances. Released when dropped.
+///
+/// The cache file itself cannot be locked because atomic writes replace it with a new file,
+/// so every instance locks the same sidecar file instead.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Block until this is the only holder of the lock
+    pub fn exclusive(path: &Path) -> Result<Self, String> {
+        let file = open(path)?;
+        file.lock()
+            .map_err(|e| format!("Failed to lock {:?} for writing: {}", path, e))?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Failed to open lock file {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock_blocks_others_until_dropped() {
+        let path = std::env::temp_dir().join(format!("gitmap-lock-{}.lock", uuid::Uuid::new_v4()));
+
+        let writer = FileLock::exclusive(&path).unwrap();
+        assert!(open(&path).unwrap().try_lock_shared().is_err());
+        drop(writer);
+        assert!(open(&path).unwrap().try_lock().is_ok());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
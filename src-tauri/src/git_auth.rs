@@ -0,0 +1,32 @@
+use git2::{Cred, CredentialType, RemoteCallbacks};
+
+// libgit2 keeps asking for credentials while they are rejected, so give up after a few tries
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// Callbacks for network operations that authenticate like the git command line does:
+/// SSH keys from the agent and HTTPS credentials from the configured credential helper.
+pub fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+
+    callbacks.credentials(move |url, username, allowed| {
+        attempts += 1;
+        if attempts > MAX_CREDENTIAL_ATTEMPTS {
+            return Err(git2::Error::from_str(&format!("Authentication failed for {}", url)));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            let config = git2::Config::open_default()?;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+        Cred::default()
+    });
+
+    callbacks
+}
@@ -0,0 +1,236 @@
+use crate::bulk_exec;
+use crate::cancellation::CancellationToken;
+use crate::disk_usage;
+use crate::repo_types::{
+    CommandOutput, CommandResult, CommandStatus, GitHealth, MaintenancePhase, MaintenanceProgress, MaintenanceResult,
+    MaintenanceSummary, MaintenanceTask,
+};
+use crate::worker_pool;
+use git2::Repository;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Thresholds at which `git gc --auto` would start repacking (gc.auto and gc.autoPackLimit)
+const LOOSE_OBJECTS_LIMIT: u64 = 6700;
+const PACK_LIMIT: usize = 50;
+
+// Repacking is heavy on memory and disk, so only a couple of repositories at a time
+pub const DEFAULT_MAINTENANCE_CONCURRENCY: usize = 2;
+
+/// Inspect the object database of a repository. Linked worktrees report on the git
+/// directory they share with their main repository.
+pub fn analyze(repo_path: &Path) -> Result<GitHealth, String> {
+    let git_dir = common_dir(repo_path)?;
+    let objects_dir = git_dir.join("objects");
+
+    let mut loose_objects = 0;
+    let mut loose_objects_bytes = 0;
+    for entry in fs::read_dir(&objects_dir).into_iter().flatten().flatten() {
+        // Loose objects live in directories named after the first two hex digits of their id
+        let name = entry.file_name();
+        let is_fanout = name.len() == 2 && name.to_string_lossy().chars().all(|c| c.is_ascii_hexdigit());
+        if !is_fanout || !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        loose_objects += fs::read_dir(entry.path()).into_iter().flatten().flatten().count() as u64;
+        loose_objects_bytes += disk_usage::allocated_size(&entry.path());
+    }
+
+    let pack_dir = objects_dir.join("pack");
+    let pack_count = fs::read_dir(&pack_dir).into_iter().flatten().flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "pack"))
+        .count();
+
+    let info_dir = objects_dir.join("info");
+    let has_commit_graph = info_dir.join("commit-graph").is_file()
+        || info_dir.join("commit-graphs").join("commit-graph-chain").is_file();
+    let gc_log = Some(git_dir.join("gc.log")).filter(|path| path.is_file());
+
+    let needs_maintenance = loose_objects > LOOSE_OBJECTS_LIMIT
+        || pack_count > PACK_LIMIT
+        || gc_log.is_some()
+        || (!has_commit_graph && (loose_objects > 0 || pack_count > 0));
+
+    Ok(GitHealth {
+        repo_path: repo_path.to_string_lossy().to_string(),
+        git_dir_bytes: disk_usage::allocated_size(&git_dir),
+        loose_objects,
+        loose_objects_bytes,
+        pack_count,
+        packs_bytes: disk_usage::allocated_size(&pack_dir),
+        reflog_bytes: disk_usage::allocated_size(&git_dir.join("logs")),
+        gc_log: gc_log.map(|path| path.to_string_lossy().to_string()),
+        has_commit_graph,
+        needs_maintenance,
+    })
+}
+
+/// Run `task` on every repository, `concurrency` at a time, streaming git's output to
+/// `on_output` and each step a repository enters to `on_progress`. Repositories sharing an
+/// object database (worktrees) are only maintained once; the others are reported as skipped.
+/// Cancelling stops running git processes and skips the rest.
+#[allow(clippy::too_many_arguments)]
+pub fn run_maintenance(
+    run_id: &str,
+    repo_paths: Vec<String>,
+    task: MaintenanceTask,
+    concurrency: usize,
+    cancel: &CancellationToken,
+    on_progress: impl Fn(MaintenanceProgress) + Sync,
+    on_output: impl Fn(CommandOutput) + Sync,
+    mut on_result: impl FnMut(&MaintenanceResult),
+) -> MaintenanceSummary {
+    let not_run = |repo_path: String, status, error, maintained_with| MaintenanceResult {
+        command: CommandResult {
+            run_id: run_id.to_string(),
+            repo_path,
+            status,
+            exit_code: None,
+            duration_ms: 0,
+            error,
+        },
+        before_bytes: 0,
+        after_bytes: 0,
+        maintained_with,
+    };
+
+    let mut maintained_in: HashMap<PathBuf, String> = HashMap::new();
+    let mut results: Vec<Option<MaintenanceResult>> = Vec::new();
+    let mut targets = Vec::new();
+    for repo_path in repo_paths {
+        let result = match common_dir(Path::new(&repo_path)) {
+            Ok(git_dir) => match maintained_in.get(&git_dir) {
+                Some(maintained) => not_run(repo_path, CommandStatus::Skipped, None, Some(maintained.clone())),
+                None => {
+                    maintained_in.insert(git_dir.clone(), repo_path.clone());
+                    targets.push((results.len(), repo_path, git_dir));
+                    results.push(None);
+                    continue;
+                }
+            },
+            Err(error) => not_run(repo_path, CommandStatus::Error, Some(error), None),
+        };
+        on_result(&result);
+        results.push(Some(result));
+    }
+
+    let command_line = command_line(task);
+    let progress = |repo_path: &str, phase| on_progress(MaintenanceProgress {
+        run_id: run_id.to_string(),
+        repo_path: repo_path.to_string(),
+        phase,
+    });
+    worker_pool::run_bounded(
+        concurrency,
+        cancel,
+        targets.clone(),
+        |(index, repo_path, git_dir)| {
+            progress(&repo_path, MaintenancePhase::Measuring);
+            let before_bytes = disk_usage::allocated_size(&git_dir);
+            progress(&repo_path, MaintenancePhase::Running);
+            let command = bulk_exec::run_one(run_id, &repo_path, command_line, cancel, &on_output);
+            progress(&repo_path, MaintenancePhase::MeasuringAfter);
+            let after_bytes = disk_usage::allocated_size(&git_dir);
+            (index, MaintenanceResult { command, before_bytes, after_bytes, maintained_with: None })
+        },
+        |(index, result)| {
+            on_result(&result);
+            results[index] = Some(result);
+        },
+    );
+
+    // Repositories the pool dropped after cancellation never ran
+    for (index, repo_path, _) in targets {
+        if results[index].is_none() {
+            let result = not_run(repo_path, CommandStatus::Cancelled, None, None);
+            on_result(&result);
+            results[index] = Some(result);
+        }
+    }
+
+    let results: Vec<MaintenanceResult> = results.into_iter().flatten().collect();
+    MaintenanceSummary {
+        run_id: run_id.to_string(),
+        task,
+        freed_bytes: results.iter().map(|result| result.before_bytes.saturating_sub(result.after_bytes)).sum(),
+        results,
+    }
+}
+
+fn command_line(task: MaintenanceTask) -> &'static str {
+    match task {
+        MaintenanceTask::Gc => "git gc",
+        MaintenanceTask::Incremental => {
+            "git maintenance run --task=commit-graph --task=loose-objects --task=incremental-repack"
+        }
+    }
+}
+
+// The git directory holding the objects, shared by a repository and its worktrees
+fn common_dir(repo_path: &Path) -> Result<PathBuf, String> {
+    let repo = Repository::open(repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    let git_dir = repo.path();
+    // A worktree's git directory names the shared one in its `commondir` file
+    let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    };
+    common_dir.canonicalize()
+        .map_err(|e| format!("Failed to resolve git directory {}: {}", common_dir.display(), e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_gc_packs_loose_objects_and_reports_sizes() {
+        let dir = temp_dir("maintenance");
+        let repo = Repository::init(&dir).unwrap();
+        for i in 0..5 {
+            commit_file(&repo, "file.txt", &format!("version {}\n", i).repeat(1000));
+        }
+        let worktree = dir.with_extension("worktree");
+        repo.worktree("linked", &worktree, None).unwrap();
+
+        let before = analyze(&dir).unwrap();
+        assert_eq!(before.loose_objects, 15);
+        assert_eq!(before.pack_count, 0);
+        assert!(before.needs_maintenance);
+
+        let repo_path = dir.to_string_lossy().to_string();
+        let paths = vec![repo_path.clone(), worktree.to_string_lossy().to_string()];
+        let phases = Mutex::new(Vec::new());
+        let summary = run_maintenance(
+            "run",
+            paths,
+            MaintenanceTask::Gc,
+            1,
+            &CancellationToken::new(),
+            |progress| phases.lock().unwrap().push(progress.phase),
+            |_| {},
+            |_| {},
+        );
+        assert_eq!(
+            phases.into_inner().unwrap(),
+            [MaintenancePhase::Measuring, MaintenancePhase::Running, MaintenancePhase::MeasuringAfter]
+        );
+        assert_eq!(summary.results.len(), 2);
+        assert_eq!(summary.results[0].command.status, CommandStatus::Succeeded);
+        assert!(summary.results[0].after_bytes < summary.results[0].before_bytes);
+        // The worktree shares the object database that was just maintained
+        assert_eq!(summary.results[1].command.status, CommandStatus::Skipped);
+        assert_eq!(summary.results[1].maintained_with.as_deref(), Some(repo_path.as_str()));
+
+        let after = analyze(&dir).unwrap();
+        assert_eq!(after.loose_objects, 0);
+        assert_eq!(after.pack_count, 1);
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(worktree).unwrap();
+    }
+}
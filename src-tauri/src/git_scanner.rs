@@ -1,60 +1,121 @@
 use crate::repo_types::{
-    GitRepository, ScanProgress, NodeModulesInfo, RepositoriesDiscovered, AnalysisProgress
+    GitRepository, ScanProgress, RepositoriesDiscovered, AnalysisProgress, WorkingTreeStatus,
+    BranchInfo, ScanConfig, RepositoryKind, SubmoduleInfo, RemoteInfo, BuildArtifactsInfo
 };
 use crate::data_store::DataStore;
+use crate::build_artifacts;
+use crate::cancellation::CancellationToken;
+use crate::worker_pool;
+use crate::scan_rules::DiscoveryFilter;
+use crate::repo_kind::{self, RepositoryLayout};
+use crate::remote_url;
+use crate::workspace::{self, ImportMode, WorkspaceFile, WorkspaceImportReport};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
-use git2::Repository;
+use git2::{BranchType, Oid, Repository, Status, StatusOptions, SubmoduleIgnore, SubmoduleStatus};
 use std::collections::HashMap;
 use tauri::{Window, Emitter};
 use chrono::{DateTime, Utc};
 use std::fs;
 use std::time::Instant;
+use std::sync::Arc;
+
+// Git metadata read from the repository itself (as opposed to the working tree on disk)
+struct GitInfo {
+    current_branch: Option<String>,
+    branches: Vec<BranchInfo>,
+    remote_url: Option<String>,
+    remotes: Vec<RemoteInfo>,
+    commit_count: u32,
+    last_commit_date: Option<DateTime<Utc>>,
+    head_oid: Option<String>,
+}
 
 pub struct GitScanner {
     pub repos: Vec<GitRepository>,
-    pub data_store: DataStore,
+    pub data_store: Arc<DataStore>, // Same handle as AppState, so there is one store per process
+    cancel_token: CancellationToken, // Stops scans running on this scanner
 }
 
 impl GitScanner {
-    pub fn new() -> Result<Self, String> {
-        let data_store = DataStore::new()?;
-        Ok(Self { 
+    pub fn new(data_store: Arc<DataStore>) -> Self {
+        Self { 
             repos: Vec::new(),
             data_store,
-        })
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// A scanner sharing this one's store but not its repository list, so work can run on it
+    /// without holding the lock around this one
+    pub fn detached(&self) -> Self {
+        Self::new(self.data_store.clone())
+    }
+
+    /// Like `detached`, but scans on it are stopped by `cancel`
+    pub fn detached_with_cancel(&self, cancel: CancellationToken) -> Self {
+        Self { cancel_token: cancel, ..self.detached() }
+    }
+
+    fn emit_scan_finished(&self, window: &Window, repos_found: u32) {
+        let cancelled = self.cancel_token.is_cancelled();
+        let _ = window.emit("scan-progress", ScanProgress {
+            current_path: if cancelled { "Scan cancelled" } else { "Scan completed" }.to_string(),
+            repos_found,
+            completed: true,
+            cancelled,
+        });
+    }
+
+    /// Number of repositories analyzed in parallel, as saved in the scan config
+    pub fn get_scan_concurrency(&self) -> Result<usize, String> {
+        let config = self.data_store.get_scan_config()?;
+        Ok(config.scan_concurrency.unwrap_or_else(worker_pool::default_concurrency))
     }
 
-    pub async fn discover_repositories(&self, window: &Window, paths: Vec<String>) -> Result<Vec<String>, String> {
+    pub fn set_scan_concurrency(&self, concurrency: usize) -> Result<(), String> {
+        self.data_store.set_scan_concurrency(concurrency)
+    }
+
+    pub fn discover_repositories(&self, window: &Window, paths: Vec<String>) -> Result<Vec<String>, String> {
         let start_time = Instant::now();
         let mut discovered_paths = Vec::new();
         let mut repos_found = 0;
 
-        for path_str in paths {
+        let cache = self.data_store.load_cache().unwrap_or_default();
+
+        'roots: for path_str in paths {
             let root_path = Path::new(&path_str);
             if !root_path.exists() || !root_path.is_dir() {
                 continue;
             }
 
-            let walker = WalkDir::new(root_path).into_iter();
-            for entry in walker.filter_entry(|e| !is_hidden(e) && !is_large_dir(e)) {
-                if let Ok(entry) = entry {
-                    if entry.file_type().is_dir() && entry.path().join(".git").exists() {
-                        if let Some(path_str) = entry.path().to_str() {
-                            discovered_paths.push(path_str.to_string());
-                            repos_found += 1;
-
-                            let _ = window.emit("scan-progress", ScanProgress {
-                                current_path: path_str.to_string(),
-                                repos_found,
-                                completed: false,
-                            });
-                        }
-                    }
+            let filter = DiscoveryFilter::for_root(root_path, &cache);
+            for repo_path in filter.repositories(root_path) {
+                if self.cancel_token.is_cancelled() {
+                    break 'roots;
+                }
+
+                if let Some(path_str) = repo_path.to_str() {
+                    discovered_paths.push(path_str.to_string());
+                    repos_found += 1;
+
+                    let _ = window.emit("scan-progress", ScanProgress {
+                        current_path: path_str.to_string(),
+                        repos_found,
+                        completed: false,
+                        cancelled: false,
+                    });
                 }
             }
         }
 
+        // A cancelled discovery must not kick off the analysis phase
+        if self.cancel_token.is_cancelled() {
+            self.emit_scan_finished(window, repos_found);
+            return Ok(discovered_paths);
+        }
+
         let elapsed = start_time.elapsed().as_millis();
         let _ = window.emit("repositories-discovered", RepositoriesDiscovered {
             count: discovered_paths.len(),
@@ -65,7 +126,7 @@ impl GitScanner {
         Ok(discovered_paths)
     }
 
-    pub async fn analyze_discovered_repositories(
+    pub fn analyze_discovered_repositories(
         &mut self,
         window: &Window,
         repo_paths: Vec<String>,
@@ -73,46 +134,64 @@ impl GitScanner {
         let total = repo_paths.len();
         let mut analyzed_repos = Vec::new();
         let existing_cache = self.data_store.load_cache().unwrap_or_default();
-
-        for (i, path_str) in repo_paths.iter().enumerate() {
-            let _ = window.emit("analysis-progress", AnalysisProgress {
-                total,
-                current: i + 1,
-                current_path: path_str.clone(),
-            });
-
-            let repo_path = Path::new(path_str);
-            match self.analyze_repository(repo_path) {
-                Ok(mut repo) => {
-                    if let Some(existing_repo) = existing_cache.repositories.get(&repo.path) {
-                        repo.is_pinned = existing_repo.is_pinned;
-                        repo.pinned_at = existing_repo.pinned_at;
+        let concurrency = self.get_scan_concurrency()?;
+        let scanner = &*self;
+
+        // Each repository is saved as soon as it is analyzed, so a cancelled run keeps its partial results
+        worker_pool::run_bounded(
+            concurrency,
+            &self.cancel_token,
+            repo_paths,
+            |path_str: String| {
+                let result = scanner.analyze_repository_with_cache(
+                    Path::new(&path_str),
+                    existing_cache.repositories.get(&path_str),
+                );
+                (path_str, result)
+            },
+            |(path_str, result)| {
+                let _ = window.emit("analysis-progress", AnalysisProgress {
+                    total,
+                    current: analyzed_repos.len() + 1,
+                    current_path: path_str.clone(),
+                });
+
+                match result {
+                    Ok(mut repo) => {
+                        if let Some(existing_repo) = existing_cache.repositories.get(&repo.path) {
+                            repo.is_pinned = existing_repo.is_pinned;
+                            repo.pinned_at = existing_repo.pinned_at;
+                        }
+                        if let Err(e) = scanner.data_store.add_repository(repo.clone()) {
+                            eprintln!("Failed to save repository {}: {}", repo.name, e);
+                        }
+                        analyzed_repos.push(repo);
                     }
-                    if let Err(e) = self.data_store.add_repository(repo.clone()) {
-                        eprintln!("Failed to save repository {}: {}", repo.name, e);
+                    Err(e) => {
+                        eprintln!("Failed to analyze repository at {}: {}", path_str, e);
                     }
-                    analyzed_repos.push(repo);
-                }
-                Err(e) => {
-                    eprintln!("Failed to analyze repository at {}: {}", path_str, e);
                 }
-            }
+            },
+        );
+
+        if self.cancel_token.is_cancelled() {
+            self.emit_scan_finished(window, analyzed_repos.len() as u32);
         }
 
-        self.repos = analyzed_repos.clone();
-        Ok(analyzed_repos)
+        self.repos = repo_kind::group_worktrees(analyzed_repos);
+        Ok(self.repos.clone())
     }
 
-    pub async fn load_cached_repositories(&mut self) -> Result<Vec<GitRepository>, String> {
+    pub fn load_cached_repositories(&mut self) -> Result<Vec<GitRepository>, String> {
         let cache = self.data_store.load_cache()?;
-        self.repos = cache.repositories.into_values().collect();
+        self.repos = repo_kind::group_worktrees(cache.repositories.into_values().collect());
         Ok(self.repos.clone())
     }
 
-    pub async fn scan_disk_with_cache(&mut self, window: &Window, force_rescan: bool) -> Result<Vec<GitRepository>, String> {
+    pub fn scan_disk_with_cache(&mut self, window: &Window, force_rescan: bool) -> Result<Vec<GitRepository>, String> {
         if !force_rescan {
             // Try to load from cache first
-            match self.load_cached_repositories().await {
+            match self.load_cached_repositories() {
                 Ok(cached_repos) if !cached_repos.is_empty() => {
                     // Optional: Validate cached repositories if needed or return them directly
                     // For now, let's assume if cache is loaded, we can return it.
@@ -133,7 +212,7 @@ impl GitScanner {
         let existing_cache = self.data_store.load_cache().unwrap_or_default();
 
         // Perform full scan
-        let new_repositories = self.scan_disk(window).await?;
+        let new_repositories = self.scan_disk(window)?;
         
         // Merge new repositories with existing ones, preserving pin states
         for mut new_repo in new_repositories {
@@ -148,7 +227,7 @@ impl GitScanner {
         }
         
         // Return all repositories (reload from cache to get complete list)
-        self.load_cached_repositories().await
+        self.load_cached_repositories()
     }
 
     // async fn validate_cached_repositories(&self, cached_repos: Vec<GitRepository>) -> Vec<GitRepository> {
@@ -163,7 +242,7 @@ impl GitScanner {
     //     valid_repos
     // }
 
-    pub async fn scan_disk(&mut self, window: &Window) -> Result<Vec<GitRepository>, String> {
+    pub fn scan_disk(&mut self, window: &Window) -> Result<Vec<GitRepository>, String> {
         self.repos.clear();
         let mut repos_found = 0;
 
@@ -175,33 +254,28 @@ impl GitScanner {
             PathBuf::from("/usr/local"),
         ];
 
-        for root_path in scan_paths {
-            if !root_path.exists() {
-                continue;
-            }
+        let scan_paths = scan_paths.into_iter()
+            .filter(|root_path| root_path.exists())
+            .collect();
 
-            self.scan_directory(&root_path, window, &mut repos_found).await?;
-        }
+        self.scan_directories(scan_paths, window, &mut repos_found)?;
 
         // Send final progress update
-        let _ = window.emit("scan-progress", ScanProgress {
-            current_path: "Scan completed".to_string(),
-            repos_found,
-            completed: true,
-        });
+        self.emit_scan_finished(window, repos_found);
 
         Ok(self.repos.clone())
     }
 
-    pub async fn scan_custom_paths(&mut self, window: &Window, custom_paths: Vec<String>) -> Result<Vec<GitRepository>, String> {
+    pub fn scan_custom_paths(&mut self, window: &Window, custom_paths: Vec<String>) -> Result<Vec<GitRepository>, String> {
         // Load existing cache to preserve pin states
         let existing_cache = self.data_store.load_cache().unwrap_or_default();
         
         self.repos.clear();
         let mut repos_found = 0;
+        let mut root_paths = Vec::new();
 
         for path_str in custom_paths {
-            let root_path = Path::new(&path_str);
+            let root_path = PathBuf::from(&path_str);
             
             // Update scan path timestamp before scanning
             if let Err(e) = self.data_store.update_scan_path_last_scanned(&path_str) {
@@ -209,16 +283,14 @@ impl GitScanner {
             }
             
             if root_path.exists() && root_path.is_dir() {
-                self.scan_directory(root_path, window, &mut repos_found).await?;
+                root_paths.push(root_path);
             }
         }
 
+        self.scan_directories(root_paths, window, &mut repos_found)?;
+
         // Send final progress update
-        let _ = window.emit("scan-progress", ScanProgress {
-            current_path: "Complete".to_string(),
-            repos_found,
-            completed: true,
-        });
+        self.emit_scan_finished(window, repos_found);
 
         // Merge new repositories with existing ones, preserving pin states
         for new_repo in &mut self.repos {
@@ -229,7 +301,7 @@ impl GitScanner {
             }
         }
 
-        // Save all found repositories to cache
+        // Save all found repositories to cache (on cancellation, whatever was found so far)
         for repo in &self.repos {
             if let Err(e) = self.data_store.add_repository(repo.clone()) {
                 eprintln!("Failed to cache repository {}: {}", repo.path, e);
@@ -237,7 +309,7 @@ impl GitScanner {
         }
 
         // Return all repositories (reload from cache to get complete list)
-        self.load_cached_repositories().await
+        self.load_cached_repositories()
     }
     
     pub fn add_scan_path(&self, path: String) -> Result<(), String> {
@@ -248,40 +320,98 @@ impl GitScanner {
         self.data_store.remove_scan_path(path)
     }
     
+    pub fn get_scan_config(&self) -> Result<ScanConfig, String> {
+        self.data_store.get_scan_config()
+    }
+    
+    pub fn set_scan_config(&self, config: ScanConfig) -> Result<(), String> {
+        self.data_store.set_scan_config(config)
+    }
+    
+    pub fn set_scan_path_overrides(&self, path: &str, overrides: Option<ScanConfig>) -> Result<(), String> {
+        self.data_store.set_scan_path_overrides(path, overrides)
+    }
+    
     pub fn get_scan_paths(&self) -> Result<Vec<crate::repo_types::ScanPath>, String> {
         let cache = self.data_store.load_cache()?;
         Ok(cache.scan_paths.into_values().collect())
     }
 
-    async fn scan_directory(&mut self, root_path: &Path, window: &Window, repos_found: &mut u32) -> Result<(), String> {
-        let walker = WalkDir::new(root_path)
-            .into_iter();
-
-        for entry in walker.filter_entry(|e| !is_hidden(e) && !is_large_dir(e)) {
-            if let Ok(entry) = entry {
-                if entry.file_type().is_dir() && entry.path().join(".git").exists() {
-                    match self.analyze_repository(entry.path()) {
-                        Ok(repo) => {
-                            *repos_found += 1;
-                            let _ = window.emit("scan-progress", ScanProgress {
-                                current_path: repo.path.clone(),
-                                repos_found: *repos_found,
-                                completed: false,
-                            });
-                            self.repos.push(repo);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to analyze repository at {:?}: {}", entry.path(), e);
-                        }
-                    }
+    /// Restore scan paths, pins and collections from an exported workspace. Repositories that
+    /// exist on disk but were not scanned yet are analyzed so they can be pinned and collected.
+    pub fn import_workspace(&mut self, workspace_file: &WorkspaceFile, mode: ImportMode) -> Result<WorkspaceImportReport, String> {
+        let home = dirs::home_dir();
+        let cache = self.data_store.load_cache()?;
+        let mut matches = workspace::match_repositories(workspace_file, &cache, home.as_deref(), |path| {
+            repo_kind::detect_kind(path).is_some()
+        });
+        
+        for matched in matches.iter_mut() {
+            let Some(path) = matched.as_deref() else { continue };
+            if cache.repositories.contains_key(path) {
+                continue;
+            }
+            
+            match self.analyze_repository_with_cache(Path::new(path), None) {
+                Ok(repo) => self.data_store.add_repository(repo)?,
+                Err(e) => {
+                    eprintln!("Failed to analyze repository {}: {}", path, e);
+                    *matched = None;
                 }
             }
         }
-        Ok(())
+        
+        self.data_store.edit(|memory| {
+            Ok(workspace::import_workspace(memory, workspace_file, &matches, mode, home.as_deref()))
+        })
     }
 
-    pub fn analyze_repository(&self, repo_path: &Path) -> Result<GitRepository, String> {
-        self.analyze_repository_with_cache(repo_path, None)
+    fn scan_directories(&mut self, root_paths: Vec<PathBuf>, window: &Window, repos_found: &mut u32) -> Result<(), String> {
+        let existing_cache = self.data_store.load_cache().unwrap_or_default();
+        let concurrency = self.get_scan_concurrency()?;
+        let scanner = &*self;
+        let mut found_repos = Vec::new();
+
+        // The walker feeds repository paths to a bounded pool of analysis workers,
+        // so discovery keeps going while earlier repositories are being analyzed
+        worker_pool::run_pipeline(
+            concurrency,
+            &self.cancel_token,
+            |push| {
+                for root_path in &root_paths {
+                    let filter = DiscoveryFilter::for_root(root_path, &existing_cache);
+
+                    for repo_path in filter.repositories(root_path) {
+                        if scanner.cancel_token.is_cancelled() || !push(repo_path) {
+                            return;
+                        }
+                    }
+                }
+            },
+            |repo_path: PathBuf| {
+                let existing_repo = existing_cache.repositories.get(repo_path.to_string_lossy().as_ref());
+                let result = scanner.analyze_repository_with_cache(&repo_path, existing_repo);
+                (repo_path, result)
+            },
+            |(repo_path, result)| match result {
+                Ok(repo) => {
+                    *repos_found += 1;
+                    let _ = window.emit("scan-progress", ScanProgress {
+                        current_path: repo.path.clone(),
+                        repos_found: *repos_found,
+                        completed: false,
+                        cancelled: false,
+                    });
+                    found_repos.push(repo);
+                }
+                Err(e) => {
+                    eprintln!("Failed to analyze repository at {:?}: {}", repo_path, e);
+                }
+            },
+        );
+
+        self.repos.extend(found_repos);
+        Ok(())
     }
 
     pub fn analyze_repository_with_cache(&self, repo_path: &Path, existing_repo: Option<&GitRepository>) -> Result<GitRepository, String> {
@@ -289,6 +419,7 @@ impl GitScanner {
             Ok(repo) => repo,
             Err(e) => return Err(format!("Failed to open repository at {:?}: {}", repo_path, e)),
         };
+        let RepositoryLayout { kind, parent_path, worktrees } = repo_kind::describe(&repo, repo_path);
 
         // Get repository name from the directory name
         let name = repo_path
@@ -300,20 +431,25 @@ impl GitScanner {
         // Calculate directory size
         let size_mb = self.get_directory_size(repo_path)?;
 
-        // Get file types
-        let file_types = self.get_file_types(repo_path);
+        // Get file types (a bare repository has no working tree to inspect)
+        let file_types = if kind == RepositoryKind::Bare {
+            HashMap::new()
+        } else {
+            self.get_file_types(repo_path)
+        };
 
         // Get git information
-        let (current_branch, branches, remote_url, commit_count, last_commit_date) = 
-            self.get_git_info(&repo)?;
+        let GitInfo { current_branch, branches, remote_url, remotes, commit_count, last_commit_date, head_oid } =
+            self.get_git_info(&repo, existing_repo)?;
 
-        // Check if we should scan node_modules
-        let node_modules_info = if self.should_scan_node_modules(repo_path, existing_repo)? {
-            self.scan_node_modules(repo_path)?
-        } else {
-            // Preserve existing node_modules info if we don't need to rescan
-            existing_repo.and_then(|repo| repo.node_modules_info.clone())
-        };
+        // Get working tree status (uncommitted work)
+        let working_tree_status = Self::get_working_tree_status(&repo);
+
+        // Get submodule checkouts and how they compare to the recorded commits
+        let submodules = Self::read_submodules(&repo);
+
+        // Size build artifacts, reusing the cached sizes while they are still current
+        let build_artifacts = self.scan_build_artifacts(repo_path, kind, existing_repo, false)?;
 
         Ok(GitRepository {
             name,
@@ -324,22 +460,43 @@ impl GitScanner {
             current_branch,
             branches,
             remote_url,
+            remotes,
             commit_count,
+            head_oid,
+            working_tree_status,
+            kind,
+            parent_path,
+            worktrees,
+            submodules,
             last_analyzed: Utc::now(),
+            last_fetched: existing_repo.and_then(|repo| repo.last_fetched),
             is_valid: true,
             is_pinned: false, // Default to unpinned for new repositories
             pinned_at: None,
-            node_modules_info,
+            build_artifacts,
         })
     }
 
     pub fn refresh_repository(&mut self, repo_path: &str) -> Result<GitRepository, String> {
+        // Explicit refreshes always re-measure build artifacts
+        self.reanalyze_repository(repo_path, true)
+    }
+
+    /// Re-analyze a repository after a change on disk, reusing cached build artifact sizes when still current
+    pub fn update_repository(&mut self, repo_path: &str) -> Result<GitRepository, String> {
+        self.reanalyze_repository(repo_path, false)
+    }
+
+    fn reanalyze_repository(&mut self, repo_path: &str, force_artifacts: bool) -> Result<GitRepository, String> {
         // Get existing repository to preserve pin state
         let cache = self.data_store.load_cache()?;
         let existing_repo = cache.repositories.get(repo_path);
         
-        // Force node_modules re-scan by passing None as existing repo for node_modules scanning
-        let mut updated_repo = self.analyze_repository_with_cache_force_node_modules(Path::new(repo_path), existing_repo)?;
+        let mut updated_repo = if force_artifacts {
+            self.analyze_repository_with_cache_force_artifacts(Path::new(repo_path), existing_repo)?
+        } else {
+            self.analyze_repository_with_cache(Path::new(repo_path), existing_repo)?
+        };
         
         // Preserve pin state from existing repository
         if let Some(existing) = existing_repo {
@@ -358,8 +515,75 @@ impl GitScanner {
         Ok(updated_repo)
     }
 
+    /// Re-read branches and their ahead/behind counts after a fetch and record when it happened;
+    /// returns None if the repository is not cached
+    pub fn record_fetch(&mut self, repo_path: &str, fetched_at: DateTime<Utc>) -> Result<Option<GitRepository>, String> {
+        let repo = Repository::open(repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        let branches = Self::get_branches(&repo);
+
+        let updated = self.data_store.edit(|memory| {
+            Ok(memory.update_repository(repo_path, |cached| {
+                cached.branches = branches;
+                cached.last_fetched = Some(fetched_at);
+            }))
+        })?;
+
+        if let Some(updated) = &updated {
+            if let Some(repo) = self.repos.iter_mut().find(|r| r.path == repo_path) {
+                *repo = updated.clone();
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Flag a cached repository whose directory disappeared; returns None if it was not cached
+    pub fn mark_repository_invalid(&mut self, repo_path: &str) -> Result<Option<GitRepository>, String> {
+        let invalid_repo = self.data_store.mark_repository_invalid(repo_path)?;
+
+        if let Some(repo) = self.repos.iter_mut().find(|r| r.path == repo_path) {
+            repo.is_valid = false;
+        }
+
+        Ok(invalid_repo)
+    }
+
+    /// Analyze and cache repositories under `dir` that are not in the cache yet
+    pub fn add_new_repositories_under(&mut self, dir: &Path) -> Result<Vec<GitRepository>, String> {
+        let cache = self.data_store.load_cache()?;
+        let mut new_repos = Vec::new();
+
+        // Apply the rules of whichever scan path contains the new directory
+        let filter = DiscoveryFilter::for_path(dir, &cache);
+
+        for repo_path in filter.repositories(dir) {
+            // Previously invalidated entries are picked up again, e.g. after a repository was moved back
+            let existing_repo = cache.repositories.get(repo_path.to_string_lossy().as_ref());
+            if existing_repo.is_some_and(|repo| repo.is_valid) {
+                continue;
+            }
+
+            match self.analyze_repository_with_cache(&repo_path, existing_repo) {
+                Ok(mut repo) => {
+                    if let Some(existing) = existing_repo {
+                        repo.is_pinned = existing.is_pinned;
+                        repo.pinned_at = existing.pinned_at;
+                    }
+                    self.data_store.add_repository(repo.clone())?;
+                    self.repos.push(repo.clone());
+                    new_repos.push(repo);
+                }
+                Err(e) => {
+                    eprintln!("Failed to analyze repository at {:?}: {}", repo_path, e);
+                }
+            }
+        }
+
+        Ok(new_repos)
+    }
+
     pub fn refresh_cache(&mut self) -> Result<Vec<GitRepository>, String> {
-        let mut cache = self.data_store.load_cache()?;
+        let cache = self.data_store.load_cache()?;
         let mut updated_repos = Vec::new();
         let mut repos_to_remove = Vec::new();
         let mut repos_to_update = Vec::new();
@@ -369,7 +593,7 @@ impl GitScanner {
             let repo_path = std::path::Path::new(path);
             
             // Check if the repository still exists
-            if repo_path.exists() && repo_path.join(".git").exists() {
+            if repo_kind::detect_kind(repo_path).is_some() {
                 // Repository exists, refresh its data
                 match self.analyze_repository_with_cache(repo_path, Some(repo)) {
                     Ok(mut updated_repo) => {
@@ -392,26 +616,27 @@ impl GitScanner {
             }
         }
         
-        // Second pass: apply the updates
-        for (path, updated_repo) in repos_to_update {
-            cache.repositories.insert(path, updated_repo);
-        }
-        
-        // Remove invalid repositories from cache
-        for path in &repos_to_remove {
-            cache.repositories.remove(path);
-        }
-        
-        // Update cache metadata
-        cache.last_updated = chrono::Utc::now();
-        
-        // Save updated cache
-        self.data_store.save_cache(&cache)?;
+        // Second pass: apply the updates to the latest cache, which may have changed during analysis
+        self.data_store.edit(|memory| {
+            for (path, mut updated_repo) in repos_to_update {
+                if let Some(current) = memory.cache().repositories.get(&path) {
+                    updated_repo.is_pinned = current.is_pinned;
+                    updated_repo.pinned_at = current.pinned_at;
+                }
+                memory.put_repository(updated_repo);
+            }
+            
+            // Remove invalid repositories from cache
+            for path in &repos_to_remove {
+                memory.remove_repository(path);
+            }
+            Ok(())
+        })?;
         
         // Update in-memory repos
-        self.repos = updated_repos.clone();
+        self.repos = repo_kind::group_worktrees(updated_repos);
         
-        Ok(updated_repos)
+        Ok(self.repos.clone())
     }
 
     pub fn get_cache_info(&self) -> Result<crate::data_store::CacheInfo, String> {
@@ -427,10 +652,7 @@ impl GitScanner {
     }
 
     pub fn remove_repository_from_cache(&self, repo_path: &str) -> Result<(), String> {
-        let mut cache = self.data_store.load_cache()?;
-        cache.repositories.remove(repo_path);
-        cache.last_updated = Utc::now();
-        self.data_store.save_cache(&cache)
+        self.data_store.remove_repository(repo_path)
     }
 
     fn get_directory_size(&self, path: &Path) -> Result<f64, String> {
@@ -439,11 +661,13 @@ impl GitScanner {
         if let Ok(entries) = fs::read_dir(path) {
             for entry in entries.flatten() {
                 let entry_path = entry.path();
-                if entry_path.is_file() {
+                // file_type does not follow symlinks, so a link back up the tree cannot loop
+                let Ok(file_type) = entry.file_type() else { continue };
+                if file_type.is_file() {
                     if let Ok(metadata) = entry.metadata() {
                         total_size += metadata.len();
                     }
-                } else if entry_path.is_dir() && entry_path.file_name() != Some(std::ffi::OsStr::new(".git")) {
+                } else if file_type.is_dir() && entry_path.file_name() != Some(std::ffi::OsStr::new(".git")) {
                     // Recursively calculate size, but skip .git directory to avoid double counting
                     if let Ok(size) = self.get_directory_size(&entry_path) {
                         total_size += (size * 1024.0 * 1024.0) as u64;
@@ -482,144 +706,275 @@ impl GitScanner {
         file_types
     }
 
-    fn get_git_info(&self, repo: &Repository) -> Result<(Option<String>, Vec<String>, Option<String>, u32, Option<DateTime<Utc>>), String> {
+    fn get_git_info(&self, repo: &Repository, existing_repo: Option<&GitRepository>) -> Result<GitInfo, String> {
         // Get current branch
         let current_branch = repo.head()
             .ok()
             .and_then(|head| head.shorthand().map(|s| s.to_string()));
 
-        // Get all branches
-        let mut branches = Vec::new();
-        if let Ok(branch_iter) = repo.branches(None) {
-            for branch in branch_iter {
-                if let Ok((branch, _)) = branch {
-                    if let Some(name) = branch.name().ok().flatten() {
-                        branches.push(name.to_string());
-                    }
+        // Get all branches with their upstream tracking state
+        let branches = Self::get_branches(repo);
+
+        // Get all remotes; remote_url keeps pointing at origin when there is one
+        let remotes = self.get_remotes(repo);
+        let remote_url = remotes.iter()
+            .find(|remote| remote.name == "origin")
+            .or(remotes.first())
+            .and_then(|remote| remote.fetch_url.clone());
+
+        // Get commit count and last commit date from HEAD (unborn branches have neither)
+        let head_oid = repo.head().ok().and_then(|head| head.target());
+
+        let mut commit_count = 0u32;
+        let mut last_commit_date = None;
+
+        if let Some(head_oid) = head_oid {
+            commit_count = Self::count_commits(repo, head_oid, existing_repo);
+
+            if let Ok(commit) = repo.find_commit(head_oid) {
+                let time = commit.time();
+                last_commit_date = Some(DateTime::from_timestamp(time.seconds(), 0)
+                    .unwrap_or_else(Utc::now));
+            }
+        }
+
+        Ok(GitInfo {
+            current_branch,
+            branches,
+            remote_url,
+            remotes,
+            commit_count,
+            last_commit_date,
+            head_oid: head_oid.map(|oid| oid.to_string()),
+        })
+    }
+
+    fn get_remotes(&self, repo: &Repository) -> Vec<RemoteInfo> {
+        let Ok(names) = repo.remotes() else {
+            return Vec::new();
+        };
+
+        names.iter()
+            .flatten()
+            .filter_map(|name| repo.find_remote(name).ok())
+            .map(|remote| {
+                let fetch_url = remote.url().map(|url| url.to_string());
+                let push_url = remote.pushurl().map(|url| url.to_string()).or_else(|| fetch_url.clone());
+                RemoteInfo {
+                    name: remote.name().unwrap_or_default().to_string(),
+                    parsed: fetch_url.as_deref().and_then(remote_url::parse_remote_url),
+                    fetch_url,
+                    push_url,
+                }
+            })
+            .collect()
+    }
+
+    fn count_commits(repo: &Repository, head_oid: Oid, existing_repo: Option<&GitRepository>) -> u32 {
+        // If the previously cached HEAD is still an ancestor of the current HEAD,
+        // only walk the commits added since then and add them to the cached count
+        let previous = existing_repo.and_then(|existing| {
+            existing.head_oid
+                .as_deref()
+                .and_then(|oid| Oid::from_str(oid).ok())
+                .map(|oid| (oid, existing.commit_count))
+        });
+
+        if let Some((previous_oid, previous_count)) = previous {
+            if previous_oid == head_oid {
+                return previous_count;
+            }
+
+            // Fails when history was rewritten or the old commit was garbage collected
+            if repo.graph_descendant_of(head_oid, previous_oid).unwrap_or(false) {
+                if let Ok(new_commits) = Self::walk_commit_count(repo, head_oid, Some(previous_oid)) {
+                    return previous_count + new_commits;
                 }
             }
         }
 
-        // Get remote URL
-        let remote_url = repo.find_remote("origin")
-            .ok()
-            .and_then(|remote| remote.url().map(|s| s.to_string()));
+        match Self::walk_commit_count(repo, head_oid, None) {
+            Ok(count) => count,
+            Err(e) => {
+                eprintln!("Failed to count commits: {}", e);
+                0
+            }
+        }
+    }
 
-        // Get commit count and last commit date
-        let mut commit_count = 0u32;
-        let mut last_commit_date = None;
+    fn walk_commit_count(repo: &Repository, head_oid: Oid, hide_oid: Option<Oid>) -> Result<u32, git2::Error> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head_oid)?;
+        if let Some(hide_oid) = hide_oid {
+            revwalk.hide(hide_oid)?;
+        }
 
-        if let Ok(mut revwalk) = repo.revwalk() {
-            if revwalk.push_head().is_ok() {
-                for commit_id in revwalk.take(1000) { // Limit to first 1000 commits for performance
-                    if let Ok(commit_oid) = commit_id {
-                        commit_count += 1;
-                        
-                        // Get the most recent commit date
-                        if last_commit_date.is_none() {
-                            if let Ok(commit) = repo.find_commit(commit_oid) {
-                                let time = commit.time();
-                                last_commit_date = Some(DateTime::from_timestamp(time.seconds(), 0)
-                                    .unwrap_or_else(|| Utc::now()));
+        let mut count = 0u32;
+        for commit_id in revwalk {
+            commit_id?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn get_branches(repo: &Repository) -> Vec<BranchInfo> {
+        let mut branches = Vec::new();
+
+        let branch_iter = match repo.branches(None) {
+            Ok(branch_iter) => branch_iter,
+            Err(_) => return branches,
+        };
+
+        for (branch, branch_type) in branch_iter.flatten() {
+            let name = match branch.name().ok().flatten() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            let mut info = BranchInfo::from_name(name);
+            info.is_remote = branch_type == BranchType::Remote;
+            info.is_head = branch.is_head();
+
+            // Ahead/behind is computed from the refs already on disk; nothing is fetched
+            if branch_type == BranchType::Local {
+                if let Ok(upstream) = branch.upstream() {
+                    info.upstream = upstream.name().ok().flatten().map(|s| s.to_string());
+
+                    if let (Some(local_oid), Some(upstream_oid)) = (branch.get().target(), upstream.get().target()) {
+                        match repo.graph_ahead_behind(local_oid, upstream_oid) {
+                            Ok((ahead, behind)) => {
+                                info.ahead = ahead as u32;
+                                info.behind = behind as u32;
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to compare branch {} with its upstream: {}", info.name, e);
                             }
                         }
                     }
                 }
             }
+
+            branches.push(info);
         }
 
-        Ok((current_branch, branches, remote_url, commit_count, last_commit_date))
+        branches
     }
 
-    fn should_scan_node_modules(&self, repo_path: &Path, existing_repo: Option<&GitRepository>) -> Result<bool, String> {
-        let package_json_path = repo_path.join("package.json");
-        
-        // If no package.json exists, no need to scan node_modules
-        if !package_json_path.exists() {
-            return Ok(false);
+    fn get_working_tree_status(repo: &Repository) -> Option<WorkingTreeStatus> {
+        // Bare repositories have no working tree to inspect
+        if repo.is_bare() {
+            return None;
         }
-        
-        // Get package.json modification time
-        let package_json_metadata = fs::metadata(&package_json_path)
-            .map_err(|e| format!("Failed to read package.json metadata: {}", e))?;
-        let package_json_modified = package_json_metadata.modified()
-            .map_err(|e| format!("Failed to get package.json modification time: {}", e))?;
-        let package_json_modified_utc = DateTime::<Utc>::from(package_json_modified);
-        
-        // If this is a new repository or we don't have node_modules info, scan it
-        if let Some(existing) = existing_repo {
-            if let Some(node_modules_info) = &existing.node_modules_info {
-                // Check if package.json was modified after our last scan
-                return Ok(package_json_modified_utc > node_modules_info.package_json_modified);
+
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(false) // Count an untracked directory once instead of walking it
+            .include_ignored(false)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true);
+
+        let statuses = match repo.statuses(Some(&mut options)) {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                eprintln!("Failed to read status for {:?}: {}", repo.workdir(), e);
+                return None;
+            }
+        };
+
+        let mut status = WorkingTreeStatus::default();
+        for entry in statuses.iter() {
+            let flags = entry.status();
+
+            if flags.is_conflicted() {
+                status.conflicted += 1;
+                continue;
+            }
+            if flags.intersects(Status::INDEX_RENAMED | Status::WT_RENAMED) {
+                status.renamed += 1;
+            }
+            if flags.intersects(Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE) {
+                status.staged += 1;
+            }
+            if flags.intersects(Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE) {
+                status.modified += 1;
+            }
+            if flags.is_wt_new() {
+                status.untracked += 1;
             }
         }
-        
-        // First time scanning or no existing node_modules info
-        Ok(true)
+
+        status.is_dirty = status.modified > 0
+            || status.staged > 0
+            || status.untracked > 0
+            || status.conflicted > 0
+            || status.renamed > 0;
+
+        Some(status)
     }
 
-    fn scan_node_modules(&self, repo_path: &Path) -> Result<Option<NodeModulesInfo>, String> {
-        let package_json_path = repo_path.join("package.json");
-        
-        // If no package.json exists, return None
-        if !package_json_path.exists() {
-            return Ok(None);
+    /// Read the current submodule state of a repository without touching the cache
+    pub fn get_submodules(&self, repo_path: &str) -> Result<Vec<SubmoduleInfo>, String> {
+        let repo = Repository::open(repo_path)
+            .map_err(|e| format!("Failed to open git repository: {}", e))?;
+        Ok(Self::read_submodules(&repo))
+    }
+
+    fn read_submodules(repo: &Repository) -> Vec<SubmoduleInfo> {
+        if repo.is_bare() {
+            return Vec::new();
         }
-        
-        // Get package.json modification time
-        let package_json_metadata = fs::metadata(&package_json_path)
-            .map_err(|e| format!("Failed to read package.json metadata: {}", e))?;
-        let package_json_modified = package_json_metadata.modified()
-            .map_err(|e| format!("Failed to get package.json modification time: {}", e))?;
-        let package_json_modified_utc = DateTime::<Utc>::from(package_json_modified);
-        
-        let mut node_modules_paths = Vec::new();
-        let mut total_size_mb = 0.0;
-        let mut count = 0;
-        
-        // Look for node_modules directories in the repository
-        let walker = WalkDir::new(repo_path)
-            .max_depth(3) // Don't go too deep to avoid nested node_modules
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|entry| {
-                entry.file_type().is_dir() && 
-                entry.file_name() == "node_modules"
-            });
-        
-        for entry in walker {
-            let node_modules_path = entry.path();
-            
-            // Calculate size of this node_modules directory
-            match self.get_directory_size(node_modules_path) {
-                Ok(size_mb) => {
-                    total_size_mb += size_mb;
-                    count += 1;
-                    node_modules_paths.push(node_modules_path.to_string_lossy().to_string());
-                }
-                Err(e) => {
-                    // Log error but continue with other node_modules directories
-                    eprintln!("Failed to calculate size for {}: {}", node_modules_path.display(), e);
-                }
+
+        let submodules = match repo.submodules() {
+            Ok(submodules) => submodules,
+            Err(e) => {
+                eprintln!("Failed to list submodules: {}", e);
+                return Vec::new();
             }
+        };
+
+        submodules.iter().map(|submodule| {
+            let name = submodule.name().unwrap_or_default().to_string();
+            let status = repo.submodule_status(&name, SubmoduleIgnore::None)
+                .unwrap_or(SubmoduleStatus::empty());
+
+            SubmoduleInfo {
+                path: submodule.path().to_string_lossy().to_string(),
+                url: submodule.url().map(|url| url.to_string()),
+                // A staged gitlink is what `git submodule update` would check out
+                recorded_commit: submodule.index_id().or(submodule.head_id()).map(|oid| oid.to_string()),
+                checked_out_commit: submodule.workdir_id().map(|oid| oid.to_string()),
+                is_uninitialized: status.contains(SubmoduleStatus::WD_UNINITIALIZED),
+                is_out_of_sync: status.contains(SubmoduleStatus::WD_MODIFIED),
+                is_modified: status.intersects(
+                    SubmoduleStatus::WD_INDEX_MODIFIED
+                        | SubmoduleStatus::WD_WD_MODIFIED
+                        | SubmoduleStatus::WD_UNTRACKED,
+                ),
+                name,
+            }
+        }).collect()
+    }
+
+    fn scan_build_artifacts(
+        &self,
+        repo_path: &Path,
+        kind: RepositoryKind,
+        existing_repo: Option<&GitRepository>,
+        force: bool,
+    ) -> Result<Option<BuildArtifactsInfo>, String> {
+        // A bare repository has no working tree to build in
+        if kind == RepositoryKind::Bare {
+            return Ok(None);
         }
-        
-        if count > 0 {
-            Ok(Some(NodeModulesInfo {
-                total_size_mb,
-                count,
-                paths: node_modules_paths,
-                last_scanned: Utc::now(),
-                package_json_modified: package_json_modified_utc,
-            }))
-        } else {
-            Ok(None)
-        }
+        let existing = existing_repo.and_then(|repo| repo.build_artifacts.as_ref());
+        build_artifacts::scan(repo_path, existing, force)
     }
-    
-    pub fn analyze_repository_with_cache_force_node_modules(&self, repo_path: &Path, existing_repo: Option<&GitRepository>) -> Result<GitRepository, String> {
+
+    pub fn analyze_repository_with_cache_force_artifacts(&self, repo_path: &Path, existing_repo: Option<&GitRepository>) -> Result<GitRepository, String> {
         let repo = Repository::open(repo_path)
             .map_err(|e| format!("Failed to open git repository: {}", e))?;
+        let RepositoryLayout { kind, parent_path, worktrees } = repo_kind::describe(&repo, repo_path);
 
         // Get repository name from the directory name
         let name = repo_path
@@ -631,15 +986,25 @@ impl GitScanner {
         // Calculate directory size
         let size_mb = self.get_directory_size(repo_path)?;
 
-        // Get file types
-        let file_types = self.get_file_types(repo_path);
+        // Get file types (a bare repository has no working tree to inspect)
+        let file_types = if kind == RepositoryKind::Bare {
+            HashMap::new()
+        } else {
+            self.get_file_types(repo_path)
+        };
 
         // Get git information
-        let (current_branch, branches, remote_url, commit_count, last_commit_date) = 
-            self.get_git_info(&repo)?;
+        let GitInfo { current_branch, branches, remote_url, remotes, commit_count, last_commit_date, head_oid } =
+            self.get_git_info(&repo, existing_repo)?;
 
-        // Force node_modules scan (ignore existing cache)
-        let node_modules_info = self.scan_node_modules(repo_path)?;
+        // Get working tree status (uncommitted work)
+        let working_tree_status = Self::get_working_tree_status(&repo);
+
+        // Get submodule checkouts and how they compare to the recorded commits
+        let submodules = Self::read_submodules(&repo);
+
+        // Force build artifact scan (ignore existing cache)
+        let build_artifacts = self.scan_build_artifacts(repo_path, kind, existing_repo, true)?;
 
         Ok(GitRepository {
             name,
@@ -650,26 +1015,144 @@ impl GitScanner {
             current_branch,
             branches,
             remote_url,
+            remotes,
             commit_count,
+            head_oid,
+            working_tree_status,
+            kind,
+            parent_path,
+            worktrees,
+            submodules,
             last_analyzed: Utc::now(),
+            last_fetched: existing_repo.and_then(|repo| repo.last_fetched),
             is_valid: true,
             is_pinned: false, // Default to unpinned for new repositories
             pinned_at: None,
-            node_modules_info,
+            build_artifacts,
         })
     }
 }
 
-fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-    entry.file_name()
-         .to_str()
-         .map(|s| s.starts_with('.') || s == "node_modules" || s == "vendor" || s == "target" || s == "build")
-         .unwrap_or(false)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+
+    // Cached entry whose count is deliberately wrong, so results show which commits were walked
+    fn cached_at(head: Oid, commit_count: u32) -> GitRepository {
+        GitRepository { head_oid: Some(head.to_string()), commit_count, ..GitRepository::fixture("/src/app") }
+    }
+
+    #[test]
+    fn test_count_commits_adds_new_commits_and_recounts_rewritten_history() {
+        let dir = temp_dir("commits");
+        let repo = Repository::init(&dir).unwrap();
+        let root = commit_file(&repo, "a.txt", "one");
+        let first = commit_file(&repo, "a.txt", "two");
+        assert_eq!(GitScanner::count_commits(&repo, first, None), 2);
+        assert_eq!(GitScanner::count_commits(&repo, first, Some(&cached_at(first, 42))), 42);
+
+        let second = commit_file(&repo, "a.txt", "three");
+        assert_eq!(GitScanner::count_commits(&repo, second, Some(&cached_at(first, 100))), 101);
+
+        // After a force reset the cached HEAD is no longer an ancestor, so everything is walked again
+        repo.reset(repo.find_commit(root).unwrap().as_object(), git2::ResetType::Hard, None).unwrap();
+        let rewritten = commit_file(&repo, "a.txt", "four");
+        assert_eq!(GitScanner::count_commits(&repo, rewritten, Some(&cached_at(second, 100))), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_branches_report_upstream_ahead_and_behind() {
+        let dir = temp_dir("branches");
+        let origin = Repository::init(dir.join("origin")).unwrap();
+        commit_file(&origin, "a.txt", "one");
+        let clone = Repository::clone(dir.join("origin").to_str().unwrap(), dir.join("clone")).unwrap();
+        let branch = clone.head().unwrap().shorthand().unwrap().to_string();
+
+        // Two commits only upstream, one only local
+        commit_file(&origin, "a.txt", "two");
+        commit_file(&origin, "a.txt", "three");
+        commit_file(&clone, "b.txt", "local");
+        clone.find_remote("origin").unwrap().fetch(&[] as &[&str], None, None).unwrap();
+
+        let branches = GitScanner::get_branches(&clone);
+        let local = branches.iter().find(|info| !info.is_remote && info.name == branch).unwrap();
+        assert!(local.is_head);
+        assert_eq!(local.upstream.as_deref(), Some(format!("origin/{}", branch).as_str()));
+        assert_eq!((local.ahead, local.behind), (1, 2));
+        let remote = branches.iter().find(|info| info.is_remote && info.name == format!("origin/{}", branch)).unwrap();
+        assert_eq!(remote.upstream, None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_working_tree_status_counts_each_kind_of_change() {
+        let dir = temp_dir("status");
+        let repo = Repository::init(&dir).unwrap();
+        commit_file(&repo, "modified.txt", "one");
+        commit_file(&repo, "staged.txt", "one");
+        commit_file(&repo, "old.txt", "renamed content\n");
+
+        fs::write(dir.join("modified.txt"), "two").unwrap();
+        fs::write(dir.join("staged.txt"), "two").unwrap();
+        fs::write(dir.join("untracked.txt"), "new").unwrap();
+        fs::rename(dir.join("old.txt"), dir.join("new.txt")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let status = GitScanner::get_working_tree_status(&repo).unwrap();
+        assert_eq!(status.modified, 1);
+        assert_eq!(status.staged, 2); // staged.txt and the rename
+        assert_eq!(status.untracked, 1);
+        assert_eq!(status.renamed, 1);
+        assert_eq!(status.conflicted, 0);
+        assert!(status.is_dirty);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 
-fn is_large_dir(entry: &walkdir::DirEntry) -> bool {
-    if let Some(file_name) = entry.file_name().to_str() {
-        return matches!(file_name, "node_modules" | "target" | "vendor" | ".git" | ".svn" | "dist" | "build");
+    #[test]
+    fn test_read_submodules_reports_uninitialized_out_of_sync_and_modified() {
+        let dir = temp_dir("submodules");
+        let dep = Repository::init(dir.join("dep")).unwrap();
+        commit_file(&dep, "lib.txt", "one");
+
+        let superproject = Repository::init(dir.join("super")).unwrap();
+        let mut submodule = superproject.submodule(dir.join("dep").to_str().unwrap(), Path::new("libs/dep"), true).unwrap();
+        submodule.clone(None).unwrap();
+        submodule.add_finalize().unwrap();
+        commit_file(&superproject, "README.md", "super");
+
+        let clean = &GitScanner::read_submodules(&superproject)[0];
+        assert_eq!(clean.path, "libs/dep");
+        assert!(clean.checked_out_commit.is_some());
+        assert_eq!(clean.recorded_commit, clean.checked_out_commit);
+        assert!(!clean.is_uninitialized && !clean.is_out_of_sync && !clean.is_modified);
+
+        // Uncommitted changes inside the submodule
+        let checkout = submodule.open().unwrap();
+        fs::write(dir.join("super/libs/dep/lib.txt"), "edited").unwrap();
+        let modified = &GitScanner::read_submodules(&superproject)[0];
+        assert!(modified.is_modified && !modified.is_out_of_sync);
+
+        // A commit in the submodule that the superproject does not record yet
+        commit_file(&checkout, "lib.txt", "two");
+        let out_of_sync = &GitScanner::read_submodules(&superproject)[0];
+        assert!(out_of_sync.is_out_of_sync && !out_of_sync.is_modified);
+        assert_ne!(out_of_sync.recorded_commit, out_of_sync.checked_out_commit);
+
+        // A fresh clone does not check out submodules
+        let clone = Repository::clone(dir.join("super").to_str().unwrap(), dir.join("clone")).unwrap();
+        let uninitialized = &GitScanner::read_submodules(&clone)[0];
+        assert!(uninitialized.is_uninitialized);
+        assert!(uninitialized.checked_out_commit.is_none());
+
+        fs::remove_dir_all(dir).unwrap();
     }
-    false
 }
@@ -2,17 +2,106 @@ mod repo_types;
 mod git_scanner;
 mod data_store;
 mod optimizations;
+mod worker_pool;
+mod cancellation;
+mod repo_watcher;
+mod scan_rules;
+mod repo_kind;
+mod remote_url;
+mod sqlite_store;
+mod cache_backup;
+mod cache_migrations;
+mod file_lock;
+mod memory_store;
+mod workspace;
+mod git_auth;
+mod manifest;
+mod bulk_exec;
+mod fetch;
+mod fast_forward;
+mod build_artifacts;
+mod trash;
+mod cleanup_log;
+mod cleanup_policy;
+mod disk_usage;
+mod git_maintenance;
+#[cfg(test)]
+mod test_support;
 
-use repo_types::{GitRepository, FileEntry, DirectoryListing, Collection};
+use repo_types::{GitRepository, FileEntry, DirectoryListing, Collection, ScanConfig, SubmoduleInfo, RestoreResult,
+    RepositorySelection, BulkCommandSummary, FetchResult, FastForwardReport, ArtifactKind, ArtifactCleanup,
+    CleanupMode, CleanupOperation, UndoCleanupResult, CleanupPolicy, CleanupPolicySettings, PolicyPreview,
+    PolicyRunReport, PolicyTrigger, DiskUsage, WorkspaceDiskUsage, GitHealth, MaintenanceTask, MaintenanceSummary,
+    CommandStatus};
 use git_scanner::GitScanner;
-use data_store::CacheInfo;
-use tauri::{command, Window, State};
+use cancellation::CancellationToken;
+use repo_watcher::RepositoryWatcher;
+use data_store::{CacheInfo, DataStore};
+use workspace::{ImportMode, WorkspaceImportReport};
+use cleanup_log::CleanupLog;
+use cleanup_policy::CleanupScheduler;
+use trash::Trash;
+use tauri::{command, Emitter, Manager, Window, State};
 use tauri::async_runtime::Mutex;
 use std::path::Path;
 use std::fs;
+use std::sync::Arc;
+use std::collections::HashMap;
 
 struct AppState {
     scanner: Mutex<GitScanner>,
+    scan_lock: Mutex<()>, // Held while a scan runs, so scans take turns without locking the scanner
+    scan_cancel: std::sync::Mutex<CancellationToken>, // Shared by running and queued scans until cancel_scan replaces it
+    data_store: Arc<DataStore>, // Shared with the scanner; usable while a scan holds the lock
+    command_runs: std::sync::Mutex<HashMap<String, CancellationToken>>, // Running bulk commands by run id
+    cleanup_log: Arc<CleanupLog>, // Shared with blocking tasks that record cleanups
+}
+
+impl AppState {
+    /// Register a cancellable run under `run_id`, refusing ids that are still running
+    fn start_run(&self, run_id: &str) -> Result<CancellationToken, String> {
+        let mut runs = self.command_runs.lock().map_err(|_| "Command run lock poisoned".to_string())?;
+        if runs.contains_key(run_id) {
+            return Err(format!("A command with run id {} is already running", run_id));
+        }
+        let cancel = CancellationToken::new();
+        runs.insert(run_id.to_string(), cancel.clone());
+        Ok(cancel)
+    }
+
+    fn finish_run(&self, run_id: &str) {
+        if let Ok(mut runs) = self.command_runs.lock() {
+            runs.remove(run_id);
+        }
+    }
+
+    // Taken before waiting for the scan lock, so cancelling also stops scans still queued
+    fn scan_token(&self) -> Result<CancellationToken, String> {
+        let cancel = self.scan_cancel.lock().map_err(|_| "Scan cancel lock poisoned".to_string())?;
+        Ok(cancel.clone())
+    }
+}
+
+/// Run blocking git or filesystem work on the blocking thread pool, so long operations
+/// do not stall the async runtime the other commands run on
+async fn run_blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+}
+
+/// Run a scan on a detached scanner off the async runtime. The scanner lock is only taken to
+/// start the scan and to store its result, so other commands keep working while it runs.
+async fn run_scan(
+    state: &AppState,
+    scan: impl FnOnce(&mut GitScanner) -> Result<Vec<GitRepository>, String> + Send + 'static,
+) -> Result<Vec<GitRepository>, String> {
+    let cancel = state.scan_token()?;
+    let _scan_running = state.scan_lock.lock().await;
+    let mut scanner = state.scanner.lock().await.detached_with_cancel(cancel);
+    let repos = run_blocking(move || scan(&mut scanner)).await??;
+    state.scanner.lock().await.repos = repos.clone();
+    Ok(repos)
 }
 
 #[command]
@@ -21,24 +110,27 @@ async fn discover_repositories(
     state: State<'_, AppState>,
     paths: Vec<String>,
 ) -> Result<Vec<String>, String> {
-    let scanner = state.scanner.lock().await;
-    scanner.discover_repositories(&window, paths).await
+    let cancel = state.scan_token()?;
+    let _scan_running = state.scan_lock.lock().await;
+    let scanner = state.scanner.lock().await.detached_with_cancel(cancel);
+    run_blocking(move || scanner.discover_repositories(&window, paths)).await?
 }
 
 #[command]
 async fn analyze_discovered_repositories(
     window: Window,
     state: State<'_, AppState>,
+    watcher: State<'_, RepositoryWatcher>,
     repo_paths: Vec<String>,
 ) -> Result<Vec<GitRepository>, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.analyze_discovered_repositories(&window, repo_paths).await
+    let result = run_scan(&state, move |scanner| scanner.analyze_discovered_repositories(&window, repo_paths)).await;
+    watcher.resync();
+    result
 }
 
 #[command]
 async fn scan_repositories(window: Window, state: State<'_, AppState>) -> Result<Vec<GitRepository>, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.scan_disk(&window).await
+    run_scan(&state, move |scanner| scanner.scan_disk(&window)).await
 }
 
 #[command]
@@ -47,14 +139,13 @@ async fn scan_repositories_with_cache(
     state: State<'_, AppState>,
     force_rescan: bool,
 ) -> Result<Vec<GitRepository>, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.scan_disk_with_cache(&window, force_rescan).await
+    run_scan(&state, move |scanner| scanner.scan_disk_with_cache(&window, force_rescan)).await
 }
 
 #[command]
 async fn load_cached_repositories(state: State<'_, AppState>) -> Result<Vec<GitRepository>, String> {
     let mut scanner = state.scanner.lock().await;
-    scanner.load_cached_repositories().await
+    scanner.load_cached_repositories()
 }
 
 #[command]
@@ -96,6 +187,12 @@ async fn refresh_repository(repo_path: String, state: State<'_, AppState>) -> Re
     scanner.refresh_repository(&repo_path)
 }
 
+#[command]
+async fn get_submodules(repo_path: String, state: State<'_, AppState>) -> Result<Vec<SubmoduleInfo>, String> {
+    let scanner = state.scanner.lock().await;
+    scanner.get_submodules(&repo_path)
+}
+
 #[command]
 async fn list_directory_contents(repo_path: String) -> Result<DirectoryListing, String> {
     use chrono::{DateTime, Utc};
@@ -239,28 +336,170 @@ async fn open_in_file_manager(repo_path: String) -> Result<(), String> {
 async fn scan_custom_paths(
     window: Window,
     state: State<'_, AppState>,
+    watcher: State<'_, RepositoryWatcher>,
     scan_paths: Vec<String>,
 ) -> Result<Vec<GitRepository>, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.scan_custom_paths(&window, scan_paths).await
+    let result = run_scan(&state, move |scanner| scanner.scan_custom_paths(&window, scan_paths)).await;
+    watcher.resync();
+    result
+}
+
+/// Run a shell command in every selected repository. Output is streamed as command-output
+/// events and each finished repository as a command-finished event.
+#[command]
+async fn run_bulk_command(
+    window: Window,
+    run_id: String,
+    selection: RepositorySelection,
+    command_line: String,
+    concurrency: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<BulkCommandSummary, String> {
+    if command_line.trim().is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+    let repo_paths = state.data_store.select_repositories(&selection)?;
+    let concurrency = concurrency.unwrap_or_else(worker_pool::default_concurrency);
+
+    let cancel = state.start_run(&run_id)?;
+    let summary = run_blocking({
+        let run_id = run_id.clone();
+        move || bulk_exec::run_in_repositories(
+            &run_id,
+            repo_paths,
+            &command_line,
+            concurrency,
+            &cancel,
+            |output| {
+                let _ = window.emit("command-output", output);
+            },
+            |result| {
+                let _ = window.emit("command-finished", result);
+            },
+        )
+    }).await;
+
+    state.finish_run(&run_id);
+    summary
+}
+
+/// Fetch all remotes of the selected repositories, then update their ahead/behind counts.
+/// Progress is streamed as fetch-progress events and each finished repository as fetch-finished.
+#[command]
+async fn fetch_repositories(
+    window: Window,
+    selection: RepositorySelection,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<FetchResult>, String> {
+    let repo_paths = state.data_store.select_repositories(&selection)?;
+    let timeout = timeout_secs.map(std::time::Duration::from_secs).unwrap_or(fetch::DEFAULT_FETCH_TIMEOUT);
+
+    let concurrency = concurrency.unwrap_or(fetch::DEFAULT_FETCH_CONCURRENCY);
+
+    let mut scanner = state.scanner.lock().await.detached();
+    run_blocking(move || {
+        let results = fetch::fetch_repositories(
+            repo_paths,
+            concurrency,
+            timeout,
+            |progress| {
+                let _ = window.emit("fetch-progress", progress);
+            },
+            |result| {
+                let _ = window.emit("fetch-finished", result);
+            },
+        );
+
+        // A failed remote may still have been preceded by successful ones, so refresh those too
+        let fetched_at = chrono::Utc::now();
+        for result in results.iter().filter(|result| !result.fetched_remotes.is_empty()) {
+            match scanner.record_fetch(&result.repo_path, fetched_at) {
+                Ok(Some(repo)) => {
+                    let _ = window.emit("repository-updated", repo);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to refresh {} after fetch: {}", result.repo_path, e),
+            }
+        }
+        results
+    }).await
+}
+
+/// Fast-forward the current branch of the selected repositories to their upstreams where
+/// that is clean; nothing is merged. Usually run after fetch_repositories.
+#[command]
+async fn fast_forward_repositories(
+    window: Window,
+    selection: RepositorySelection,
+    state: State<'_, AppState>,
+) -> Result<FastForwardReport, String> {
+    let repo_paths = state.data_store.select_repositories(&selection)?;
+    let mut scanner = state.scanner.lock().await.detached();
+    run_blocking(move || {
+        let report = fast_forward::fast_forward_repositories(repo_paths, worker_pool::default_concurrency());
+        for update in &report.updated {
+            match scanner.update_repository(&update.repo_path) {
+                Ok(repo) => {
+                    let _ = window.emit("repository-updated", repo);
+                }
+                Err(e) => eprintln!("Failed to refresh {} after fast-forward: {}", update.repo_path, e),
+            }
+        }
+        report
+    }).await
+}
+
+#[command]
+async fn cancel_bulk_command(run_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let runs = state.command_runs.lock().map_err(|_| "Command run lock poisoned".to_string())?;
+    let cancel = runs.get(&run_id).ok_or_else(|| format!("No command running with run id {}", run_id))?;
+    cancel.cancel();
+    Ok(())
+}
+
+#[command]
+async fn cancel_scan(state: State<'_, AppState>) -> Result<(), String> {
+    // Deliberately does not take the scanner lock: a running scan is holding it
+    let mut cancel = state.scan_cancel.lock().map_err(|_| "Scan cancel lock poisoned".to_string())?;
+    cancel.cancel();
+    // Scans started from now on are not affected
+    *cancel = CancellationToken::new();
+    Ok(())
+}
+
+#[command]
+async fn get_scan_concurrency(state: State<'_, AppState>) -> Result<usize, String> {
+    let scanner = state.scanner.lock().await;
+    scanner.get_scan_concurrency()
+}
+
+#[command]
+async fn set_scan_concurrency(concurrency: usize, state: State<'_, AppState>) -> Result<(), String> {
+    let scanner = state.scanner.lock().await;
+    scanner.set_scan_concurrency(concurrency)
 }
 
 #[command]
 async fn refresh_cache(state: State<'_, AppState>) -> Result<Vec<GitRepository>, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.refresh_cache()
+    run_scan(&state, |scanner| scanner.refresh_cache()).await
 }
 
 #[command]
-async fn add_scan_path(path: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn add_scan_path(path: String, state: State<'_, AppState>, watcher: State<'_, RepositoryWatcher>) -> Result<(), String> {
     let scanner = state.scanner.lock().await;
-    scanner.add_scan_path(path)
+    scanner.add_scan_path(path)?;
+    watcher.resync();
+    Ok(())
 }
 
 #[command]
-async fn remove_scan_path(path: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn remove_scan_path(path: String, state: State<'_, AppState>, watcher: State<'_, RepositoryWatcher>) -> Result<(), String> {
     let scanner = state.scanner.lock().await;
-    scanner.remove_scan_path(&path)
+    scanner.remove_scan_path(&path)?;
+    watcher.resync();
+    Ok(())
 }
 
 #[command]
@@ -269,6 +508,114 @@ async fn get_scan_paths(state: State<'_, AppState>) -> Result<Vec<repo_types::Sc
     scanner.get_scan_paths()
 }
 
+#[command]
+async fn get_scan_config(state: State<'_, AppState>) -> Result<ScanConfig, String> {
+    let scanner = state.scanner.lock().await;
+    scanner.get_scan_config()
+}
+
+#[command]
+async fn set_scan_config(config: ScanConfig, state: State<'_, AppState>, watcher: State<'_, RepositoryWatcher>) -> Result<(), String> {
+    let scanner = state.scanner.lock().await;
+    scanner.set_scan_config(config)?;
+    watcher.resync();
+    Ok(())
+}
+
+#[command]
+async fn set_scan_path_overrides(
+    path: String,
+    overrides: Option<ScanConfig>,
+    state: State<'_, AppState>,
+    watcher: State<'_, RepositoryWatcher>,
+) -> Result<(), String> {
+    let scanner = state.scanner.lock().await;
+    scanner.set_scan_path_overrides(&path, overrides)?;
+    watcher.resync();
+    Ok(())
+}
+
+#[command]
+async fn list_cache_backups(state: State<'_, AppState>) -> Result<Vec<cache_backup::CacheBackup>, String> {
+    state.data_store.list_cache_backups()
+}
+
+#[command]
+async fn restore_cache_backup(
+    file_name: String,
+    state: State<'_, AppState>,
+    watcher: State<'_, RepositoryWatcher>,
+) -> Result<Vec<GitRepository>, String> {
+    let mut scanner = state.scanner.lock().await;
+    scanner.data_store.restore_cache_backup(&file_name)?;
+    watcher.resync();
+    scanner.load_cached_repositories()
+}
+
+#[command]
+async fn migrate_cache_to_sqlite(state: State<'_, AppState>) -> Result<usize, String> {
+    state.data_store.migrate_to_sqlite()
+}
+
+/// Write scan paths, pins and collections to a portable file; returns the number of repositories
+#[command]
+async fn export_workspace(file_path: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let cache = state.data_store.load_cache()?;
+    let workspace_file = workspace::export_workspace(&cache, dirs::home_dir().as_deref());
+    workspace::write_workspace_file(Path::new(&file_path), &workspace_file)?;
+    Ok(workspace_file.repositories.len())
+}
+
+#[command]
+async fn import_workspace(
+    file_path: String,
+    mode: ImportMode,
+    state: State<'_, AppState>,
+    watcher: State<'_, RepositoryWatcher>,
+) -> Result<WorkspaceImportReport, String> {
+    let workspace_file = workspace::read_workspace_file(Path::new(&file_path))?;
+    // Repositories missing from the cache are analyzed, so import off the scanner lock
+    let mut scanner = state.scanner.lock().await.detached();
+    let report = run_blocking(move || scanner.import_workspace(&workspace_file, mode)).await??;
+    watcher.resync();
+    Ok(report)
+}
+
+/// Write a TOML manifest of the repositories under `root_path`; returns the number listed
+#[command]
+async fn generate_manifest(root_path: String, file_path: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let cache = state.data_store.load_cache()?;
+    let repos: Vec<GitRepository> = cache.repositories.into_values().collect();
+    let manifest = manifest::build_manifest(&repos, Path::new(&root_path));
+    manifest::write_manifest(Path::new(&file_path), &manifest)?;
+    Ok(manifest.repos.len())
+}
+
+/// Clone the repositories of a manifest that are missing below `root_path`
+#[command]
+async fn restore_from_manifest(
+    window: Window,
+    file_path: String,
+    root_path: String,
+    state: State<'_, AppState>,
+) -> Result<RestoreResult, String> {
+    let manifest = manifest::read_manifest(Path::new(&file_path))?;
+    let mut scanner = state.scanner.lock().await.detached();
+    run_blocking(move || {
+        let result = manifest::restore(&manifest, Path::new(&root_path), |progress| {
+            let _ = window.emit("restore-progress", progress);
+        });
+
+        // Make the new clones show up without a rescan
+        for path in &result.cloned {
+            if let Err(e) = scanner.update_repository(path) {
+                eprintln!("Failed to analyze restored repository {}: {}", path, e);
+            }
+        }
+        result
+    }).await
+}
+
 #[command]
 async fn delete_repository(repo_path: String, state: State<'_, AppState>) -> Result<(), String> {
     let scanner = state.scanner.lock().await;
@@ -278,27 +625,23 @@ async fn delete_repository(repo_path: String, state: State<'_, AppState>) -> Res
 // Pin-related commands
 #[command]
 async fn toggle_repository_pin(repo_path: String, state: State<'_, AppState>) -> Result<GitRepository, String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.data_store.toggle_repository_pin(&repo_path)
+    state.data_store.toggle_repository_pin(&repo_path)
 }
 
 #[command]
 async fn get_pinned_repositories(state: State<'_, AppState>) -> Result<Vec<GitRepository>, String> {
-    let scanner = state.scanner.lock().await;
-    scanner.data_store.get_pinned_repositories()
+    state.data_store.get_pinned_repositories()
 }
 
 // Collection-related commands
 #[command]
 async fn create_collection(name: String, color: String, state: State<'_, AppState>) -> Result<Collection, String> {
-    let scanner = state.scanner.lock().await;
-    scanner.data_store.create_collection(name, color)
+    state.data_store.create_collection(name, color)
 }
 
 #[command]
 async fn get_collections(state: State<'_, AppState>) -> Result<Vec<Collection>, String> {
-    let scanner = state.scanner.lock().await;
-    scanner.data_store.get_collections()
+    state.data_store.get_collections()
 }
 
 #[command]
@@ -307,8 +650,7 @@ async fn add_repository_to_collection(
     repo_path: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let scanner = state.scanner.lock().await;
-    scanner.data_store.add_repository_to_collection(&collection_id, &repo_path)
+    state.data_store.add_repository_to_collection(&collection_id, &repo_path)
 }
 
 #[command]
@@ -316,14 +658,12 @@ async fn get_repositories_in_collection(
     collection_id: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<GitRepository>, String> {
-    let scanner = state.scanner.lock().await;
-    scanner.data_store.get_repositories_in_collection(&collection_id)
+    state.data_store.get_repositories_in_collection(&collection_id)
 }
 
 #[command]
 async fn delete_collection(collection_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner.data_store.delete_collection(&collection_id)
+    state.data_store.delete_collection(&collection_id)
 }
 
 #[command]
@@ -332,68 +672,216 @@ async fn remove_repo_from_collection(
     repo_path: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let mut scanner = state.scanner.lock().await;
-    scanner
-        .data_store
+    state.data_store
         .remove_repository_from_collection(&collection_id, &repo_path)
 }
 
 #[command]
-async fn delete_node_modules(repo_path: String) -> Result<(), String> {
-    use std::fs;
-    use walkdir::WalkDir;
-    
-    let repo_path = Path::new(&repo_path);
-    
-    if !repo_path.exists() {
+async fn delete_build_artifacts(
+    repo_path: String,
+    kinds: Option<Vec<ArtifactKind>>,
+    mode: Option<CleanupMode>,
+    state: State<'_, AppState>,
+) -> Result<ArtifactCleanup, String> {
+    if !Path::new(&repo_path).exists() {
         return Err("Repository path does not exist".to_string());
     }
     
-    // Look for node_modules directories in the repository
-    let walker = WalkDir::new(repo_path)
-        .max_depth(3) // Don't go too deep to avoid nested node_modules
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|entry| {
-            entry.file_type().is_dir() && 
-            entry.file_name() == "node_modules"
-        });
+    let mode = mode.unwrap_or_default();
+    let trash = Trash::for_mode(mode)?;
+    let cleanup_log = state.cleanup_log.clone();
+    let cleanup = run_blocking(move || {
+        let mut cleanup = build_artifacts::clean(Path::new(&repo_path), kinds.as_deref(), mode, trash.as_ref())?;
+        if mode == CleanupMode::Trash {
+            cleanup.operation_id = cleanup_log.record(&repo_path, &cleanup)?;
+        }
+        Ok::<_, String>(cleanup)
+    }).await??;
     
-    let mut deleted_count = 0;
-    let mut errors = Vec::new();
+    // A dry run that finds nothing is a valid answer; an actual cleanup of nothing is a mistake
+    if mode != CleanupMode::DryRun && cleanup.removed.is_empty() && cleanup.errors.is_empty() {
+        return Err("No build artifacts found to delete".to_string());
+    }
     
-    for entry in walker {
-        let node_modules_path = entry.path();
-        match fs::remove_dir_all(node_modules_path) {
-            Ok(_) => {
-                deleted_count += 1;
-                println!("Deleted node_modules at: {}", node_modules_path.display());
+    Ok(cleanup)
+}
+
+#[command]
+async fn list_cleanup_operations(state: State<'_, AppState>) -> Result<Vec<CleanupOperation>, String> {
+    state.cleanup_log.operations()
+}
+
+#[command]
+async fn undo_cleanup(operation_id: String, state: State<'_, AppState>) -> Result<UndoCleanupResult, String> {
+    state.cleanup_log.undo(&operation_id, &Trash::home()?)
+}
+
+#[command]
+async fn get_cleanup_policies(state: State<'_, AppState>) -> Result<Vec<CleanupPolicy>, String> {
+    state.data_store.get_cleanup_policies()
+}
+
+#[command]
+async fn create_cleanup_policy(settings: CleanupPolicySettings, state: State<'_, AppState>) -> Result<CleanupPolicy, String> {
+    state.data_store.create_cleanup_policy(settings)
+}
+
+#[command]
+async fn update_cleanup_policy(
+    policy_id: String,
+    settings: CleanupPolicySettings,
+    state: State<'_, AppState>,
+) -> Result<CleanupPolicy, String> {
+    state.data_store.update_cleanup_policy(&policy_id, settings)
+}
+
+#[command]
+async fn delete_cleanup_policy(policy_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.data_store.delete_cleanup_policy(&policy_id)
+}
+
+#[command]
+async fn preview_cleanup_policy(policy_id: String, state: State<'_, AppState>) -> Result<PolicyPreview, String> {
+    let policy = state.data_store.get_cleanup_policy(&policy_id)?;
+    let data_store = state.data_store.clone();
+    run_blocking(move || cleanup_policy::preview(&data_store, &policy)).await?
+}
+
+/// Apply a cleanup policy now, regardless of its schedule
+#[command]
+async fn run_cleanup_policy(
+    window: Window,
+    policy_id: String,
+    state: State<'_, AppState>,
+) -> Result<PolicyRunReport, String> {
+    let policy = state.data_store.get_cleanup_policy(&policy_id)?;
+    let data_store = state.data_store.clone();
+    let cleanup_log = state.cleanup_log.clone();
+    let mut scanner = state.scanner.lock().await.detached();
+    run_blocking(move || {
+        let report = cleanup_policy::execute(&data_store, &cleanup_log, &policy, PolicyTrigger::Manual)?;
+        for cleaned in report.repositories.iter().filter(|cleaned| !cleaned.cleanup.removed.is_empty()) {
+            match scanner.update_repository(&cleaned.repo_path) {
+                Ok(repo) => {
+                    let _ = window.emit("repository-updated", repo);
+                }
+                Err(e) => eprintln!("Failed to refresh {} after cleanup: {}", cleaned.repo_path, e),
             }
-            Err(e) => {
-                let error_msg = format!("Failed to delete {}: {}", node_modules_path.display(), e);
-                errors.push(error_msg);
+        }
+        Ok(report)
+    }).await?
+}
+
+#[command]
+async fn get_cleanup_reports(policy_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<PolicyRunReport>, String> {
+    state.data_store.get_cleanup_reports(policy_id.as_deref())
+}
+
+/// Disk usage breakdown of one repository; cancel with cancel_bulk_command
+#[command]
+async fn get_disk_usage(run_id: String, repo_path: String, state: State<'_, AppState>) -> Result<DiskUsage, String> {
+    let cancel = state.start_run(&run_id)?;
+    let usage = run_blocking(move || disk_usage::analyze_repository(Path::new(&repo_path), &cancel)).await;
+    state.finish_run(&run_id);
+    usage?
+}
+
+/// Disk usage of the selected repositories (all cached ones by default) and the `top_n`
+/// largest. A `disk-usage-analyzed` event is emitted for every repository as it finishes;
+/// cancel with cancel_bulk_command.
+#[command]
+async fn get_workspace_disk_usage(
+    window: Window,
+    run_id: String,
+    selection: Option<RepositorySelection>,
+    top_n: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<WorkspaceDiskUsage, String> {
+    let repo_paths = match selection {
+        Some(selection) => state.data_store.select_repositories(&selection)?,
+        None => state.data_store.read(|memory| memory.cache().repositories.keys().cloned().collect())?,
+    };
+
+    let cancel = state.start_run(&run_id)?;
+    let report = run_blocking(move || disk_usage::analyze_workspace(
+        repo_paths,
+        top_n.unwrap_or(20),
+        worker_pool::default_concurrency(),
+        &cancel,
+        |usage| {
+            let _ = window.emit("disk-usage-analyzed", usage);
+        },
+    )).await;
+
+    state.finish_run(&run_id);
+    report
+}
+
+#[command]
+async fn get_git_health(repo_path: String) -> Result<GitHealth, String> {
+    run_blocking(move || git_maintenance::analyze(Path::new(&repo_path))).await?
+}
+
+/// Run `git gc` or incremental `git maintenance` in the selected repositories, reporting the
+/// git directory size before and after. Each step is streamed as maintenance-progress events,
+/// output as maintenance-output and each finished repository as maintenance-finished; cancel
+/// with cancel_bulk_command.
+#[command]
+async fn run_git_maintenance(
+    window: Window,
+    run_id: String,
+    selection: RepositorySelection,
+    task: MaintenanceTask,
+    concurrency: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceSummary, String> {
+    let repo_paths = state.data_store.select_repositories(&selection)?;
+    let concurrency = concurrency.unwrap_or(git_maintenance::DEFAULT_MAINTENANCE_CONCURRENCY);
+
+    let cancel = state.start_run(&run_id)?;
+    let mut scanner = state.scanner.lock().await.detached();
+    let summary = run_blocking({
+        let run_id = run_id.clone();
+        move || {
+            let summary = git_maintenance::run_maintenance(
+                &run_id,
+                repo_paths,
+                task,
+                concurrency,
+                &cancel,
+                |progress| {
+                    let _ = window.emit("maintenance-progress", progress);
+                },
+                |output| {
+                    let _ = window.emit("maintenance-output", output);
+                },
+                |result| {
+                    let _ = window.emit("maintenance-finished", result);
+                },
+            );
+
+            // Repository sizes changed
+            for result in summary.results.iter().filter(|result| result.command.status == CommandStatus::Succeeded) {
+                match scanner.update_repository(&result.command.repo_path) {
+                    Ok(repo) => {
+                        let _ = window.emit("repository-updated", repo);
+                    }
+                    Err(e) => eprintln!("Failed to refresh {} after maintenance: {}", result.command.repo_path, e),
+                }
             }
+            summary
         }
-    }
-    
-    if !errors.is_empty() {
-        return Err(format!("Deleted {} node_modules directories but encountered errors: {}", 
-                          deleted_count, errors.join("; ")));
-    }
-    
-    if deleted_count == 0 {
-        return Err("No node_modules directories found to delete".to_string());
-    }
-    
-    Ok(())
+    }).await;
+
+    state.finish_run(&run_id);
+    summary
 }
 
 // === OPTIMIZED SEARCH COMMANDS ===
 
 #[command]
-async fn find_repositories_under_path(path: String) -> Result<Vec<GitRepository>, String> {
-    let data_store = data_store::DataStore::new()?;
-    data_store.find_repositories_under_path_optimized(&path)
+async fn find_repositories_under_path(path: String, state: State<'_, AppState>) -> Result<Vec<GitRepository>, String> {
+    state.data_store.find_repositories_under_path_optimized(&path)
 }
 
 #[command]
@@ -401,36 +889,62 @@ async fn advanced_repository_search(
     name_prefix: Option<String>,
     min_size_mb: Option<f64>,
     max_size_mb: Option<f64>,
-    file_type: Option<String>
+    file_type: Option<String>,
+    remote_host: Option<String>,
+    remote_owner: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<GitRepository>, String> {
-    let data_store = data_store::DataStore::new()?;
-    data_store.advanced_search(
+    state.data_store.advanced_search(
         name_prefix.as_deref(),
         min_size_mb,
         max_size_mb,
-        file_type.as_deref()
+        file_type.as_deref(),
+        remote_host.as_deref(),
+        remote_owner.as_deref()
     )
 }
 
 #[command]
-async fn get_repository_fast(repo_path: String) -> Result<Option<GitRepository>, String> {
-    let data_store = data_store::DataStore::new()?;
-    data_store.get_repository_fast(&repo_path)
+async fn get_repository_fast(repo_path: String, state: State<'_, AppState>) -> Result<Option<GitRepository>, String> {
+    state.data_store.get_repository_fast(&repo_path)
 }
 
 #[command]
-async fn get_optimization_stats() -> Result<serde_json::Value, String> {
-    let data_store = data_store::DataStore::new()?;
-    data_store.get_optimization_stats()
+async fn get_optimization_stats(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    state.data_store.get_optimization_stats()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let scanner = GitScanner::new().expect("Failed to initialize GitScanner");
+    // Without the data directory nothing outlives the session, but the app still starts
+    let app_data_dir = data_store::app_data_dir().unwrap_or_else(|e| {
+        let dir = std::env::temp_dir().join("github-repo-manager");
+        eprintln!("{}; keeping data in {:?}", e, dir);
+        let _ = std::fs::create_dir_all(&dir);
+        dir
+    });
+    let data_store = Arc::new(DataStore::open(&app_data_dir));
+    data_store.start_write_behind();
+    let scanner = GitScanner::new(data_store.clone());
+    let exit_store = data_store.clone();
+    let cleanup_log = Arc::new(CleanupLog::new(&app_data_dir));
 
     tauri::Builder::default()
         .manage(AppState {
             scanner: Mutex::new(scanner),
+            scan_lock: Mutex::new(()),
+            scan_cancel: std::sync::Mutex::new(CancellationToken::new()),
+            data_store,
+            command_runs: std::sync::Mutex::new(HashMap::new()),
+            cleanup_log,
+        })
+        .setup(|app| {
+            // Keep cached repositories up to date as they change on disk
+            let watcher = RepositoryWatcher::start(app.handle().clone())?;
+            app.manage(watcher);
+            // Apply cleanup policies that run on a schedule
+            CleanupScheduler::start(app.handle().clone())?;
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
@@ -439,17 +953,35 @@ pub fn run() {
             load_cached_repositories,
             get_cache_info,
             clear_cache,
+            migrate_cache_to_sqlite,
+            export_workspace,
+            import_workspace,
+            generate_manifest,
+            restore_from_manifest,
+            list_cache_backups,
+            restore_cache_backup,
             cleanup_invalid_repositories,
             open_in_vscode,
             refresh_repository,
+            get_submodules,
             list_directory_contents,
             read_file_content,
             open_in_file_manager,
             scan_custom_paths,
+            cancel_scan,
+            run_bulk_command,
+            cancel_bulk_command,
+            fetch_repositories,
+            fast_forward_repositories,
+            get_scan_concurrency,
+            set_scan_concurrency,
             refresh_cache,
             add_scan_path,
             remove_scan_path,
             get_scan_paths,
+            get_scan_config,
+            set_scan_config,
+            set_scan_path_overrides,
             delete_repository,
             toggle_repository_pin,
             get_pinned_repositories,
@@ -459,10 +991,35 @@ pub fn run() {
             remove_repo_from_collection,
             delete_collection,
             get_repositories_in_collection,
-            delete_node_modules,
+            delete_build_artifacts,
+            list_cleanup_operations,
+            undo_cleanup,
+            get_cleanup_policies,
+            create_cleanup_policy,
+            update_cleanup_policy,
+            delete_cleanup_policy,
+            preview_cleanup_policy,
+            run_cleanup_policy,
+            get_cleanup_reports,
+            get_disk_usage,
+            get_workspace_disk_usage,
+            get_git_health,
+            run_git_maintenance,
             discover_repositories,
-            analyze_discovered_repositories
+            analyze_discovered_repositories,
+            find_repositories_under_path,
+            advanced_repository_search,
+            get_repository_fast,
+            get_optimization_stats
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(move |_app, event| {
+            // Write out changes still waiting for the write-behind thread
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = exit_store.flush() {
+                    eprintln!("Failed to write repository cache on exit: {}", e);
+                }
+            }
+        });
 }
@@ -0,0 +1,320 @@
+use crate::cache_backup;
+use crate::git_auth;
+use crate::repo_kind;
+use crate::repo_types::{GitRepository, RepositoryKind, RestoreFailure, RestoreProgress, RestoreResult, RestoreStatus};
+use git2::build::RepoBuilder;
+use git2::{FetchOptions, Repository};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+// Clone progress is reported at most this often per repository
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A shareable list of repositories and where they live below a common root, e.g.
+///
+/// ```toml
+/// version = 1
+///
+/// [[repo]]
+/// path = "tools/cli"
+/// default_branch = "main"
+///
+/// [[repo.remotes]]
+/// name = "origin"
+/// url = "git@github.com:acme/cli.git"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Manifest {
+    pub version: u32,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<ManifestRepo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManifestRepo {
+    pub path: String, // Relative to the manifest root, with "/" separators
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bare: bool,
+    pub remotes: Vec<ManifestRemote>, // Cloned from the first one
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManifestRemote {
+    pub name: String,
+    pub url: String,
+}
+
+/// List the repositories under `root` that can be cloned again, i.e. that have a remote.
+/// Worktrees and submodules are left out; they come back with their main repository.
+pub fn build_manifest(repos: &[GitRepository], root: &Path) -> Manifest {
+    let mut entries: Vec<ManifestRepo> = repos.iter()
+        .filter(|repo| matches!(repo.kind, RepositoryKind::Normal | RepositoryKind::Bare))
+        .filter_map(|repo| {
+            let relative = Path::new(&repo.path).strip_prefix(root).ok()?;
+            let remotes: Vec<ManifestRemote> = repo.remotes.iter()
+                .filter_map(|remote| Some(ManifestRemote {
+                    name: remote.name.clone(),
+                    url: remote.fetch_url.clone()?,
+                }))
+                .collect();
+            if remotes.is_empty() || relative.as_os_str().is_empty() {
+                return None;
+            }
+            let remotes = sort_origin_first(remotes);
+
+            Some(ManifestRepo {
+                path: relative.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                default_branch: default_branch(Path::new(&repo.path), &remotes[0].name)
+                    .or_else(|| repo.current_branch.clone()),
+                bare: repo.kind == RepositoryKind::Bare,
+                remotes,
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Manifest { version: MANIFEST_VERSION, repos: entries }
+}
+
+fn sort_origin_first(mut remotes: Vec<ManifestRemote>) -> Vec<ManifestRemote> {
+    remotes.sort_by_key(|remote| remote.name != "origin");
+    remotes
+}
+
+// Branch the remote's HEAD points to, as recorded by the last clone or `git remote set-head`
+fn default_branch(repo_path: &Path, remote_name: &str) -> Option<String> {
+    let repo = Repository::open(repo_path).ok()?;
+    let head = repo.find_reference(&format!("refs/remotes/{}/HEAD", remote_name)).ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix(&format!("refs/remotes/{}/", remote_name)).map(str::to_string)
+}
+
+pub fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest: Manifest = toml::from_str(&content)
+        .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+
+    if manifest.version > MANIFEST_VERSION {
+        return Err(format!(
+            "Manifest version {} is newer than this app supports ({})",
+            manifest.version, MANIFEST_VERSION
+        ));
+    }
+    for repo in &manifest.repos {
+        relative_path(&repo.path)?;
+        if repo.remotes.is_empty() {
+            return Err(format!("Manifest entry {} has no remotes", repo.path));
+        }
+    }
+    Ok(manifest)
+}
+
+pub fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), String> {
+    let content = toml::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    cache_backup::write_atomic(path, content.as_bytes())
+}
+
+// Manifests are shared, so entries must not point outside the root they are restored into
+fn relative_path(path: &str) -> Result<PathBuf, String> {
+    let relative = PathBuf::from(path);
+    let is_plain = relative.components().all(|component| matches!(component, Component::Normal(_)));
+    if !is_plain || relative.as_os_str().is_empty() {
+        return Err(format!("Invalid manifest path {:?}: must be relative and stay inside the root", path));
+    }
+    Ok(relative)
+}
+
+/// Clone every manifest entry that is missing below `root`, one after another.
+/// Entries whose directory already holds a repository are skipped.
+pub fn restore(manifest: &Manifest, root: &Path, mut on_progress: impl FnMut(RestoreProgress)) -> RestoreResult {
+    let mut result = RestoreResult::default();
+    let total = manifest.repos.len();
+
+    for (index, entry) in manifest.repos.iter().enumerate() {
+        let dest = match relative_path(&entry.path) {
+            Ok(relative) => root.join(relative),
+            Err(e) => {
+                result.failed.push(RestoreFailure { path: entry.path.clone(), error: e });
+                continue;
+            }
+        };
+        let dest_str = dest.to_string_lossy().to_string();
+        let mut report = |status, received_objects, total_objects, error: Option<String>| {
+            on_progress(RestoreProgress {
+                path: dest_str.clone(),
+                current: index + 1,
+                total,
+                status,
+                received_objects,
+                total_objects,
+                error,
+            });
+        };
+
+        if repo_kind::detect_kind(&dest).is_some() {
+            report(RestoreStatus::Skipped, 0, 0, None);
+            result.skipped.push(dest_str.clone());
+            continue;
+        }
+
+        report(RestoreStatus::Cloning, 0, 0, None);
+        let mut last_report = Instant::now();
+        let cloned = clone_entry(entry, &dest, &mut |received, total_objects| {
+            if last_report.elapsed() >= PROGRESS_INTERVAL || received == total_objects {
+                last_report = Instant::now();
+                report(RestoreStatus::Cloning, received, total_objects, None);
+            }
+        });
+
+        match cloned {
+            Ok(()) => {
+                report(RestoreStatus::Cloned, 0, 0, None);
+                result.cloned.push(dest_str.clone());
+            }
+            Err(e) => {
+                report(RestoreStatus::Failed, 0, 0, Some(e.clone()));
+                result.failed.push(RestoreFailure { path: dest_str.clone(), error: e });
+            }
+        }
+    }
+
+    result
+}
+
+fn clone_entry(entry: &ManifestRepo, dest: &Path, on_transfer: &mut dyn FnMut(usize, usize)) -> Result<(), String> {
+    let (origin, others) = entry.remotes.split_first()
+        .ok_or_else(|| format!("Manifest entry {} has no remotes", entry.path))?;
+
+    // Only an empty directory can be cloned into; anything else is left for the user to sort out
+    let existed = dest.exists();
+    if existed && fs::read_dir(dest).map(|mut entries| entries.next().is_some()).unwrap_or(true) {
+        return Err(format!("{} already exists and is not a repository", dest.display()));
+    }
+
+    let mut callbacks = git_auth::remote_callbacks();
+    callbacks.transfer_progress(|stats| {
+        on_transfer(stats.received_objects(), stats.total_objects());
+        true
+    });
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+
+    let mut builder = RepoBuilder::new();
+    builder.bare(entry.bare).fetch_options(fetch_options);
+    if let Some(branch) = &entry.default_branch {
+        builder.branch(branch);
+    }
+    builder.remote_create(|repo, _name, url| repo.remote(&origin.name, url));
+
+    let cloned = builder.clone(&origin.url, dest)
+        .map_err(|e| format!("Failed to clone {}: {}", origin.url, e))
+        .and_then(|repo| {
+            for remote in others {
+                repo.remote(&remote.name, &remote.url)
+                    .map_err(|e| format!("Failed to add remote {}: {}", remote.name, e))?;
+            }
+            Ok(())
+        });
+
+    // Leave no half-cloned directory behind, so the next restore tries again
+    if cloned.is_err() {
+        if existed {
+            if let Ok(entries) = fs::read_dir(dest) {
+                for entry in entries.flatten() {
+                    let _ = fs::remove_dir_all(entry.path()).or_else(|_| fs::remove_file(entry.path()));
+                }
+            }
+        } else {
+            let _ = fs::remove_dir_all(dest);
+        }
+    }
+    cloned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+    use git2::RepositoryInitOptions;
+
+    // Bare repository with one commit on `branch`, usable as a remote
+    fn bare_remote(dir: &Path, branch: &str) -> String {
+        let work = dir.join("work");
+        let repo = Repository::init_opts(&work, RepositoryInitOptions::new().initial_head(branch)).unwrap();
+        commit_file(&repo, "README.md", "hello");
+
+        let bare = dir.join("remote.git");
+        RepoBuilder::new().bare(true).clone(work.to_str().unwrap(), &bare).unwrap();
+        bare.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_restore_clones_missing_and_skips_present() {
+        let dir = temp_dir("manifest");
+        let url = bare_remote(&dir, "trunk");
+        let root = dir.join("workspace");
+        Repository::init(root.join("existing")).unwrap();
+
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            repos: vec![
+                ManifestRepo {
+                    path: "team/app".to_string(),
+                    default_branch: Some("trunk".to_string()),
+                    bare: false,
+                    remotes: vec![
+                        ManifestRemote { name: "upstream".to_string(), url: url.clone() },
+                        ManifestRemote { name: "mirror".to_string(), url: url.clone() },
+                    ],
+                },
+                ManifestRepo {
+                    path: "existing".to_string(),
+                    default_branch: None,
+                    bare: false,
+                    remotes: vec![ManifestRemote { name: "origin".to_string(), url }],
+                },
+            ],
+        };
+
+        // Round trip through the TOML file format
+        let manifest_path = dir.join("repos.toml");
+        write_manifest(&manifest_path, &manifest).unwrap();
+        let manifest = read_manifest(&manifest_path).unwrap();
+
+        let mut events = Vec::new();
+        let result = restore(&manifest, &root, |progress| events.push(progress.status));
+
+        assert_eq!(result.cloned, vec![root.join("team/app").to_string_lossy().to_string()]);
+        assert_eq!(result.skipped.len(), 1);
+        assert!(result.failed.is_empty());
+        assert_eq!(events.first(), Some(&RestoreStatus::Cloning));
+        assert_eq!(events.last(), Some(&RestoreStatus::Skipped));
+
+        let clone = Repository::open(root.join("team/app")).unwrap();
+        assert_eq!(clone.head().unwrap().shorthand(), Some("trunk"));
+        assert!(clone.find_remote("upstream").is_ok());
+        assert!(clone.find_remote("mirror").is_ok());
+        assert!(root.join("team/app/README.md").is_file());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_paths_must_stay_inside_root() {
+        assert!(relative_path("tools/cli").is_ok());
+        assert!(relative_path("../outside").is_err());
+        assert!(relative_path("/etc/repo").is_err());
+        assert!(relative_path("").is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use crate::data_store::RepositoryCache;
+use crate::optimizations::{PathTrie, RepositoryIndex};
+use crate::repo_types::{CleanupPolicy, Collection, GitRepository, PolicyRunReport, ScanConfig, ScanPath};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// The repository cache as held in memory, together with its search indexes.
+///
+/// This is the source of truth for the running app: every mutation goes through one of the
+/// methods below, which keep the indexes in step and record which entries changed so only
+/// those need to be written to disk (and merged into whatever other instances wrote there).
+pub struct MemoryStore {
+    cache: RepositoryCache,
+    path_trie: PathTrie,
+    repo_index: RepositoryIndex,
+    dirty: DirtyKeys,
+}
+
+// Entries changed since the last flush
+#[derive(Default)]
+struct DirtyKeys {
+    everything: bool, // The whole cache was replaced
+    repositories: HashSet<String>,
+    scan_paths: HashSet<String>,
+    collections: HashSet<String>,
+    cleanup_policies: HashSet<String>,
+    cleanup_reports: HashSet<String>,
+    scan_config: bool,
+}
+
+/// Unflushed changes, with the values they had in memory when taken
+pub enum CacheChanges {
+    Replaced(RepositoryCache),
+    Entries(CachePatch),
+}
+
+/// Changed entries; `None` means the entry was removed
+#[derive(Default)]
+pub struct CachePatch {
+    pub repositories: Vec<(String, Option<GitRepository>)>,
+    pub scan_paths: Vec<(String, Option<ScanPath>)>,
+    pub collections: Vec<(String, Option<Collection>)>,
+    pub cleanup_policies: Vec<(String, Option<CleanupPolicy>)>,
+    pub cleanup_reports: Vec<(String, Option<PolicyRunReport>)>,
+    pub scan_config: Option<ScanConfig>,
+}
+
+impl CacheChanges {
+    /// Apply these changes on top of `cache`, e.g. the version another instance wrote to disk
+    pub fn apply_to(&self, cache: &mut RepositoryCache) {
+        match self {
+            CacheChanges::Replaced(replacement) => *cache = replacement.clone(),
+            CacheChanges::Entries(patch) => {
+                apply_entries(&mut cache.repositories, &patch.repositories);
+                apply_entries(&mut cache.scan_paths, &patch.scan_paths);
+                apply_entries(&mut cache.collections, &patch.collections);
+                apply_entries(&mut cache.cleanup_policies, &patch.cleanup_policies);
+                apply_entries(&mut cache.cleanup_reports, &patch.cleanup_reports);
+                if let Some(config) = &patch.scan_config {
+                    cache.scan_config = config.clone();
+                }
+            }
+        }
+    }
+}
+
+fn apply_entries<T: Clone>(map: &mut HashMap<String, T>, entries: &[(String, Option<T>)]) {
+    for (key, value) in entries {
+        match value {
+            Some(value) => map.insert(key.clone(), value.clone()),
+            None => map.remove(key),
+        };
+    }
+}
+
+fn snapshot<T: Clone>(map: &HashMap<String, T>, keys: &HashSet<String>) -> Vec<(String, Option<T>)> {
+    keys.iter().map(|key| (key.clone(), map.get(key).cloned())).collect()
+}
+
+// Update a map entry in place, returning the updated value
+fn update_entry<K: Eq + Hash, T: Clone>(map: &mut HashMap<K, T>, key: &K, update: impl FnOnce(&mut T)) -> Option<T> {
+    let value = map.get_mut(key)?;
+    update(value);
+    Some(value.clone())
+}
+
+impl MemoryStore {
+    pub fn new(cache: RepositoryCache) -> Self {
+        let mut store = Self {
+            cache,
+            path_trie: PathTrie::new(),
+            repo_index: RepositoryIndex::new(),
+            dirty: DirtyKeys::default(),
+        };
+        store.rebuild_indexes();
+        store
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.path_trie.clear();
+        self.repo_index.clear();
+        for (path, repo) in &self.cache.repositories {
+            self.path_trie.insert_repository(path);
+            self.repo_index.insert_repository(repo);
+        }
+    }
+
+    pub fn cache(&self) -> &RepositoryCache {
+        &self.cache
+    }
+
+    pub fn path_trie(&self) -> &PathTrie {
+        &self.path_trie
+    }
+
+    pub fn repo_index(&self) -> &RepositoryIndex {
+        &self.repo_index
+    }
+
+    fn touch(&mut self) {
+        self.cache.last_updated = Utc::now();
+    }
+
+    fn unindex(&mut self, repo: &GitRepository) {
+        self.path_trie.remove_repository(&repo.path);
+        self.repo_index.remove_repository(repo);
+    }
+
+    fn index(&mut self, repo: &GitRepository) {
+        self.path_trie.insert_repository(&repo.path);
+        self.repo_index.insert_repository(repo);
+    }
+
+    pub fn put_repository(&mut self, repo: GitRepository) {
+        if let Some(old) = self.cache.repositories.remove(&repo.path) {
+            self.unindex(&old);
+        }
+        self.index(&repo);
+        self.dirty.repositories.insert(repo.path.clone());
+        self.cache.repositories.insert(repo.path.clone(), repo);
+        self.touch();
+    }
+
+    /// Apply `update` to a repository; returns the updated repository, or None if it is not cached
+    pub fn update_repository(&mut self, path: &str, update: impl FnOnce(&mut GitRepository)) -> Option<GitRepository> {
+        let mut repo = self.cache.repositories.get(path)?.clone();
+        update(&mut repo);
+        self.put_repository(repo.clone());
+        Some(repo)
+    }
+
+    pub fn remove_repository(&mut self, path: &str) -> Option<GitRepository> {
+        let repo = self.cache.repositories.remove(path)?;
+        self.unindex(&repo);
+        self.dirty.repositories.insert(path.to_string());
+        self.touch();
+        Some(repo)
+    }
+
+    pub fn put_scan_path(&mut self, scan_path: ScanPath) {
+        self.dirty.scan_paths.insert(scan_path.path.clone());
+        self.cache.scan_paths.insert(scan_path.path.clone(), scan_path);
+        self.touch();
+    }
+
+    pub fn update_scan_path(&mut self, path: &str, update: impl FnOnce(&mut ScanPath)) -> Option<ScanPath> {
+        let scan_path = update_entry(&mut self.cache.scan_paths, &path.to_string(), update)?;
+        self.dirty.scan_paths.insert(path.to_string());
+        self.touch();
+        Some(scan_path)
+    }
+
+    pub fn remove_scan_path(&mut self, path: &str) -> Option<ScanPath> {
+        let scan_path = self.cache.scan_paths.remove(path)?;
+        self.dirty.scan_paths.insert(path.to_string());
+        self.touch();
+        Some(scan_path)
+    }
+
+    pub fn set_scan_config(&mut self, config: ScanConfig) {
+        self.cache.scan_config = config;
+        self.dirty.scan_config = true;
+        self.touch();
+    }
+
+    pub fn put_collection(&mut self, collection: Collection) {
+        self.dirty.collections.insert(collection.id.clone());
+        self.cache.collections.insert(collection.id.clone(), collection);
+        self.touch();
+    }
+
+    pub fn update_collection(&mut self, id: &str, update: impl FnOnce(&mut Collection)) -> Option<Collection> {
+        let collection = update_entry(&mut self.cache.collections, &id.to_string(), update)?;
+        self.dirty.collections.insert(id.to_string());
+        self.touch();
+        Some(collection)
+    }
+
+    pub fn remove_collection(&mut self, id: &str) -> Option<Collection> {
+        let collection = self.cache.collections.remove(id)?;
+        self.dirty.collections.insert(id.to_string());
+        self.touch();
+        Some(collection)
+    }
+
+    pub fn put_cleanup_policy(&mut self, policy: CleanupPolicy) {
+        self.dirty.cleanup_policies.insert(policy.id.clone());
+        self.cache.cleanup_policies.insert(policy.id.clone(), policy);
+        self.touch();
+    }
+
+    pub fn update_cleanup_policy(&mut self, id: &str, update: impl FnOnce(&mut CleanupPolicy)) -> Option<CleanupPolicy> {
+        let policy = update_entry(&mut self.cache.cleanup_policies, &id.to_string(), update)?;
+        self.dirty.cleanup_policies.insert(id.to_string());
+        self.touch();
+        Some(policy)
+    }
+
+    pub fn remove_cleanup_policy(&mut self, id: &str) -> Option<CleanupPolicy> {
+        let policy = self.cache.cleanup_policies.remove(id)?;
+        self.dirty.cleanup_policies.insert(id.to_string());
+        self.touch();
+        Some(policy)
+    }
+
+    pub fn put_cleanup_report(&mut self, report: PolicyRunReport) {
+        self.dirty.cleanup_reports.insert(report.id.clone());
+        self.cache.cleanup_reports.insert(report.id.clone(), report);
+        self.touch();
+    }
+
+    pub fn remove_cleanup_report(&mut self, id: &str) -> Option<PolicyRunReport> {
+        let report = self.cache.cleanup_reports.remove(id)?;
+        self.dirty.cleanup_reports.insert(id.to_string());
+        self.touch();
+        Some(report)
+    }
+
+    /// Replace everything, e.g. when clearing the cache or restoring a backup
+    pub fn replace(&mut self, cache: RepositoryCache) {
+        self.cache = cache;
+        self.dirty = DirtyKeys { everything: true, ..DirtyKeys::default() };
+        self.touch();
+        self.rebuild_indexes();
+    }
+
+    pub fn has_changes(&self) -> bool {
+        let dirty = &self.dirty;
+        dirty.everything
+            || dirty.scan_config
+            || !dirty.repositories.is_empty()
+            || !dirty.scan_paths.is_empty()
+            || !dirty.collections.is_empty()
+            || !dirty.cleanup_policies.is_empty()
+            || !dirty.cleanup_reports.is_empty()
+    }
+
+    fn pending_changes(&self) -> Option<CacheChanges> {
+        if !self.has_changes() {
+            return None;
+        }
+        if self.dirty.everything {
+            return Some(CacheChanges::Replaced(self.cache.clone()));
+        }
+
+        Some(CacheChanges::Entries(CachePatch {
+            repositories: snapshot(&self.cache.repositories, &self.dirty.repositories),
+            scan_paths: snapshot(&self.cache.scan_paths, &self.dirty.scan_paths),
+            collections: snapshot(&self.cache.collections, &self.dirty.collections),
+            cleanup_policies: snapshot(&self.cache.cleanup_policies, &self.dirty.cleanup_policies),
+            cleanup_reports: snapshot(&self.cache.cleanup_reports, &self.dirty.cleanup_reports),
+            scan_config: self.dirty.scan_config.then(|| self.cache.scan_config.clone()),
+        }))
+    }
+
+    /// Take the unflushed changes to write them out
+    pub fn take_changes(&mut self) -> Option<CacheChanges> {
+        let changes = self.pending_changes();
+        self.dirty = DirtyKeys::default();
+        changes
+    }
+
+    /// Mark changes taken with `take_changes` as unflushed again after a failed write
+    pub fn restore_changes(&mut self, changes: &CacheChanges) {
+        match changes {
+            CacheChanges::Replaced(_) => self.dirty.everything = true,
+            CacheChanges::Entries(patch) => {
+                self.dirty.repositories.extend(patch.repositories.iter().map(|(key, _)| key.clone()));
+                self.dirty.scan_paths.extend(patch.scan_paths.iter().map(|(key, _)| key.clone()));
+                self.dirty.collections.extend(patch.collections.iter().map(|(key, _)| key.clone()));
+                self.dirty.cleanup_policies.extend(patch.cleanup_policies.iter().map(|(key, _)| key.clone()));
+                self.dirty.cleanup_reports.extend(patch.cleanup_reports.iter().map(|(key, _)| key.clone()));
+                self.dirty.scan_config |= patch.scan_config.is_some();
+            }
+        }
+    }
+
+    /// Adopt the cache another instance wrote to disk, keeping changes not flushed yet on top
+    pub fn rebase(&mut self, mut cache: RepositoryCache) {
+        if let Some(changes) = self.pending_changes() {
+            changes.apply_to(&mut cache);
+        }
+        self.cache = cache;
+        self.rebuild_indexes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(path: &str, name: &str) -> GitRepository {
+        GitRepository { name: name.to_string(), ..GitRepository::fixture(path) }
+    }
+
+    #[test]
+    fn test_mutations_keep_indexes_in_step() {
+        let mut store = MemoryStore::new(RepositoryCache::default());
+        store.put_repository(repo("/src/app", "app"));
+        store.update_repository("/src/app", |repo| repo.name = "renamed".to_string());
+
+        assert!(store.repo_index().find_repositories_by_name_prefix("app").is_empty());
+        assert_eq!(store.repo_index().find_repositories_by_name_prefix("ren"), vec!["/src/app"]);
+        assert_eq!(store.path_trie().find_repositories_under_path("/src"), vec!["/src/app"]);
+
+        store.remove_repository("/src/app");
+        assert!(store.path_trie().find_repositories_under_path("/src").is_empty());
+        assert!(store.repo_index().find_repositories_by_name_prefix("ren").is_empty());
+    }
+
+    #[test]
+    fn test_changes_are_taken_once_and_restored_after_failure() {
+        let mut store = MemoryStore::new(RepositoryCache::default());
+        store.put_repository(repo("/src/a", "a"));
+        store.put_repository(repo("/src/b", "b"));
+        store.remove_repository("/src/b");
+
+        let Some(CacheChanges::Entries(patch)) = store.take_changes() else {
+            panic!("expected a patch");
+        };
+        assert_eq!(patch.repositories.len(), 2);
+        assert!(patch.repositories.iter().any(|(path, repo)| path == "/src/b" && repo.is_none()));
+        assert!(!store.has_changes());
+
+        store.restore_changes(&CacheChanges::Entries(patch));
+        assert!(store.has_changes());
+    }
+
+    #[test]
+    fn test_rebase_keeps_local_changes_over_disk_contents() {
+        let mut disk = RepositoryCache::default();
+        disk.repositories.insert("/src/theirs".to_string(), repo("/src/theirs", "theirs"));
+        disk.repositories.insert("/src/shared".to_string(), repo("/src/shared", "old"));
+
+        let mut store = MemoryStore::new(RepositoryCache::default());
+        store.put_repository(repo("/src/shared", "new"));
+        store.put_repository(repo("/src/mine", "mine"));
+        store.rebase(disk);
+
+        let repositories = &store.cache().repositories;
+        assert_eq!(repositories.len(), 3);
+        assert_eq!(repositories["/src/shared"].name, "new");
+        assert_eq!(store.repo_index().find_repositories_by_name_prefix("theirs"), vec!["/src/theirs"]);
+        // Still unflushed, so the next flush writes them
+        assert!(store.has_changes());
+    }
+}
@@ -1,8 +1,5 @@
 use crate::repo_types::GitRepository;
-use std::collections::HashMap;
-use lru::LruCache;
-use std::num::NonZeroUsize;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct TrieNode {
@@ -99,15 +96,6 @@ impl PathTrie {
     }
 }
 
-// Thread-safe LRU cache for repositories
-pub type RepositoryCache = Arc<Mutex<LruCache<String, GitRepository>>>;
-
-pub fn create_repository_cache(capacity: usize) -> RepositoryCache {
-    Arc::new(Mutex::new(LruCache::new(
-        NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(1000).unwrap())
-    )))
-}
-
 // Repository index for fast searches
 #[derive(Debug)]
 pub struct RepositoryIndex {
@@ -115,6 +103,8 @@ pub struct RepositoryIndex {
     pub by_size_range: HashMap<u32, Vec<String>>, // size_mb_rounded -> repo_paths
     pub by_commit_count_range: HashMap<u32, Vec<String>>, // commit_count_range -> repo_paths
     pub by_file_type: HashMap<String, Vec<String>>, // file_extension -> repo_paths
+    pub by_remote_host: HashMap<String, Vec<String>>, // remote host (lowercase) -> repo_paths
+    pub by_remote_owner: HashMap<String, Vec<String>>, // remote owner/organization (lowercase) -> repo_paths
 }
 
 impl RepositoryIndex {
@@ -124,6 +114,8 @@ impl RepositoryIndex {
             by_size_range: HashMap::new(),
             by_commit_count_range: HashMap::new(),
             by_file_type: HashMap::new(),
+            by_remote_host: HashMap::new(),
+            by_remote_owner: HashMap::new(),
         }
     }
     
@@ -150,11 +142,39 @@ impl RepositoryIndex {
                 .or_insert_with(Vec::new)
                 .push(repo.path.clone());
         }
+        
+        // Index by remote host and owner (a repository is listed once even if several remotes match)
+        for host in Self::remote_hosts(repo) {
+            self.by_remote_host.entry(host)
+                .or_insert_with(Vec::new)
+                .push(repo.path.clone());
+        }
+        for owner in Self::remote_owners(repo) {
+            self.by_remote_owner.entry(owner)
+                .or_insert_with(Vec::new)
+                .push(repo.path.clone());
+        }
+    }
+    
+    fn remote_hosts(repo: &GitRepository) -> HashSet<String> {
+        repo.remotes.iter()
+            .filter_map(|remote| remote.parsed.as_ref()?.host.clone())
+            .collect()
+    }
+    
+    fn remote_owners(repo: &GitRepository) -> HashSet<String> {
+        repo.remotes.iter()
+            .filter_map(|remote| remote.parsed.as_ref()?.owner.as_ref().map(|owner| owner.to_lowercase()))
+            .collect()
     }
     
     // O(1) removal from all indices
     pub fn remove_repository(&mut self, repo: &GitRepository) {
-        self.by_name.remove(&repo.name.to_lowercase());
+        // Another repository with the same name may own the entry by now
+        let name = repo.name.to_lowercase();
+        if self.by_name.get(&name) == Some(&repo.path) {
+            self.by_name.remove(&name);
+        }
         
         let size_range = ((repo.size_mb / 50.0) as u32) * 50;
         if let Some(paths) = self.by_size_range.get_mut(&size_range) {
@@ -171,6 +191,18 @@ impl RepositoryIndex {
                 paths.retain(|path| path != &repo.path);
             }
         }
+        
+        for host in Self::remote_hosts(repo) {
+            if let Some(paths) = self.by_remote_host.get_mut(&host) {
+                paths.retain(|path| path != &repo.path);
+            }
+        }
+        
+        for owner in Self::remote_owners(repo) {
+            if let Some(paths) = self.by_remote_owner.get_mut(&owner) {
+                paths.retain(|path| path != &repo.path);
+            }
+        }
     }
     
     // Fast prefix search by name
@@ -189,6 +221,20 @@ impl RepositoryIndex {
             .unwrap_or_default()
     }
     
+    // Fast search by remote host, e.g. "github.com"
+    pub fn find_repositories_by_remote_host(&self, host: &str) -> Vec<String> {
+        self.by_remote_host.get(&host.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+    
+    // Fast search by remote owner (user or organization)
+    pub fn find_repositories_by_remote_owner(&self, owner: &str) -> Vec<String> {
+        self.by_remote_owner.get(&owner.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+    
     // Fast search by size range
     pub fn find_repositories_by_size_range(&self, min_mb: f64, max_mb: f64) -> Vec<String> {
         let min_range = ((min_mb / 50.0) as u32) * 50;
@@ -209,6 +255,8 @@ impl RepositoryIndex {
         self.by_size_range.clear();
         self.by_commit_count_range.clear();
         self.by_file_type.clear();
+        self.by_remote_host.clear();
+        self.by_remote_owner.clear();
     }
 }
 
@@ -229,12 +277,20 @@ mod tests {
             current_branch: None,
             branches: vec![],
             remote_url: None,
+            remotes: vec![],
             commit_count: 10,
+            head_oid: None,
+            working_tree_status: None,
+            kind: Default::default(),
+            parent_path: None,
+            worktrees: vec![],
+            submodules: vec![],
             last_analyzed: Utc::now(),
+            last_fetched: None,
             is_valid: true,
             is_pinned: false,
             pinned_at: None,
-            node_modules_info: None,
+            build_artifacts: None,
         }
     }
 
@@ -268,4 +324,28 @@ mod tests {
         let by_size = index.find_repositories_by_size_range(100.0, 200.0);
         assert_eq!(by_size.len(), 1);
     }
+
+    #[test]
+    fn test_repository_index_by_remote() {
+        let mut index = RepositoryIndex::new();
+        let mut repo = create_test_repo("fork", "/path/to/fork", 1.0);
+        repo.remotes = ["git@github.com:me/fork.git", "https://GitHub.com/Upstream-Org/fork"]
+            .iter()
+            .enumerate()
+            .map(|(i, url)| crate::repo_types::RemoteInfo {
+                name: if i == 0 { "origin" } else { "upstream" }.to_string(),
+                fetch_url: Some(url.to_string()),
+                push_url: Some(url.to_string()),
+                parsed: crate::remote_url::parse_remote_url(url),
+            })
+            .collect();
+
+        index.insert_repository(&repo);
+        assert_eq!(index.find_repositories_by_remote_host("github.com"), vec!["/path/to/fork"]);
+        assert_eq!(index.find_repositories_by_remote_owner("upstream-org"), vec!["/path/to/fork"]);
+        assert_eq!(index.find_repositories_by_remote_owner("me"), vec!["/path/to/fork"]);
+
+        index.remove_repository(&repo);
+        assert!(index.find_repositories_by_remote_host("github.com").is_empty());
+    }
 }
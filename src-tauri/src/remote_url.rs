@@ -0,0 +1,132 @@
+use crate::repo_types::ParsedRemoteUrl;
+
+/// Split a git remote URL into host, owner and repository name.
+///
+/// Handles `scheme://[user[:password]@]host[:port]/path` URLs (ssh, https, http, git, file),
+/// scp-like `[user@]host:path` SSH addresses and plain local paths. Everything between the
+/// host and the repository name is the owner, so GitLab subgroups come out as `group/subgroup`.
+pub fn parse_remote_url(url: &str) -> Option<ParsedRemoteUrl> {
+    let url = url.trim();
+    if url.is_empty() {
+        return None;
+    }
+
+    if let Some((scheme, rest)) = url.split_once("://") {
+        let scheme = scheme.to_lowercase();
+        // The ext transport (git+ssh, ssh+git) is plain ssh as far as hosts are concerned
+        let scheme = match scheme.as_str() {
+            "git+ssh" | "ssh+git" => "ssh".to_string(),
+            _ => scheme,
+        };
+
+        if scheme == "file" {
+            return Some(from_path("file", rest));
+        }
+
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        // Credentials may contain '@', the host never does
+        let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        return Some(from_host_and_path(&scheme, strip_port(host_port), path));
+    }
+
+    if let Some((user_host, path)) = split_scp_like(url) {
+        let host = user_host.rsplit_once('@').map_or(user_host, |(_, host)| host);
+        return Some(from_host_and_path("ssh", host, path));
+    }
+
+    Some(from_path("local", url))
+}
+
+// scp-like syntax has a ':' before the first '/', and is not a Windows drive letter (C:\repo)
+fn split_scp_like(url: &str) -> Option<(&str, &str)> {
+    let colon = url.find(':')?;
+    if url[..colon].contains('/') || url[..colon].contains('\\') {
+        return None;
+    }
+    if colon == 1 && url.as_bytes()[0].is_ascii_alphabetic() {
+        return None;
+    }
+    Some((&url[..colon], &url[colon + 1..]))
+}
+
+fn strip_port(host_port: &str) -> &str {
+    // Bracketed IPv6 literal, e.g. [::1]:2222
+    if let Some(rest) = host_port.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    host_port.split(':').next().unwrap_or(host_port)
+}
+
+fn from_host_and_path(scheme: &str, host: &str, path: &str) -> ParsedRemoteUrl {
+    let mut segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let repo_name = segments.pop().map(strip_git_suffix);
+    // ssh://host/~user/repo.git style home-directory paths
+    let owner = (!segments.is_empty())
+        .then(|| segments.iter().map(|segment| segment.trim_start_matches('~')).collect::<Vec<_>>().join("/"));
+
+    ParsedRemoteUrl {
+        scheme: scheme.to_string(),
+        host: (!host.is_empty()).then(|| host.to_lowercase()),
+        owner,
+        repo_name,
+    }
+}
+
+// Local remotes have no host or owner, only the repository directory name
+fn from_path(scheme: &str, path: &str) -> ParsedRemoteUrl {
+    let repo_name = path
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(strip_git_suffix);
+
+    ParsedRemoteUrl {
+        scheme: scheme.to_string(),
+        host: None,
+        owner: None,
+        repo_name,
+    }
+}
+
+fn strip_git_suffix(name: &str) -> String {
+    name.strip_suffix(".git").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(url: &str) -> (String, Option<String>, Option<String>, Option<String>) {
+        let parsed = parse_remote_url(url).unwrap();
+        (parsed.scheme, parsed.host, parsed.owner, parsed.repo_name)
+    }
+
+    fn expected(scheme: &str, host: Option<&str>, owner: Option<&str>, repo: Option<&str>) -> (String, Option<String>, Option<String>, Option<String>) {
+        (scheme.to_string(), host.map(str::to_string), owner.map(str::to_string), repo.map(str::to_string))
+    }
+
+    #[test]
+    fn test_parses_ssh_forms() {
+        assert_eq!(parts("git@github.com:rust-lang/cargo.git"), expected("ssh", Some("github.com"), Some("rust-lang"), Some("cargo")));
+        assert_eq!(parts("ssh://git@GitLab.example.com:2222/group/sub/project.git"), expected("ssh", Some("gitlab.example.com"), Some("group/sub"), Some("project")));
+        assert_eq!(parts("git+ssh://host.xz/~alice/repo"), expected("ssh", Some("host.xz"), Some("alice"), Some("repo")));
+        assert_eq!(parts("host.xz:repo.git"), expected("ssh", Some("host.xz"), None, Some("repo")));
+    }
+
+    #[test]
+    fn test_parses_http_and_git_protocols() {
+        assert_eq!(parts("https://github.com/tauri-apps/tauri"), expected("https", Some("github.com"), Some("tauri-apps"), Some("tauri")));
+        assert_eq!(parts("https://user:p@ss@bitbucket.org/team/app.git/"), expected("https", Some("bitbucket.org"), Some("team"), Some("app")));
+        assert_eq!(parts("git://[::1]:9418/mirror/linux.git"), expected("git", Some("::1"), Some("mirror"), Some("linux")));
+    }
+
+    #[test]
+    fn test_parses_local_paths() {
+        assert_eq!(parts("file:///srv/git/tools.git"), expected("file", None, None, Some("tools")));
+        assert_eq!(parts("/home/me/src/project/"), expected("local", None, None, Some("project")));
+        assert_eq!(parts("../upstream.git"), expected("local", None, None, Some("upstream")));
+        assert_eq!(parts("C:\\repos\\app.git"), expected("local", None, None, Some("app")));
+        assert!(parse_remote_url("  ").is_none());
+    }
+}
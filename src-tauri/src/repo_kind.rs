@@ -0,0 +1,230 @@
+use crate::repo_types::{GitRepository, RepositoryKind};
+use git2::Repository;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// How a repository relates to other repositories on disk
+pub struct RepositoryLayout {
+    pub kind: RepositoryKind,
+    pub parent_path: Option<String>,
+    pub worktrees: Vec<String>,
+}
+
+/// Classify `dir` by how it stores its git data, or None if it is not a repository root
+pub fn detect_kind(dir: &Path) -> Option<RepositoryKind> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(RepositoryKind::Normal);
+    }
+    if dot_git.is_file() {
+        return read_gitdir_file(dir).map(|git_dir| kind_for_linked_git_dir(&git_dir));
+    }
+    is_bare_repository(dir).then_some(RepositoryKind::Bare)
+}
+
+/// A bare repository keeps HEAD, objects and refs directly in its own directory
+pub fn is_bare_repository(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+/// Directory holding HEAD, index and refs for the repository rooted at `dir`
+pub fn git_dir(dir: &Path) -> Option<PathBuf> {
+    match detect_kind(dir)? {
+        RepositoryKind::Bare => Some(dir.to_path_buf()),
+        _ if dir.join(".git").is_dir() => Some(dir.join(".git")),
+        _ => read_gitdir_file(dir),
+    }
+}
+
+/// Resolve the `gitdir:` pointer of a `.git` file, as written for worktrees, submodules and --separate-git-dir
+fn read_gitdir_file(dir: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(dir.join(".git")).ok()?;
+    let target = content.lines().find_map(|line| line.strip_prefix("gitdir:"))?.trim();
+    // Relative pointers are relative to the working tree; absolute ones replace it
+    Some(dir.join(target))
+}
+
+fn kind_for_linked_git_dir(git_dir: &Path) -> RepositoryKind {
+    let names: Vec<&str> = git_dir.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    // Linked worktrees live in <common dir>/worktrees/<name>, submodules in <super>/.git/modules/<path>
+    if names.len() >= 2 && names[names.len() - 2] == "worktrees" {
+        RepositoryKind::Worktree
+    } else if names.windows(2).any(|pair| pair == [".git", "modules"]) {
+        RepositoryKind::Submodule
+    } else {
+        RepositoryKind::Normal
+    }
+}
+
+/// Work out the kind of an opened repository, the repository it belongs to and its linked worktrees
+pub fn describe(repo: &Repository, dir: &Path) -> RepositoryLayout {
+    let kind = detect_kind(dir).unwrap_or(if repo.is_bare() { RepositoryKind::Bare } else { RepositoryKind::Normal });
+
+    let parent_path = match kind {
+        // The common dir is the main repository's .git directory, or the bare repository itself
+        RepositoryKind::Worktree => common_dir(repo).map(|common_dir| {
+            let main_repo = if common_dir.file_name().is_some_and(|name| name == ".git") {
+                common_dir.parent().unwrap_or(&common_dir)
+            } else {
+                &common_dir
+            };
+            path_string(main_repo)
+        }),
+        // The superproject is the closest enclosing working tree
+        RepositoryKind::Submodule => dir.ancestors()
+            .skip(1)
+            .find(|ancestor| detect_kind(ancestor).is_some_and(|kind| kind != RepositoryKind::Bare))
+            .map(path_string),
+        _ => None,
+    };
+
+    let worktrees = if kind == RepositoryKind::Worktree {
+        Vec::new()
+    } else {
+        linked_worktrees(repo)
+    };
+
+    RepositoryLayout { kind, parent_path, worktrees }
+}
+
+/// A linked worktree's git dir records the shared repository directory in its `commondir` file
+fn common_dir(repo: &Repository) -> Option<PathBuf> {
+    let content = fs::read_to_string(repo.path().join("commondir")).ok()?;
+    let joined = repo.path().join(content.trim());
+
+    // Resolve the usual "../.." lexically so the result matches how paths are stored in the cache
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+fn linked_worktrees(repo: &Repository) -> Vec<String> {
+    let Ok(names) = repo.worktrees() else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<String> = names.iter()
+        .flatten()
+        .filter_map(|name| repo.find_worktree(name).ok())
+        // Skip worktrees whose directory was deleted without `git worktree remove`
+        .filter(|worktree| worktree.validate().is_ok())
+        .map(|worktree| path_string(worktree.path()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn path_string(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() { path.to_string() } else { trimmed.to_string() }
+}
+
+/// Reorder repositories so each linked worktree directly follows its main repository.
+/// Worktrees whose main repository is not in the list keep their position.
+pub fn group_worktrees(repos: Vec<GitRepository>) -> Vec<GitRepository> {
+    let paths: HashSet<String> = repos.iter().map(|repo| repo.path.clone()).collect();
+    let (worktrees, others): (Vec<_>, Vec<_>) = repos.into_iter().partition(|repo| {
+        repo.kind == RepositoryKind::Worktree
+            && repo.parent_path.as_ref().is_some_and(|parent| paths.contains(parent))
+    });
+
+    let mut by_parent: HashMap<String, Vec<GitRepository>> = HashMap::new();
+    for worktree in worktrees {
+        let parent = worktree.parent_path.clone().unwrap_or_default();
+        by_parent.entry(parent).or_default().push(worktree);
+    }
+
+    let mut grouped = Vec::with_capacity(paths.len());
+    for repo in others {
+        let children = by_parent.remove(&repo.path);
+        grouped.push(repo);
+        if let Some(mut children) = children {
+            children.sort_by(|a, b| a.path.cmp(&b.path));
+            grouped.extend(children);
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, temp_dir};
+
+    #[test]
+    fn test_detects_normal_bare_worktree_and_submodule_layouts() {
+        let root = temp_dir("repo-kind");
+        let main = root.join("main");
+        let repo = Repository::init(&main).unwrap();
+        commit_file(&repo, "README.md", "main");
+        repo.worktree("feature", &root.join("feature"), None).unwrap();
+        Repository::init_bare(root.join("mirror.git")).unwrap();
+
+        // Submodule checkouts point into the superproject's .git/modules
+        let submodule = main.join("libs/dep");
+        fs::create_dir_all(main.join(".git/modules/dep")).unwrap();
+        fs::create_dir_all(&submodule).unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/dep\n").unwrap();
+
+        // A --separate-git-dir that merely has a "modules" directory in its path is not a submodule
+        let separate = root.join("separate");
+        fs::create_dir_all(root.join("store/modules/separate")).unwrap();
+        fs::create_dir_all(&separate).unwrap();
+        fs::write(separate.join(".git"), "gitdir: ../store/modules/separate\n").unwrap();
+
+        assert_eq!(detect_kind(&main), Some(RepositoryKind::Normal));
+        assert_eq!(detect_kind(&root.join("feature")), Some(RepositoryKind::Worktree));
+        assert_eq!(detect_kind(&root.join("mirror.git")), Some(RepositoryKind::Bare));
+        assert_eq!(detect_kind(&submodule), Some(RepositoryKind::Submodule));
+        assert_eq!(detect_kind(&separate), Some(RepositoryKind::Normal));
+        assert_eq!(detect_kind(&root), None);
+
+        let main_layout = describe(&repo, &main);
+        assert_eq!(main_layout.worktrees, vec![path_string(&root.join("feature"))]);
+
+        let worktree = Repository::open(root.join("feature")).unwrap();
+        let layout = describe(&worktree, &root.join("feature"));
+        assert_eq!(layout.kind, RepositoryKind::Worktree);
+        assert_eq!(
+            fs::canonicalize(layout.parent_path.unwrap()).unwrap(),
+            fs::canonicalize(&main).unwrap()
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_group_worktrees_places_worktrees_after_their_parent() {
+        let repo = |path: &str, kind: RepositoryKind, parent: Option<&str>| GitRepository {
+            kind,
+            parent_path: parent.map(str::to_string),
+            ..GitRepository::fixture(path)
+        };
+
+        let grouped = group_worktrees(vec![
+            repo("/w/app-fix", RepositoryKind::Worktree, Some("/w/app")),
+            repo("/w/other", RepositoryKind::Normal, None),
+            repo("/w/orphan", RepositoryKind::Worktree, Some("/gone")),
+            repo("/w/app", RepositoryKind::Normal, None),
+        ]);
+
+        let order: Vec<&str> = grouped.iter().map(|repo| repo.path.as_str()).collect();
+        assert_eq!(order, vec!["/w/other", "/w/orphan", "/w/app", "/w/app-fix"]);
+    }
+}
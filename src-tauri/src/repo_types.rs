@@ -16,13 +16,156 @@ pub struct AnalysisProgress {
     pub current_path: String,
 }
 
+/// Kind of generated directory that can be deleted and rebuilt
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    NodeModules,  // node_modules next to package.json
+    CargoTarget,  // Rust target/ next to Cargo.toml
+    MavenTarget,  // target/ next to pom.xml
+    GradleBuild,  // build/ next to build.gradle
+    GradleCache,  // .gradle next to build.gradle
+    PythonVenv,   // .venv or venv virtual environments
+    PythonCache,  // __pycache__ and tool caches such as .pytest_cache
+    NextBuild,    // .next next to next.config.*
+    NuxtBuild,    // .nuxt and .output next to nuxt.config.*
+    JsDist,       // dist/ next to package.json
+    CmakeBuild,   // CMake build trees next to CMakeLists.txt
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NodeModulesInfo {
-    pub total_size_mb: f64,
-    pub count: u32,
+pub struct ArtifactKindInfo {
+    pub kind: ArtifactKind,
+    pub size_mb: f64,
     pub paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BuildArtifactsInfo {
+    pub total_size_mb: f64,
+    pub kinds: Vec<ArtifactKindInfo>, // Largest first
     pub last_scanned: DateTime<Utc>,
-    pub package_json_modified: DateTime<Utc>,
+    pub markers_modified: DateTime<Utc>, // Newest marker file (package.json, Cargo.toml, ...) at the last scan
+}
+
+/// What a build artifact cleanup does with the directories it finds
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupMode {
+    DryRun, // Only report what would be removed
+    Trash,  // Move to the freedesktop trash, so the cleanup can be undone; Linux and BSDs only
+    #[default]
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemovedArtifact {
+    pub kind: ArtifactKind,
+    pub path: String,
+    pub bytes: u64,
+    pub trashed_path: Option<String>, // Where the directory went in trash mode
+}
+
+/// Outcome of a build artifact cleanup; in dry-run mode, what it would remove
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ArtifactCleanup {
+    pub mode: CleanupMode,
+    pub removed: Vec<RemovedArtifact>,
+    pub total_bytes: u64,
+    pub errors: Vec<String>,
+    pub operation_id: Option<String>, // Log entry to undo a trash-mode cleanup with
+}
+
+/// Logged trash-mode cleanup
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CleanupOperation {
+    pub id: String,
+    pub repo_path: String,
+    pub performed_at: DateTime<Utc>,
+    pub artifacts: Vec<RemovedArtifact>,
+    pub undone_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub restorable: bool, // Not undone yet and all trashed directories still exist; set when listing
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UndoCleanupResult {
+    pub operation_id: String,
+    pub restored: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WorkingTreeStatus {
+    pub modified: u32,   // Tracked files changed or deleted in the working tree
+    pub staged: u32,     // Files with changes added to the index
+    pub untracked: u32,  // New files not yet added to the index
+    pub conflicted: u32, // Files with unresolved merge conflicts
+    pub renamed: u32,    // Files renamed in the index or working tree
+    pub is_dirty: bool,  // Whether any of the above is non-zero
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_remote: bool, // Remote-tracking branch (e.g. origin/main) rather than a local branch
+    pub is_head: bool,   // Whether this is the currently checked out branch
+    pub upstream: Option<String>, // Configured upstream of a local branch, e.g. "origin/main"
+    pub ahead: u32,  // Commits on this branch that are not on its upstream
+    pub behind: u32, // Commits on the upstream that are not on this branch
+}
+
+impl BranchInfo {
+    /// Branch entry without tracking information
+    pub fn from_name(name: String) -> Self {
+        Self {
+            name,
+            is_remote: false,
+            is_head: false,
+            upstream: None,
+            ahead: 0,
+            behind: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ParsedRemoteUrl {
+    pub scheme: String, // "ssh", "https", "http", "git", "file" or "local"
+    pub host: Option<String>, // Lowercased, without user or port; None for local remotes
+    pub owner: Option<String>, // User or organization, e.g. "rust-lang" or "group/subgroup"
+    pub repo_name: Option<String>, // Without the ".git" suffix
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub fetch_url: Option<String>,
+    pub push_url: Option<String>, // Explicit pushurl if configured, otherwise the fetch URL
+    pub parsed: Option<ParsedRemoteUrl>, // Parsed from the fetch URL
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubmoduleInfo {
+    pub name: String,
+    pub path: String, // Relative to the superproject's working tree
+    pub url: Option<String>,
+    pub recorded_commit: Option<String>,    // Commit the superproject pins the submodule to
+    pub checked_out_commit: Option<String>, // Commit currently checked out in the submodule, None if not cloned
+    pub is_uninitialized: bool, // Not cloned or not checked out yet
+    pub is_out_of_sync: bool,   // Checked out commit differs from the recorded one
+    pub is_modified: bool,      // Submodule working tree has uncommitted or untracked changes
+}
+
+/// How a repository stores its git data
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryKind {
+    #[default]
+    Normal,    // Working tree with its own .git directory
+    Bare,      // No working tree; the directory itself holds HEAD, objects and refs
+    Worktree,  // Linked worktree created with `git worktree add`
+    Submodule, // Submodule checkout whose git data lives in the superproject
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,15 +176,64 @@ pub struct GitRepository {
     pub file_types: HashMap<String, u32>, // extension -> count
     pub last_commit_date: Option<DateTime<Utc>>,
     pub current_branch: Option<String>,
-    pub branches: Vec<String>,
-    pub remote_url: Option<String>,
+    pub branches: Vec<BranchInfo>,
+    pub remote_url: Option<String>, // Fetch URL of origin, or of the first remote if there is no origin
+    #[serde(default)]
+    pub remotes: Vec<RemoteInfo>,
     pub commit_count: u32,
+    #[serde(default)]
+    pub head_oid: Option<String>, // HEAD commit at the last analysis, used to count commits incrementally
+    #[serde(default)]
+    pub working_tree_status: Option<WorkingTreeStatus>, // None when the status could not be read (e.g. bare repos)
+    #[serde(default)]
+    pub kind: RepositoryKind,
+    #[serde(default)]
+    pub parent_path: Option<String>, // Main repository of a worktree, or superproject of a submodule
+    #[serde(default)]
+    pub worktrees: Vec<String>, // Paths of linked worktrees (main repositories only)
+    #[serde(default)]
+    pub submodules: Vec<SubmoduleInfo>,
     // Persistence metadata
     pub last_analyzed: DateTime<Utc>,
+    #[serde(default)]
+    pub last_fetched: Option<DateTime<Utc>>, // Last fetch from gitmap in which at least one remote succeeded
     pub is_valid: bool, // Whether the repository still exists and is accessible
     pub is_pinned: bool, // Whether the repository is pinned
     pub pinned_at: Option<DateTime<Utc>>, // When it was pinned
-    pub node_modules_info: Option<NodeModulesInfo>,
+    #[serde(default)]
+    pub build_artifacts: Option<BuildArtifactsInfo>, // None when the repository has no build artifacts
+}
+
+#[cfg(test)]
+impl GitRepository {
+    /// Valid, unpinned entry for `path` with no git details; tests set what they need with
+    /// struct update syntax
+    pub fn fixture(path: &str) -> Self {
+        Self {
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.to_string(),
+            size_mb: 1.0,
+            file_types: HashMap::new(),
+            last_commit_date: None,
+            current_branch: None,
+            branches: Vec::new(),
+            remote_url: None,
+            remotes: Vec::new(),
+            commit_count: 1,
+            head_oid: None,
+            working_tree_status: None,
+            kind: RepositoryKind::Normal,
+            parent_path: None,
+            worktrees: Vec::new(),
+            submodules: Vec::new(),
+            last_analyzed: DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z").unwrap().with_timezone(&Utc),
+            last_fetched: None,
+            is_valid: true,
+            is_pinned: false,
+            pinned_at: None,
+            build_artifacts: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,6 +241,8 @@ pub struct ScanProgress {
     pub current_path: String,
     pub repos_found: u32,
     pub completed: bool,
+    #[serde(default)]
+    pub cancelled: bool, // Set on the final event when the scan was stopped by cancel_scan
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -71,6 +265,44 @@ pub struct ScanPath {
     pub path: String,
     pub last_scanned: Option<DateTime<Utc>>,
     pub repository_count: usize,
+    #[serde(default)]
+    pub overrides: Option<ScanConfig>, // Extra patterns and depth limit applied on top of the global config
+}
+
+/// Rules deciding which directories discovery descends into.
+/// Patterns use gitignore syntax and are matched relative to each scan path.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanConfig {
+    #[serde(default)]
+    pub include_patterns: Vec<String>, // Take precedence over exclude patterns, e.g. "vendor" or ".dotfiles"
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>, // Directories that are skipped entirely
+    #[serde(default)]
+    pub max_depth: Option<usize>, // Directory levels below the scan path; None for unlimited
+    #[serde(default)]
+    pub scan_concurrency: Option<usize>, // Repositories analyzed in parallel; None for one per core. Not used in overrides
+}
+
+/// Dependency and build output directories, plus tool caches under home directories that
+/// are full of checkouts nobody works in. Other dot directories (e.g. ".dotfiles") are scanned.
+pub const DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[
+    "node_modules", "vendor", "target", "build", "dist",
+    ".cache", ".npm", ".pnpm-store", ".yarn", ".cargo", ".rustup", ".gradle", ".m2",
+    ".nvm", ".pyenv", ".rbenv", ".gem", ".vscode", ".vscode-server", ".Trash", ".local/share/Trash",
+];
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            include_patterns: Vec::new(),
+            exclude_patterns: DEFAULT_EXCLUDE_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            max_depth: None,
+            scan_concurrency: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -81,3 +313,327 @@ pub struct Collection {
     pub repository_paths: Vec<String>, // Paths of repositories in this collection
     pub created_at: DateTime<Utc>,
 }
+
+/// What happened to one repository while restoring from a manifest
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreStatus {
+    Cloning, // Sent repeatedly while objects are received
+    Cloned,
+    Skipped, // Already present
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestoreProgress {
+    pub path: String, // Local path the repository is cloned to
+    pub current: usize, // 1-based position in the manifest
+    pub total: usize,
+    pub status: RestoreStatus,
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub error: Option<String>, // Set when status is Failed
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RestoreResult {
+    pub cloned: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<RestoreFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestoreFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Which repositories an operation across many repositories applies to
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepositorySelection {
+    Paths { paths: Vec<String> },
+    Collection { collection_id: String },
+    Search { filter: SearchFilter },
+}
+
+/// Same criteria as advanced_repository_search; all set criteria must match
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SearchFilter {
+    pub name_prefix: Option<String>,
+    pub min_size_mb: Option<f64>,
+    pub max_size_mb: Option<f64>,
+    pub file_type: Option<String>,
+    pub remote_host: Option<String>,
+    pub remote_owner: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of output from a command running in a repository
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommandOutput {
+    pub run_id: String,
+    pub repo_path: String,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandStatus {
+    Succeeded, // Exited with code 0
+    Failed,    // Exited with another code or was killed by a signal
+    Cancelled, // Stopped, or never started, because the run was cancelled
+    Error,     // Could not be started
+    Skipped,   // Not run because another repository in the run covers it
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommandResult {
+    pub run_id: String,
+    pub repo_path: String,
+    pub status: CommandStatus,
+    pub exit_code: Option<i32>, // None when killed by a signal or not run
+    pub duration_ms: u128,
+    pub error: Option<String>, // Set when status is Error
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkCommandSummary {
+    pub run_id: String,
+    pub results: Vec<CommandResult>, // In selection order
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+/// Transfer progress of one remote while fetching a repository
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FetchProgress {
+    pub repo_path: String,
+    pub remote: String,
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchStatus {
+    Fetched,  // Every remote was fetched
+    Failed,   // At least one remote failed; see errors
+    TimedOut, // Gave up waiting; remotes fetched before that are kept
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FetchResult {
+    pub repo_path: String,
+    pub status: FetchStatus,
+    pub fetched_remotes: Vec<String>,
+    pub errors: Vec<String>, // One per failed remote, prefixed with its name, then the timeout if any
+    pub duration_ms: u128,
+}
+
+/// Why a repository was left alone by a fast-forward pull
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FastForwardSkipReason {
+    Bare,
+    Detached,   // HEAD is not on a branch
+    Unborn,     // The branch has no commits yet
+    NoUpstream,
+    Dirty,      // Uncommitted changes to tracked files
+    UpToDate,
+    Diverged,   // The branch has commits the upstream does not, so a merge or rebase is needed
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FastForwardUpdate {
+    pub repo_path: String,
+    pub branch: String,
+    pub upstream: String,
+    pub from: String, // Commit ids before and after
+    pub to: String,
+    pub commits: usize, // Number of commits the branch moved forward
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FastForwardSkip {
+    pub repo_path: String,
+    pub reason: FastForwardSkipReason,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FastForwardFailure {
+    pub repo_path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FastForwardReport {
+    pub updated: Vec<FastForwardUpdate>,
+    pub skipped: Vec<FastForwardSkip>,
+    pub failed: Vec<FastForwardFailure>,
+}
+
+/// User-editable part of a cleanup policy
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CleanupPolicySettings {
+    pub name: String,
+    pub enabled: bool, // Disabled policies never run on their schedule
+    pub inactive_days: u32, // Last commit and last file change must both be older than this
+    pub kinds: Option<Vec<ArtifactKind>>, // None: every kind
+    pub exclude_pinned: bool,
+    pub exclude_collections: Vec<String>, // Collection ids
+    pub mode: CleanupMode,
+    pub interval_hours: Option<u32>, // Run automatically this often; None: on demand only
+}
+
+/// Rule for removing build artifacts from inactive repositories
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CleanupPolicy {
+    pub id: String,
+    pub settings: CleanupPolicySettings,
+    pub last_scheduled_run: Option<DateTime<Utc>>, // The schedule counts from here
+    pub last_manual_run: Option<DateTime<Utc>>, // Runs on demand do not move the schedule
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyMatch {
+    pub repo_path: String,
+    pub name: String,
+    pub last_commit_date: Option<DateTime<Utc>>,
+    pub last_modified: Option<DateTime<Utc>>, // Newest file outside .git and build artifacts
+    pub estimated_bytes: u64, // From the cached artifact sizes
+    pub kinds: Vec<ArtifactKind>,
+}
+
+/// Repositories a policy would clean right now
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyPreview {
+    pub policy_id: String,
+    pub evaluated_at: DateTime<Utc>,
+    pub matches: Vec<PolicyMatch>,
+    pub estimated_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyTrigger {
+    Manual,
+    Scheduled,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyRepositoryCleanup {
+    pub repo_path: String,
+    pub cleanup: ArtifactCleanup,
+}
+
+/// Record of one policy run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyRunReport {
+    pub id: String,
+    pub policy_id: String,
+    pub policy_name: String,
+    pub trigger: PolicyTrigger,
+    pub mode: CleanupMode,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub repositories: Vec<PolicyRepositoryCleanup>,
+    pub total_bytes: u64,
+    pub errors: Vec<String>, // Repositories that could not be cleaned at all
+}
+
+/// Space a repository takes on disk, in allocated bytes, by category
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DiskUsage {
+    pub repo_path: String,
+    pub working_tree_bytes: u64, // Tracked and untracked files that are not ignored
+    pub git_objects_bytes: u64,  // Packs and loose objects
+    pub git_other_bytes: u64,    // Rest of the git directory: index, refs, logs, submodule git dirs
+    pub lfs_bytes: u64,          // Git LFS object store
+    pub build_artifacts_bytes: u64,
+    pub ignored_bytes: u64, // Ignored files outside build artifacts
+    pub total_bytes: u64,
+    pub hardlinked_bytes: u64, // Further links to files already counted, not included in the total
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiskUsageFailure {
+    pub repo_path: String,
+    pub error: String,
+}
+
+/// Disk usage across many repositories
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WorkspaceDiskUsage {
+    pub totals: DiskUsage, // Sums over all analyzed repositories; repo_path is empty
+    pub top_repositories: Vec<DiskUsage>, // Largest first
+    pub repositories_analyzed: usize,
+    pub failures: Vec<DiskUsageFailure>,
+    pub cancelled: bool, // Stopped by cancel_bulk_command before every repository was analyzed
+}
+
+/// State of a repository's object database and what maintenance could reclaim
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitHealth {
+    pub repo_path: String,
+    pub git_dir_bytes: u64, // Allocated size of the whole (common) git directory
+    pub loose_objects: u64,
+    pub loose_objects_bytes: u64,
+    pub pack_count: usize,
+    pub packs_bytes: u64, // Pack files with their indexes, bitmaps and reverse indexes
+    pub reflog_bytes: u64,
+    pub gc_log: Option<String>, // Path of a gc.log left by a failed automatic gc
+    pub has_commit_graph: bool,
+    pub needs_maintenance: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    Gc,          // git gc: repack everything and prune old unreachable objects
+    Incremental, // git maintenance run with the cheap tasks: commit-graph, loose-objects, incremental-repack
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaintenanceResult {
+    pub command: CommandResult,
+    pub before_bytes: u64, // Git directory size before and after the run
+    pub after_bytes: u64,
+    pub maintained_with: Option<String>, // Set when skipped: the repository sharing this git directory that was maintained
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenancePhase {
+    Measuring,      // Measuring the git directory before running
+    Running,        // git gc or git maintenance is running
+    MeasuringAfter, // Measuring the git directory after the run
+}
+
+/// A repository moved to another step of maintenance. git prints no progress of its own
+/// when its output is not a terminal.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaintenanceProgress {
+    pub run_id: String,
+    pub repo_path: String,
+    pub phase: MaintenancePhase,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaintenanceSummary {
+    pub run_id: String,
+    pub task: MaintenanceTask,
+    pub results: Vec<MaintenanceResult>, // In selection order; worktrees sharing a git directory are skipped
+    pub freed_bytes: u64,
+}
@@ -0,0 +1,369 @@
+use crate::repo_kind;
+use crate::scan_rules::DiscoveryFilter;
+use crate::AppState;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use walkdir::WalkDir;
+
+// Quiet period after the last change before affected repositories are re-analyzed
+const DEBOUNCE: Duration = Duration::from_millis(750);
+// Upper bound on how long a continuous stream of events can postpone processing
+const MAX_DEBOUNCE: Duration = Duration::from_secs(5);
+// How often the watch list is rebuilt from the cache even without an explicit resync
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+// Directory levels below a scan root that are watched for new clones
+const ROOT_WATCH_DEPTH: usize = 3;
+
+// Entries inside .git whose changes affect what we show for a repository
+const GIT_WATCHED_ENTRIES: &[&str] = &["HEAD", "index", "refs", "packed-refs"];
+
+enum WatcherMessage {
+    Fs(notify::Result<Event>),
+    Resync,
+}
+
+/// Keeps cached repositories in sync with the filesystem.
+///
+/// Watches every known repository's `.git/HEAD`, `.git/index` and refs, plus the
+/// directories under each registered scan path so that new clones are discovered.
+/// Changes are debounced, only the affected repositories are re-analyzed, and a
+/// `repository-updated` event is emitted for each of them.
+pub struct RepositoryWatcher {
+    sender: Sender<WatcherMessage>,
+}
+
+impl RepositoryWatcher {
+    pub fn start(app: AppHandle) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel();
+
+        let fs_sender = sender.clone();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = fs_sender.send(WatcherMessage::Fs(event));
+        })
+        .map_err(|e| format!("Failed to create filesystem watcher: {}", e))?;
+
+        thread::Builder::new()
+            .name("repository-watcher".to_string())
+            .spawn(move || WatchLoop::new(app, watcher).run(receiver))
+            .map_err(|e| format!("Failed to start repository watcher: {}", e))?;
+
+        let _ = sender.send(WatcherMessage::Resync);
+        Ok(Self { sender })
+    }
+
+    /// Rebuild the watch list, e.g. after scan paths were added or a scan finished
+    pub fn resync(&self) {
+        let _ = self.sender.send(WatcherMessage::Resync);
+    }
+}
+
+/// Watched repositories, for mapping changed paths back to the repository they belong to
+#[derive(Default)]
+struct WatchedRepositories {
+    repo_roots: HashMap<PathBuf, String>, // repository root -> cached repository path
+    git_dirs: HashMap<PathBuf, String>,   // git directory (.git, a worktree's gitdir, or a bare repo) -> cached repository path
+}
+
+impl WatchedRepositories {
+    /// Map a changed path to the repository it belongs to, if it is one we care about
+    fn repository_for_git_path(&self, path: &Path) -> Option<String> {
+        for ancestor in path.ancestors() {
+            if let Some(repo_path) = self.git_dirs.get(ancestor) {
+                let relative = path.strip_prefix(ancestor).ok()?;
+
+                // The git directory itself, or one of the entries that affect branch and status info
+                return match relative.components().map(Component::as_os_str).next() {
+                    None => Some(repo_path.clone()),
+                    Some(entry) => {
+                        let is_lock_file = path.extension().is_some_and(|ext| ext == "lock");
+                        let watched_entry = entry.to_str().is_some_and(|entry| GIT_WATCHED_ENTRIES.contains(&entry));
+                        (watched_entry && !is_lock_file).then(|| repo_path.clone())
+                    }
+                };
+            }
+
+            if let Some(repo_path) = self.repo_roots.get(ancestor) {
+                // The repository directory itself was removed or renamed; other changes in
+                // the working tree are not tracked
+                return (ancestor == path).then(|| repo_path.clone());
+            }
+        }
+
+        None
+    }
+}
+
+/// Coalesces a burst of events: due once things were quiet for DEBOUNCE, or MAX_DEBOUNCE
+/// after the first event of a continuous stream
+#[derive(Default)]
+struct Debounce {
+    first_event: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl Debounce {
+    fn record(&mut self, now: Instant) {
+        self.first_event.get_or_insert(now);
+        self.last_event = Some(now);
+    }
+
+    fn is_pending(&self) -> bool {
+        self.first_event.is_some()
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        self.time_until_due(now).is_some_and(|wait| wait.is_zero())
+    }
+
+    // None when nothing is pending
+    fn time_until_due(&self, now: Instant) -> Option<Duration> {
+        let (first, last) = self.first_event.zip(self.last_event)?;
+        let deadline = (last + DEBOUNCE).min(first + MAX_DEBOUNCE);
+        Some(deadline.saturating_duration_since(now))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+struct WatchLoop {
+    app: AppHandle,
+    watcher: RecommendedWatcher,
+    watched: HashMap<PathBuf, RecursiveMode>,
+    repositories: WatchedRepositories,
+    scan_roots: Vec<PathBuf>,
+    pending_repos: HashSet<String>,
+    pending_dirs: HashSet<PathBuf>,
+    debounce: Debounce,
+    last_resync: Instant,
+}
+
+impl WatchLoop {
+    fn new(app: AppHandle, watcher: RecommendedWatcher) -> Self {
+        Self {
+            app,
+            watcher,
+            watched: HashMap::new(),
+            repositories: WatchedRepositories::default(),
+            scan_roots: Vec::new(),
+            pending_repos: HashSet::new(),
+            pending_dirs: HashSet::new(),
+            debounce: Debounce::default(),
+            last_resync: Instant::now(),
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<WatcherMessage>) {
+        loop {
+            match receiver.recv_timeout(self.next_timeout()) {
+                Ok(WatcherMessage::Fs(Ok(event))) => self.handle_event(event),
+                Ok(WatcherMessage::Fs(Err(e))) => eprintln!("Filesystem watcher error: {}", e),
+                Ok(WatcherMessage::Resync) => self.sync_watches(),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if self.debounce.is_due(Instant::now()) {
+                self.process_pending();
+                self.sync_watches();
+            } else if !self.debounce.is_pending() && self.last_resync.elapsed() >= RESYNC_INTERVAL {
+                self.sync_watches();
+            }
+        }
+    }
+
+    fn next_timeout(&self) -> Duration {
+        self.debounce.time_until_due(Instant::now())
+            .unwrap_or_else(|| RESYNC_INTERVAL.saturating_sub(self.last_resync.elapsed()))
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        let mut relevant = false;
+
+        for path in &event.paths {
+            if let Some(repo_path) = self.repositories.repository_for_git_path(path) {
+                self.pending_repos.insert(repo_path);
+                relevant = true;
+            } else if self.is_new_directory_event(&event.kind, path) {
+                self.pending_dirs.insert(path.clone());
+                relevant = true;
+            }
+        }
+
+        if relevant {
+            self.debounce.record(Instant::now());
+        }
+    }
+
+    fn is_new_directory_event(&self, kind: &EventKind, path: &Path) -> bool {
+        let created = matches!(kind, EventKind::Create(_) | EventKind::Modify(notify::event::ModifyKind::Name(_)));
+        created && path.is_dir() && self.scan_roots.iter().any(|root| path.starts_with(root))
+    }
+
+    fn process_pending(&mut self) {
+        let pending_repos: Vec<String> = self.pending_repos.drain().collect();
+        let pending_dirs: Vec<PathBuf> = self.pending_dirs.drain().collect();
+        self.debounce.reset();
+
+        // Analyze on a detached scanner, so a running scan or command does not hold us up
+        let state = self.app.state::<AppState>();
+        let mut scanner = state.scanner.blocking_lock().detached();
+
+        for repo_path in pending_repos {
+            let result = if repo_kind::detect_kind(Path::new(&repo_path)).is_some() {
+                scanner.update_repository(&repo_path).map(Some)
+            } else {
+                scanner.mark_repository_invalid(&repo_path)
+            };
+
+            match result {
+                Ok(Some(repo)) => {
+                    let _ = self.app.emit("repository-updated", repo);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to update repository {}: {}", repo_path, e),
+            }
+        }
+
+        for dir in pending_dirs {
+            match scanner.add_new_repositories_under(&dir) {
+                Ok(new_repos) => {
+                    for repo in new_repos {
+                        let _ = self.app.emit("repository-updated", repo);
+                    }
+                }
+                Err(e) => eprintln!("Failed to discover repositories under {:?}: {}", dir, e),
+            }
+        }
+    }
+
+    /// Bring the set of watched paths in line with the current cache contents
+    fn sync_watches(&mut self) {
+        self.last_resync = Instant::now();
+
+        let cache = match self.app.state::<AppState>().data_store.load_cache() {
+            Ok(cache) => cache,
+            Err(e) => {
+                eprintln!("Repository watcher could not load cache: {}", e);
+                return;
+            }
+        };
+
+        let mut desired: HashMap<PathBuf, RecursiveMode> = HashMap::new();
+        let mut repo_roots = HashMap::new();
+        let mut git_dirs = HashMap::new();
+
+        for (path, repo) in &cache.repositories {
+            let Some(git_dir) = repo.is_valid.then(|| repo_kind::git_dir(Path::new(path))).flatten() else {
+                continue;
+            };
+            if !git_dir.is_dir() {
+                continue;
+            }
+
+            desired.insert(git_dir.clone(), RecursiveMode::NonRecursive);
+            if git_dir.join("refs").is_dir() {
+                desired.insert(git_dir.join("refs"), RecursiveMode::Recursive);
+            }
+            repo_roots.insert(PathBuf::from(path), path.clone());
+            git_dirs.insert(git_dir, path.clone());
+        }
+
+        self.scan_roots = cache.scan_paths.keys().map(PathBuf::from).collect();
+        for root in &self.scan_roots {
+            // Same exclude/include rules as discovery, but never inside known repositories
+            let filter = DiscoveryFilter::for_root(root, &cache);
+            let max_depth = filter.max_depth().map_or(ROOT_WATCH_DEPTH, |depth| depth.min(ROOT_WATCH_DEPTH));
+
+            let walker = WalkDir::new(root)
+                .max_depth(max_depth)
+                .into_iter()
+                .filter_entry(|e| {
+                    e.file_type().is_dir()
+                        && filter.should_descend(e)
+                        && !repo_roots.contains_key(e.path())
+                        && !repo_kind::is_bare_repository(e.path())
+                });
+
+            for entry in walker.flatten() {
+                desired.insert(entry.into_path(), RecursiveMode::NonRecursive);
+            }
+        }
+
+        let stale: Vec<PathBuf> = self.watched.iter()
+            .filter(|(path, mode)| desired.get(*path) != Some(*mode))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in stale {
+            // Fails harmlessly when the path is already gone
+            let _ = self.watcher.unwatch(&path);
+            self.watched.remove(&path);
+        }
+
+        for (path, mode) in desired {
+            if self.watched.contains_key(&path) {
+                continue;
+            }
+            match self.watcher.watch(&path, mode) {
+                Ok(()) => {
+                    self.watched.insert(path, mode);
+                }
+                Err(e) => eprintln!("Failed to watch {:?}: {}", path, e),
+            }
+        }
+
+        self.repositories = WatchedRepositories { repo_roots, git_dirs };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_git_metadata_changes_map_to_their_repository() {
+        let repositories = WatchedRepositories {
+            repo_roots: HashMap::from([(PathBuf::from("/src/app"), "/src/app".to_string())]),
+            git_dirs: HashMap::from([(PathBuf::from("/src/app/.git"), "/src/app".to_string())]),
+        };
+        let repo_for = |path: &str| repositories.repository_for_git_path(Path::new(path));
+
+        assert_eq!(repo_for("/src/app/.git/refs/heads/x"), Some("/src/app".to_string()));
+        assert_eq!(repo_for("/src/app/.git/HEAD"), Some("/src/app".to_string()));
+        assert_eq!(repo_for("/src/app"), Some("/src/app".to_string()));
+        assert_eq!(repo_for("/src/app/.git/index.lock"), None);
+        assert_eq!(repo_for("/src/app/.git/refs/heads/x.lock"), None);
+        assert_eq!(repo_for("/src/app/.git/objects/ab/cdef"), None);
+        assert_eq!(repo_for("/src/app/src/main.rs"), None);
+        assert_eq!(repo_for("/src/other/.git/HEAD"), None);
+    }
+
+    #[test]
+    fn test_debounce_waits_for_quiet_but_not_forever() {
+        let start = Instant::now();
+        let ms = |n: u64| start + Duration::from_millis(n);
+        let mut debounce = Debounce::default();
+        assert_eq!(debounce.time_until_due(start), None);
+
+        debounce.record(start);
+        debounce.record(ms(500));
+        assert!(!debounce.is_due(ms(1000)));
+        assert!(debounce.is_due(ms(1250)));
+
+        // A continuous stream of events is processed after MAX_DEBOUNCE anyway
+        let mut debounce = Debounce::default();
+        for n in (0..=5000).step_by(500) {
+            debounce.record(ms(n));
+        }
+        assert!(debounce.is_due(ms(5000)));
+
+        debounce.reset();
+        assert!(!debounce.is_pending());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::data_store::RepositoryCache;
+use crate::repo_kind;
+use crate::repo_types::{RepositoryKind, ScanConfig};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// Compiled include/exclude rules for walking a single scan path
+pub struct DiscoveryFilter {
+    root: PathBuf,
+    matcher: Gitignore,
+    max_depth: Option<usize>,
+}
+
+impl DiscoveryFilter {
+    /// Build the filter for `root` from the global config plus the scan path's own overrides.
+    /// Override patterns are added last, so they win over global ones; an override depth replaces the global one.
+    pub fn new(root: &Path, config: &ScanConfig, overrides: Option<&ScanConfig>) -> Result<Self, String> {
+        let mut builder = GitignoreBuilder::new(root);
+
+        let mut exclude_patterns = config.exclude_patterns.iter().collect::<Vec<_>>();
+        let mut include_patterns = config.include_patterns.iter().collect::<Vec<_>>();
+        if let Some(overrides) = overrides {
+            exclude_patterns.extend(&overrides.exclude_patterns);
+            include_patterns.extend(&overrides.include_patterns);
+        }
+
+        for pattern in exclude_patterns {
+            builder.add_line(None, pattern)
+                .map_err(|e| format!("Invalid exclude pattern '{}': {}", pattern, e))?;
+        }
+        // Includes are whitelist entries, which gitignore lets override any earlier exclude
+        for pattern in include_patterns {
+            let pattern = pattern.trim_start_matches('!');
+            builder.add_line(None, &format!("!{}", pattern))
+                .map_err(|e| format!("Invalid include pattern '{}': {}", pattern, e))?;
+        }
+
+        let matcher = builder.build()
+            .map_err(|e| format!("Failed to compile scan rules: {}", e))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            matcher,
+            max_depth: overrides.and_then(|o| o.max_depth).or(config.max_depth),
+        })
+    }
+
+    /// Filter for `root` using the rules stored in the cache. Invalid stored rules fall back to the defaults.
+    pub fn for_root(root: &Path, cache: &RepositoryCache) -> Self {
+        let overrides = cache.scan_paths
+            .get(root.to_string_lossy().as_ref())
+            .and_then(|scan_path| scan_path.overrides.as_ref());
+
+        Self::new(root, &cache.scan_config, overrides).unwrap_or_else(|e| {
+            eprintln!("Falling back to default scan rules for {:?}: {}", root, e);
+            Self::new(root, &ScanConfig::default(), None)
+                .expect("default scan rules are valid")
+        })
+    }
+
+    /// Filter for an arbitrary directory, using the rules of the scan path that contains it
+    pub fn for_path(path: &Path, cache: &RepositoryCache) -> Self {
+        let root = cache.scan_paths
+            .keys()
+            .map(PathBuf::from)
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .unwrap_or_else(|| path.to_path_buf());
+
+        Self::for_root(&root, cache)
+    }
+
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Whether the walker should enter (or yield) this entry
+    pub fn should_descend(&self, entry: &DirEntry) -> bool {
+        // The scan path itself is always walked, whatever its name
+        if entry.depth() == 0 || !entry.file_type().is_dir() {
+            return true;
+        }
+
+        // Never walk into git internals
+        if entry.file_name() == ".git" {
+            return false;
+        }
+
+        !matches!(self.matcher.matched(entry.path(), true), Match::Ignore(_))
+    }
+
+    /// Repository roots below `start` (the root or a directory below it), skipping excluded
+    /// directories and honoring the depth limit relative to the root. Bare repositories are
+    /// reported but not walked into, since their contents are git internals.
+    pub fn repositories<'a>(&'a self, start: &Path) -> impl Iterator<Item = PathBuf> + 'a {
+        let start_depth = start.strip_prefix(&self.root)
+            .map(|relative| relative.components().count())
+            .unwrap_or(0);
+
+        let mut walker = WalkDir::new(start);
+        if let Some(max_depth) = self.max_depth {
+            walker = walker.max_depth(max_depth.saturating_sub(start_depth));
+        }
+
+        let mut entries = walker
+            .into_iter()
+            .filter_entry(move |e| e.file_type().is_dir() && self.should_descend(e));
+
+        std::iter::from_fn(move || loop {
+            let entry = match entries.next()? {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            match repo_kind::detect_kind(entry.path()) {
+                Some(kind) => {
+                    if kind == RepositoryKind::Bare {
+                        entries.skip_current_dir();
+                    }
+                    return Some(entry.into_path());
+                }
+                None => continue,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::fs;
+
+    fn create_tree(dirs: &[&str]) -> PathBuf {
+        let root = temp_dir("scan-rules");
+        for dir in dirs {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        root
+    }
+
+    fn found_repos(filter: &DiscoveryFilter) -> Vec<String> {
+        let mut repos: Vec<String> = filter.repositories(&filter.root)
+            .map(|path| path.strip_prefix(&filter.root).unwrap().to_string_lossy().to_string())
+            .collect();
+        repos.sort();
+        repos
+    }
+
+    #[test]
+    fn test_default_rules_skip_dependency_and_cache_directories() {
+        let root = create_tree(&[
+            "app/.git", "vendor/lib/.git", "node_modules/pkg/.git", ".dotfiles/.git",
+            ".cache/tool/.git", ".cargo/git/checkouts/dep/.git",
+        ]);
+        let filter = DiscoveryFilter::new(&root, &ScanConfig::default(), None).unwrap();
+
+        assert_eq!(found_repos(&filter), vec![".dotfiles", "app"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_include_patterns_and_overrides_reenable_excluded_directories() {
+        let root = create_tree(&["app/.git", "vendor/lib/.git", ".cache/tool/.git", "deep/a/b/c/.git"]);
+
+        let mut config = ScanConfig::default();
+        config.include_patterns.push("vendor".to_string());
+        let overrides = ScanConfig {
+            include_patterns: vec![".cache".to_string()],
+            exclude_patterns: vec!["deep".to_string()],
+            ..ScanConfig::default()
+        };
+        let filter = DiscoveryFilter::new(&root, &config, Some(&overrides)).unwrap();
+
+        assert_eq!(found_repos(&filter), vec![".cache/tool", "app", "vendor/lib"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_max_depth_limits_walk() {
+        let root = create_tree(&["app/.git", "group/nested/.git"]);
+        let config = ScanConfig { max_depth: Some(1), ..ScanConfig::default() };
+        let filter = DiscoveryFilter::new(&root, &config, None).unwrap();
+
+        assert_eq!(found_repos(&filter), vec!["app"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
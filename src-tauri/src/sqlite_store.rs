@@ -0,0 +1,477 @@
+use crate::cache_migrations;
+use crate::data_store::{RepositoryCache, CACHE_VERSION};
+use crate::memory_store::CacheChanges;
+use crate::repo_types::{CleanupPolicy, Collection, GitRepository, PolicyRunReport, ScanPath};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+// How long to wait for another app instance to finish writing before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS repositories (
+        path TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        is_pinned INTEGER NOT NULL DEFAULT 0,
+        is_valid INTEGER NOT NULL DEFAULT 1,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS repositories_pinned ON repositories (is_pinned);
+    CREATE TABLE IF NOT EXISTS scan_paths (
+        path TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS collections (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        color TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS collection_members (
+        collection_id TEXT NOT NULL REFERENCES collections (id) ON DELETE CASCADE,
+        repo_path TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (collection_id, repo_path)
+    );
+    CREATE TABLE IF NOT EXISTS cleanup_policies (
+        id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS cleanup_reports (
+        id TEXT PRIMARY KEY,
+        policy_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// SQLite storage engine for the repository cache.
+///
+/// Repositories and scan paths are stored as one JSON document per row, with the columns
+/// needed for lookups split out, so a single pin toggle or collection edit only touches
+/// the affected rows instead of rewriting the whole cache.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open database {:?}: {}", path, e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .and_then(|_| conn.execute_batch("PRAGMA foreign_keys = ON;"))
+            .and_then(|_| conn.execute_batch(SCHEMA))
+            .map_err(|e| format!("Failed to initialize database schema: {}", e))?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        self.conn.lock().map_err(|_| "Database connection lock poisoned".to_string())
+    }
+
+    /// Run `f` in a transaction that also bumps `last_updated`. The write lock is taken up
+    /// front, so reads inside `f` cannot be invalidated by another instance writing meanwhile.
+    fn write<T>(&self, f: impl FnOnce(&Transaction) -> Result<T, String>) -> Result<T, String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(db_error)?;
+        let result = f(&tx)?;
+        set_meta(&tx, "last_updated", &Utc::now().to_rfc3339())?;
+        tx.commit().map_err(db_error)?;
+        Ok(result)
+    }
+
+    pub fn load_cache(&self) -> Result<RepositoryCache, String> {
+        let (cache, migrated) = read_cache(&*self.conn()?)?;
+        if migrated {
+            // Rows written by an older version are rewritten once in the current format
+            self.replace_all(&cache)?;
+        }
+        Ok(cache)
+    }
+
+    /// Version the database was last written in, without loading or migrating anything
+    pub fn cache_version(&self) -> Result<Option<String>, String> {
+        get_meta(&*self.conn()?, "cache_version")
+    }
+
+    /// Replace the whole contents with `cache`, e.g. for the JSON import or a full refresh
+    pub fn replace_all(&self, cache: &RepositoryCache) -> Result<(), String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(db_error)?;
+        write_cache(&tx, cache)?;
+        tx.commit().map_err(db_error)
+    }
+
+    /// Write the changes made in memory since the last flush. A patch only touches the
+    /// affected rows; a replaced cache rewrites everything.
+    pub fn apply_changes(&self, changes: &CacheChanges) -> Result<(), String> {
+        let patch = match changes {
+            CacheChanges::Replaced(cache) => return self.replace_all(cache),
+            CacheChanges::Entries(patch) => patch,
+        };
+
+        self.write(|tx| {
+            for (path, repo) in &patch.repositories {
+                match repo {
+                    Some(repo) => upsert_repository(tx, repo)?,
+                    None => delete_row(tx, "DELETE FROM repositories WHERE path = ?1", path)?,
+                }
+            }
+            for (path, scan_path) in &patch.scan_paths {
+                match scan_path {
+                    Some(scan_path) => upsert_scan_path(tx, scan_path)?,
+                    None => delete_row(tx, "DELETE FROM scan_paths WHERE path = ?1", path)?,
+                }
+            }
+            for (id, collection) in &patch.collections {
+                // Members are rewritten along with the collection; ON DELETE CASCADE drops the old ones
+                delete_row(tx, "DELETE FROM collections WHERE id = ?1", id)?;
+                if let Some(collection) = collection {
+                    insert_collection(tx, collection)?;
+                    for path in &collection.repository_paths {
+                        add_collection_member(tx, id, path)?;
+                    }
+                }
+            }
+            for (id, policy) in &patch.cleanup_policies {
+                match policy {
+                    Some(policy) => upsert_cleanup_policy(tx, policy)?,
+                    None => delete_row(tx, "DELETE FROM cleanup_policies WHERE id = ?1", id)?,
+                }
+            }
+            for (id, report) in &patch.cleanup_reports {
+                match report {
+                    Some(report) => upsert_cleanup_report(tx, report)?,
+                    None => delete_row(tx, "DELETE FROM cleanup_reports WHERE id = ?1", id)?,
+                }
+            }
+            if let Some(config) = &patch.scan_config {
+                set_meta(tx, "scan_config", &to_json(config)?)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+// Returns the cache and whether its repository rows or scan config had to be migrated
+fn read_cache(conn: &Connection) -> Result<(RepositoryCache, bool), String> {
+    let mut cache = RepositoryCache::default();
+    let version = get_meta(conn, "cache_version")?.unwrap_or_else(|| CACHE_VERSION.to_string());
+
+    // Repository rows and the scan config are read as raw JSON so they can go through the
+    // same migrations as the JSON cache file
+    let mut repositories = Map::new();
+    let mut stmt = conn.prepare("SELECT path, data FROM repositories").map_err(db_error)?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(db_error)?;
+    for row in rows {
+        let (path, data) = row.map_err(db_error)?;
+        repositories.insert(path, from_json::<Value>(&data)?);
+    }
+
+    let scan_config = get_meta(conn, "scan_config")?
+        .map(|config| from_json::<Value>(&config))
+        .transpose()?;
+
+    let mut document = json!({ "cache_version": version, "repositories": repositories });
+    if let Some(scan_config) = scan_config {
+        document["scan_config"] = scan_config;
+    }
+    let migrated = cache_migrations::migrate(&mut document)?;
+    cache.repositories = serde_json::from_value(document["repositories"].take())
+        .map_err(|e| format!("Failed to parse database row: {}", e))?;
+    if !document["scan_config"].is_null() {
+        cache.scan_config = serde_json::from_value(document["scan_config"].take())
+            .map_err(|e| format!("Failed to parse database row: {}", e))?;
+    }
+    cache.cache_version = document["cache_version"].as_str().unwrap_or(CACHE_VERSION).to_string();
+
+    let mut stmt = conn.prepare("SELECT data FROM scan_paths").map_err(db_error)?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(db_error)?;
+    for data in rows {
+        let scan_path: ScanPath = from_json(&data.map_err(db_error)?)?;
+        cache.scan_paths.insert(scan_path.path.clone(), scan_path);
+    }
+
+    for collection in load_collections(conn)? {
+        cache.collections.insert(collection.id.clone(), collection);
+    }
+
+    let mut stmt = conn.prepare("SELECT data FROM cleanup_policies").map_err(db_error)?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(db_error)?;
+    for data in rows {
+        let policy: CleanupPolicy = from_json(&data.map_err(db_error)?)?;
+        cache.cleanup_policies.insert(policy.id.clone(), policy);
+    }
+
+    let mut stmt = conn.prepare("SELECT data FROM cleanup_reports").map_err(db_error)?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(db_error)?;
+    for data in rows {
+        let report: PolicyRunReport = from_json(&data.map_err(db_error)?)?;
+        cache.cleanup_reports.insert(report.id.clone(), report);
+    }
+
+    if let Some(last_updated) = get_meta(conn, "last_updated")? {
+        cache.last_updated = parse_time(&last_updated)?;
+    }
+
+    Ok((cache, migrated))
+}
+
+fn write_cache(tx: &Transaction, cache: &RepositoryCache) -> Result<(), String> {
+    tx.execute_batch("
+        DELETE FROM collection_members;
+        DELETE FROM collections;
+        DELETE FROM cleanup_policies;
+        DELETE FROM cleanup_reports;
+        DELETE FROM scan_paths;
+        DELETE FROM repositories;
+    ").map_err(db_error)?;
+
+    for repo in cache.repositories.values() {
+        upsert_repository(tx, repo)?;
+    }
+    for scan_path in cache.scan_paths.values() {
+        upsert_scan_path(tx, scan_path)?;
+    }
+    for collection in cache.collections.values() {
+        insert_collection(tx, collection)?;
+        for path in &collection.repository_paths {
+            add_collection_member(tx, &collection.id, path)?;
+        }
+    }
+
+    for policy in cache.cleanup_policies.values() {
+        upsert_cleanup_policy(tx, policy)?;
+    }
+    for report in cache.cleanup_reports.values() {
+        upsert_cleanup_report(tx, report)?;
+    }
+
+    set_meta(tx, "scan_config", &to_json(&cache.scan_config)?)?;
+    set_meta(tx, "last_updated", &cache.last_updated.to_rfc3339())?;
+    set_meta(tx, "cache_version", &cache.cache_version)
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("Failed to serialize database row: {}", e))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: &str) -> Result<T, String> {
+    serde_json::from_str(data).map_err(|e| format!("Failed to parse database row: {}", e))
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid timestamp '{}' in database: {}", value, e))
+}
+
+fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(db_error)
+}
+
+fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn upsert_repository(conn: &Connection, repo: &GitRepository) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO repositories (path, name, is_pinned, is_valid, data) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (path) DO UPDATE SET
+             name = excluded.name, is_pinned = excluded.is_pinned,
+             is_valid = excluded.is_valid, data = excluded.data",
+        params![repo.path, repo.name, repo.is_pinned, repo.is_valid, to_json(repo)?],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn upsert_scan_path(conn: &Connection, scan_path: &ScanPath) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO scan_paths (path, data) VALUES (?1, ?2)
+         ON CONFLICT (path) DO UPDATE SET data = excluded.data",
+        params![scan_path.path, to_json(scan_path)?],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn upsert_cleanup_policy(conn: &Connection, policy: &CleanupPolicy) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO cleanup_policies (id, data) VALUES (?1, ?2)
+         ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+        params![policy.id, to_json(policy)?],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn upsert_cleanup_report(conn: &Connection, report: &PolicyRunReport) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO cleanup_reports (id, policy_id, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT (id) DO UPDATE SET policy_id = excluded.policy_id, data = excluded.data",
+        params![report.id, report.policy_id, to_json(report)?],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn insert_collection(conn: &Connection, collection: &Collection) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO collections (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![collection.id, collection.name, collection.color, collection.created_at.to_rfc3339()],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn delete_row(conn: &Connection, sql: &str, key: &str) -> Result<(), String> {
+    conn.execute(sql, [key]).map_err(db_error)?;
+    Ok(())
+}
+
+fn add_collection_member(conn: &Connection, collection_id: &str, repo_path: &str) -> Result<(), String> {
+    // Members keep the order they were added in, like the JSON store's repository_paths
+    conn.execute(
+        "INSERT OR IGNORE INTO collection_members (collection_id, repo_path, position)
+         SELECT ?1, ?2, COALESCE(MAX(position), 0) + 1 FROM collection_members WHERE collection_id = ?1",
+        [collection_id, repo_path],
+    ).map_err(db_error)?;
+    Ok(())
+}
+
+fn load_collections(conn: &Connection) -> Result<Vec<Collection>, String> {
+    let mut members: HashMap<String, Vec<String>> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT collection_id, repo_path FROM collection_members ORDER BY position")
+        .map_err(db_error)?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(db_error)?;
+    for row in rows {
+        let (collection_id, repo_path) = row.map_err(db_error)?;
+        members.entry(collection_id).or_default().push(repo_path);
+    }
+
+    let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM collections").map_err(db_error)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+    }).map_err(db_error)?;
+
+    rows.map(|row| {
+        let (id, name, color, created_at) = row.map_err(db_error)?;
+        Ok(Collection {
+            repository_paths: members.remove(&id).unwrap_or_default(),
+            created_at: parse_time(&created_at)?,
+            id,
+            name,
+            color,
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_store::CachePatch;
+    use crate::repo_types::{BranchInfo, ScanConfig};
+    use crate::test_support::temp_dir;
+
+    fn temp_store() -> (SqliteStore, std::path::PathBuf) {
+        let dir = temp_dir("sqlite");
+        (SqliteStore::open(&dir.join("cache.db")).unwrap(), dir)
+    }
+
+    fn repo(path: &str) -> GitRepository {
+        GitRepository {
+            size_mb: 1.5,
+            file_types: HashMap::from([("rs".to_string(), 3)]),
+            branches: vec![BranchInfo::from_name("main".to_string())],
+            commit_count: 7,
+            ..GitRepository::fixture(path)
+        }
+    }
+
+    #[test]
+    fn test_replace_all_round_trips_cache() {
+        let (store, dir) = temp_store();
+        let mut cache = RepositoryCache::default();
+        cache.repositories.insert("/src/app".to_string(), repo("/src/app"));
+        cache.scan_paths.insert("/src".to_string(), ScanPath {
+            path: "/src".to_string(),
+            last_scanned: None,
+            repository_count: 1,
+            overrides: Some(ScanConfig { max_depth: Some(2), ..ScanConfig::default() }),
+        });
+        cache.collections.insert("c1".to_string(), Collection {
+            id: "c1".to_string(),
+            name: "Work".to_string(),
+            color: "#ff0000".to_string(),
+            repository_paths: vec!["/src/app".to_string()],
+            created_at: Utc::now(),
+        });
+
+        store.replace_all(&cache).unwrap();
+        let loaded = store.load_cache().unwrap();
+
+        assert_eq!(loaded.repositories["/src/app"].commit_count, 7);
+        assert_eq!(loaded.scan_paths["/src"].overrides.as_ref().unwrap().max_depth, Some(2));
+        assert_eq!(loaded.collections["c1"].repository_paths, vec!["/src/app"]);
+        assert_eq!(loaded.cache_version, CACHE_VERSION);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_changes_touches_only_changed_rows() {
+        let (store, dir) = temp_store();
+        let mut cache = RepositoryCache::default();
+        cache.repositories.insert("/src/a".to_string(), repo("/src/a"));
+        cache.repositories.insert("/src/b".to_string(), repo("/src/b"));
+        store.apply_changes(&CacheChanges::Replaced(cache)).unwrap();
+
+        let mut pinned = repo("/src/b");
+        pinned.is_pinned = true;
+        let collection = Collection {
+            id: "c1".to_string(),
+            name: "Work".to_string(),
+            color: "#fff".to_string(),
+            repository_paths: vec!["/src/b".to_string(), "/src/a".to_string()],
+            created_at: Utc::now(),
+        };
+        store.apply_changes(&CacheChanges::Entries(CachePatch {
+            repositories: vec![("/src/b".to_string(), Some(pinned)), ("/src/c".to_string(), None)],
+            collections: vec![("c1".to_string(), Some(collection.clone()))],
+            ..CachePatch::default()
+        })).unwrap();
+
+        let loaded = store.load_cache().unwrap();
+        assert_eq!(loaded.repositories.len(), 2);
+        assert!(loaded.repositories["/src/b"].is_pinned);
+        assert_eq!(loaded.collections["c1"].repository_paths, vec!["/src/b", "/src/a"]);
+
+        // Rewriting a collection replaces its members
+        store.apply_changes(&CacheChanges::Entries(CachePatch {
+            repositories: vec![("/src/a".to_string(), None)],
+            collections: vec![("c1".to_string(), Some(Collection { repository_paths: vec!["/src/b".to_string()], ..collection }))],
+            ..CachePatch::default()
+        })).unwrap();
+
+        let loaded = store.load_cache().unwrap();
+        assert_eq!(loaded.repositories.len(), 1);
+        assert_eq!(loaded.collections["c1"].repository_paths, vec!["/src/b"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+use git2::{Oid, Repository, Signature};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Fresh directory under the system temp dir, named `gitmap-<label>-<uuid>`
+pub fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gitmap-{}-{}", label, uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `name` in the working tree, stage it and commit it on HEAD; returns the new commit
+pub fn commit_file(repo: &Repository, name: &str, content: &str) -> Oid {
+    let path = repo.workdir().expect("repository has no working tree").join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(&path, content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(name)).unwrap();
+    index.write().unwrap();
+
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, &format!("Update {}", name), &tree, &parents).unwrap()
+}
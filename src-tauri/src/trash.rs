@@ -0,0 +1,163 @@
+use crate::repo_types::CleanupMode;
+use chrono::Local;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+// Give up looking for a free name after this many "name.N" candidates
+const MAX_NAME_ATTEMPTS: u32 = 10_000;
+
+/// A freedesktop.org trash directory (`files/` holds the trashed items, `info/` one
+/// `.trashinfo` file per item recording where it came from).
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    /// The user's home trash, `$XDG_DATA_HOME/Trash` (usually `~/.local/share/Trash`)
+    #[cfg(all(unix, not(any(target_os = "macos", target_os = "ios", target_os = "android"))))]
+    pub fn home() -> Result<Self, String> {
+        let data_dir = dirs::data_dir().ok_or("Could not find the user data directory")?;
+        Ok(Self::at(data_dir.join("Trash")))
+    }
+
+    /// macOS and Windows keep their trash elsewhere and in other formats, so items put in a
+    /// freedesktop trash there would never show up in the system's trash
+    #[cfg(not(all(unix, not(any(target_os = "macos", target_os = "ios", target_os = "android")))))]
+    pub fn home() -> Result<Self, String> {
+        Err("Moving to the trash is only supported on Linux and other freedesktop.org desktops; use delete instead".to_string())
+    }
+
+    /// The trash a cleanup in `mode` moves directories to; only trash mode needs one
+    pub fn for_mode(mode: CleanupMode) -> Result<Option<Self>, String> {
+        match mode {
+            CleanupMode::Trash => Self::home().map(Some),
+            CleanupMode::DryRun | CleanupMode::Delete => Ok(None),
+        }
+    }
+
+    pub fn at(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Move `path` into the trash and return where it ended up. Only works within one
+    /// filesystem; items on other mounts are refused rather than copied.
+    pub fn put(&self, path: &Path) -> Result<PathBuf, String> {
+        let path = path.canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        let files_dir = self.root.join("files");
+        let info_dir = self.root.join("info");
+        fs::create_dir_all(&files_dir)
+            .and_then(|_| fs::create_dir_all(&info_dir))
+            .map_err(|e| format!("Failed to create trash directory {}: {}", self.root.display(), e))?;
+
+        let base_name = path.file_name()
+            .ok_or_else(|| format!("Cannot trash {}", path.display()))?
+            .to_string_lossy()
+            .to_string();
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&path),
+            Local::now().format("%Y-%m-%dT%H:%M:%S"),
+        );
+
+        for attempt in 1..=MAX_NAME_ATTEMPTS {
+            let name = if attempt == 1 { base_name.clone() } else { format!("{}.{}", base_name, attempt) };
+            let info_path = info_dir.join(format!("{}.trashinfo", name));
+            let trashed_path = files_dir.join(&name);
+
+            // Creating the info file exclusively reserves the name, as the spec requires
+            let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Failed to write {}: {}", info_path.display(), e)),
+            };
+            if trashed_path.exists() {
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+
+            let moved = info_file.write_all(info.as_bytes())
+                .and_then(|_| fs::rename(&path, &trashed_path));
+            return match moved {
+                Ok(()) => Ok(trashed_path),
+                Err(e) => {
+                    let _ = fs::remove_file(&info_path);
+                    if e.kind() == ErrorKind::CrossesDevices {
+                        Err(format!("{} is on a different filesystem than the trash", path.display()))
+                    } else {
+                        Err(format!("Failed to move {} to the trash: {}", path.display(), e))
+                    }
+                }
+            };
+        }
+
+        Err(format!("No free name for {} in the trash", base_name))
+    }
+
+    /// Move a trashed item back to `original`, which must not exist again by now
+    pub fn restore(&self, trashed_path: &Path, original: &Path) -> Result<(), String> {
+        if !trashed_path.exists() {
+            return Err(format!("{} is no longer in the trash", original.display()));
+        }
+        if original.exists() {
+            return Err(format!("{} exists again; not overwriting it", original.display()));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to recreate {}: {}", parent.display(), e))?;
+        }
+        fs::rename(trashed_path, original)
+            .map_err(|e| format!("Failed to restore {}: {}", original.display(), e))?;
+
+        if let Some(name) = trashed_path.file_name() {
+            let info_path = self.root.join("info").join(format!("{}.trashinfo", name.to_string_lossy()));
+            let _ = fs::remove_file(info_path);
+        }
+        Ok(())
+    }
+}
+
+// Percent-encode a path for the trashinfo Path key, keeping separators and unreserved characters
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_and_restore_round_trip() {
+        let dir = std::env::temp_dir().join(format!("gitmap-trash-{}", uuid::Uuid::new_v4()));
+        let trash = Trash::at(dir.join("Trash"));
+        for project in ["a b", "c"] {
+            fs::create_dir_all(dir.join(project).join("node_modules/pkg")).unwrap();
+            fs::write(dir.join(project).join("node_modules/pkg/index.js"), "x").unwrap();
+        }
+
+        let first = trash.put(&dir.join("a b/node_modules")).unwrap();
+        let second = trash.put(&dir.join("c/node_modules")).unwrap();
+        assert_eq!(second.file_name().unwrap(), "node_modules.2");
+        assert!(!dir.join("a b/node_modules").exists());
+
+        let info = fs::read_to_string(dir.join("Trash/info/node_modules.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"));
+        assert!(info.contains("/a%20b/node_modules\n"));
+
+        let original = dir.join("a b/node_modules");
+        trash.restore(&first, &original).unwrap();
+        assert!(original.join("pkg/index.js").exists());
+        assert!(!dir.join("Trash/info/node_modules.trashinfo").exists());
+        assert!(trash.restore(&first, &original).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
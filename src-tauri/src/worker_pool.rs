@@ -0,0 +1,165 @@
+use crate::cancellation::CancellationToken;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+// Items the producer may queue ahead per worker
+const QUEUE_ITEMS_PER_WORKER: usize = 2;
+
+/// Default worker count: one per available CPU core
+pub fn default_concurrency() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// Runs `work` on every item produced by `producer` using at most `concurrency` worker threads.
+///
+/// The producer runs on its own thread and feeds a bounded queue, so discovery and
+/// processing overlap without the producer racing arbitrarily far ahead. The `push`
+/// function handed to the producer returns `false` once the workers are gone, at which
+/// point the producer should stop. Results are passed to `on_result` on the calling
+/// thread in the order they complete.
+///
+/// Once `cancel` is triggered `push` returns `false` and queued items are dropped
+/// without being processed. Producers doing long work between pushes should also
+/// check the token themselves.
+pub fn run_pipeline<T, R, P, W, F>(concurrency: usize, cancel: &CancellationToken, producer: P, work: W, mut on_result: F)
+where
+    T: Send,
+    R: Send,
+    P: FnOnce(&dyn Fn(T) -> bool) + Send,
+    W: Fn(T) -> R + Sync,
+    F: FnMut(R),
+{
+    let concurrency = concurrency.max(1);
+    let (item_tx, item_rx) = mpsc::sync_channel::<T>(concurrency * QUEUE_ITEMS_PER_WORKER);
+    let item_rx = Mutex::new(item_rx);
+    let (result_tx, result_rx) = mpsc::channel::<R>();
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            producer(&|item| !cancel.is_cancelled() && item_tx.send(item).is_ok());
+            // item_tx is dropped here, which lets idle workers exit
+        });
+
+        for _ in 0..concurrency {
+            let result_tx = result_tx.clone();
+            let item_rx = &item_rx;
+            let work = &work;
+
+            scope.spawn(move || loop {
+                // Only one idle worker waits on the queue at a time; the rest wait on the lock
+                let next = match item_rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => break,
+                };
+
+                match next {
+                    // Keep draining after cancellation so a producer blocked on a full queue can finish
+                    Ok(_) if cancel.is_cancelled() => continue,
+                    Ok(item) => {
+                        if result_tx.send(work(item)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break, // Producer finished and the queue is drained
+                }
+            });
+        }
+
+        // Drop our sender so the result loop ends once every worker has exited
+        drop(result_tx);
+
+        for result in result_rx {
+            on_result(result);
+        }
+    });
+}
+
+/// Convenience wrapper around [`run_pipeline`] for a list of items that is already known
+pub fn run_bounded<T, R, W, F>(concurrency: usize, cancel: &CancellationToken, items: Vec<T>, work: W, on_result: F)
+where
+    T: Send,
+    R: Send,
+    W: Fn(T) -> R + Sync,
+    F: FnMut(R),
+{
+    run_pipeline(
+        concurrency,
+        cancel,
+        move |push| {
+            for item in items {
+                if !push(item) {
+                    break;
+                }
+            }
+        },
+        work,
+        on_result,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_run_bounded_processes_every_item_within_limit() {
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let mut results = Vec::new();
+
+        run_bounded(
+            3,
+            &CancellationToken::new(),
+            (0..50).collect(),
+            |n: u32| {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(2));
+                active.fetch_sub(1, Ordering::SeqCst);
+                n * 2
+            },
+            |r| results.push(r),
+        );
+
+        results.sort();
+        assert_eq!(results, (0..50).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn test_cancelled_pipeline_stops_producer_and_skips_queued_items() {
+        let concurrency = 2;
+        let cancel = CancellationToken::new();
+        let worked = AtomicUsize::new(0);
+        let mut processed = 0;
+
+        run_pipeline(
+            concurrency,
+            &cancel,
+            |push| {
+                let mut n = 0;
+                while push(n) {
+                    n += 1;
+                }
+            },
+            |n: usize| {
+                worked.fetch_add(1, Ordering::SeqCst);
+                if n == 10 {
+                    cancel.cancel();
+                }
+                n
+            },
+            |_| processed += 1,
+        );
+
+        // The producer would run forever without cancellation. Items 0..=10 were taken before
+        // the cancel; after it, at most a full queue plus one item per worker can get through.
+        let capacity = concurrency * QUEUE_ITEMS_PER_WORKER;
+        assert!(processed <= 11 + capacity + concurrency, "processed {} items", processed);
+        assert_eq!(processed, worked.load(Ordering::SeqCst));
+    }
+}
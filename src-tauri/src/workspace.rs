@@ -0,0 +1,476 @@
+use crate::cache_backup;
+use crate::data_store::RepositoryCache;
+use crate::memory_store::MemoryStore;
+use crate::remote_url::parse_remote_url;
+use crate::repo_types::{Collection, ScanConfig, ScanPath};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const WORKSPACE_VERSION: u32 = 1;
+
+/// Scan paths, collections and pins in a form that can be restored on another machine.
+///
+/// Paths under the home directory are written as `~/...`, and repositories are identified by
+/// their path relative to a scan root plus their remote URL, so a workspace still matches when
+/// the home directory or the layout below the scan roots differs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceFile {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub scan_config: ScanConfig,
+    pub scan_roots: Vec<WorkspaceScanRoot>,
+    pub repositories: Vec<WorkspaceRepository>,
+    pub collections: Vec<WorkspaceCollection>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceScanRoot {
+    pub path: String, // Portable path, "~/..." when under the home directory
+    pub overrides: Option<ScanConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceRepository {
+    pub name: String,
+    pub remote_url: Option<String>,
+    pub scan_root: Option<usize>, // Index into scan_roots, None when outside every scan root
+    pub path: String, // Relative to the scan root with "/" separators, otherwise a portable path
+    pub is_pinned: bool,
+    pub pinned_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceCollection {
+    pub name: String,
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub repositories: Vec<usize>, // Indexes into repositories
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    Merge,   // Add to the current scan paths, pins and collections
+    Replace, // Replace them, keeping the scanned repository data
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnmatchedRepository {
+    pub name: String,
+    pub remote_url: Option<String>,
+    pub expected_path: String, // Where the repository would be on this machine
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WorkspaceImportReport {
+    pub scan_paths_added: usize,
+    pub repositories_matched: usize,
+    pub collections_imported: usize,
+    pub unmatched: Vec<UnmatchedRepository>,
+}
+
+pub fn read_workspace_file(path: &Path) -> Result<WorkspaceFile, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read workspace file: {}", e))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse workspace file: {}", e))?;
+
+    let version = value.get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or("Not a workspace file: missing version")?;
+    if version > WORKSPACE_VERSION as u64 {
+        return Err(format!(
+            "Workspace file version {} is newer than this app supports ({})",
+            version, WORKSPACE_VERSION
+        ));
+    }
+
+    serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse workspace file (version {}): {}", version, e))
+}
+
+pub fn write_workspace_file(path: &Path, workspace: &WorkspaceFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(workspace)
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+    cache_backup::write_atomic(path, content.as_bytes())
+}
+
+/// Build a workspace file from the cache
+pub fn export_workspace(cache: &RepositoryCache, home: Option<&Path>) -> WorkspaceFile {
+    let mut scan_paths: Vec<&ScanPath> = cache.scan_paths.values().collect();
+    scan_paths.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut repos: Vec<_> = cache.repositories.values().collect();
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let repositories = repos.iter()
+        .map(|repo| {
+            let (scan_root, path) = match scan_root_of(&scan_paths, &repo.path) {
+                Some((index, relative)) => (Some(index), relative),
+                None => (None, to_portable(Path::new(&repo.path), home)),
+            };
+
+            WorkspaceRepository {
+                name: repo.name.clone(),
+                remote_url: repo.remote_url.clone(),
+                scan_root,
+                path,
+                is_pinned: repo.is_pinned,
+                pinned_at: repo.pinned_at,
+            }
+        })
+        .collect();
+
+    let index_by_path: HashMap<&str, usize> = repos.iter()
+        .enumerate()
+        .map(|(index, repo)| (repo.path.as_str(), index))
+        .collect();
+
+    let mut collections: Vec<WorkspaceCollection> = cache.collections.values()
+        .map(|collection| WorkspaceCollection {
+            name: collection.name.clone(),
+            color: collection.color.clone(),
+            created_at: collection.created_at,
+            repositories: collection.repository_paths
+                .iter()
+                .filter_map(|path| index_by_path.get(path.as_str()).copied())
+                .collect(),
+        })
+        .collect();
+    collections.sort_by(|a, b| a.name.cmp(&b.name));
+
+    WorkspaceFile {
+        version: WORKSPACE_VERSION,
+        exported_at: Utc::now(),
+        scan_config: cache.scan_config.clone(),
+        scan_roots: scan_paths.iter()
+            .map(|scan_path| WorkspaceScanRoot {
+                path: to_portable(Path::new(&scan_path.path), home),
+                overrides: scan_path.overrides.clone(),
+            })
+            .collect(),
+        repositories,
+        collections,
+    }
+}
+
+// Deepest scan path containing the repository, with the path relative to it
+fn scan_root_of(scan_paths: &[&ScanPath], repo_path: &str) -> Option<(usize, String)> {
+    scan_paths.iter()
+        .enumerate()
+        .filter_map(|(index, scan_path)| {
+            let relative = Path::new(repo_path).strip_prefix(&scan_path.path).ok()?;
+            Some((index, scan_path.path.len(), to_slash(relative)))
+        })
+        .max_by_key(|(_, root_len, _)| *root_len)
+        .map(|(index, _, relative)| (index, relative))
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn to_portable(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", to_slash(relative)),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+fn from_portable(path: &str, home: Option<&Path>) -> PathBuf {
+    let relative = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        _ => return PathBuf::from(path),
+    };
+
+    match home {
+        Some(home) => relative.split('/').filter(|part| !part.is_empty()).fold(home.to_path_buf(), |path, part| path.join(part)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Where an exported repository is expected on this machine
+fn expected_path(workspace: &WorkspaceFile, repo: &WorkspaceRepository, home: Option<&Path>) -> PathBuf {
+    match repo.scan_root.and_then(|index| workspace.scan_roots.get(index)) {
+        Some(root) => repo.path
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .fold(from_portable(&root.path, home), |path, part| path.join(part)),
+        None => from_portable(&repo.path, home),
+    }
+}
+
+// Remote URLs match when host, owner and name agree, so ssh and https clones are the same repository
+fn same_remote(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match (parse_remote_url(a), parse_remote_url(b)) {
+        (Some(a), Some(b)) => a.host.is_some()
+            && a.host == b.host
+            && a.owner.map(|owner| owner.to_lowercase()) == b.owner.map(|owner| owner.to_lowercase())
+            && a.repo_name.map(|name| name.to_lowercase()) == b.repo_name.map(|name| name.to_lowercase()),
+        _ => false,
+    }
+}
+
+/// Find the local path of every exported repository: the cached repository at its expected
+/// path, else a cached repository with the same remote, else the expected path if `is_repository`
+/// finds one there that was not scanned yet. `None` for repositories that could not be matched.
+pub fn match_repositories(
+    workspace: &WorkspaceFile,
+    cache: &RepositoryCache,
+    home: Option<&Path>,
+    is_repository: impl Fn(&Path) -> bool,
+) -> Vec<Option<String>> {
+    let mut cached: Vec<_> = cache.repositories.values().collect();
+    cached.sort_by(|a, b| a.path.cmp(&b.path));
+
+    workspace.repositories.iter()
+        .map(|repo| {
+            let expected = expected_path(workspace, repo, home);
+            let expected_str = expected.to_string_lossy().to_string();
+            if cache.repositories.contains_key(&expected_str) {
+                return Some(expected_str);
+            }
+
+            if let Some(url) = &repo.remote_url {
+                let mut candidates = cached.iter().filter(|local| {
+                    local.remote_url.iter()
+                        .chain(local.remotes.iter().filter_map(|remote| remote.fetch_url.as_ref()))
+                        .any(|local_url| same_remote(url, local_url))
+                });
+                // Prefer a clone with the same directory name when there are several
+                let first = candidates.next();
+                let same_name = first.into_iter()
+                    .chain(candidates)
+                    .find(|local| Path::new(&local.path).file_name() == expected.file_name());
+                if let Some(local) = same_name.or(first) {
+                    return Some(local.path.clone());
+                }
+            }
+
+            is_repository(&expected).then_some(expected_str)
+        })
+        .collect()
+}
+
+/// Apply a workspace to the store, using the local paths from `match_repositories`
+pub fn import_workspace(
+    memory: &mut MemoryStore,
+    workspace: &WorkspaceFile,
+    matches: &[Option<String>],
+    mode: ImportMode,
+    home: Option<&Path>,
+) -> WorkspaceImportReport {
+    let mut report = WorkspaceImportReport::default();
+    let scan_roots: Vec<String> = workspace.scan_roots.iter()
+        .map(|root| from_portable(&root.path, home).to_string_lossy().to_string())
+        .collect();
+
+    if mode == ImportMode::Replace {
+        let cache = memory.cache();
+        let removed_paths: Vec<String> = cache.scan_paths.keys()
+            .filter(|path| !scan_roots.contains(path))
+            .cloned()
+            .collect();
+        let pinned: Vec<String> = cache.repositories.values()
+            .filter(|repo| repo.is_pinned)
+            .map(|repo| repo.path.clone())
+            .collect();
+        let collections: Vec<String> = cache.collections.keys().cloned().collect();
+
+        for path in &removed_paths {
+            memory.remove_scan_path(path);
+        }
+        for path in &pinned {
+            memory.update_repository(path, |repo| {
+                repo.is_pinned = false;
+                repo.pinned_at = None;
+            });
+        }
+        for id in &collections {
+            memory.remove_collection(id);
+        }
+        memory.set_scan_config(workspace.scan_config.clone());
+    }
+
+    for (root, path) in workspace.scan_roots.iter().zip(scan_roots) {
+        let existing = memory.update_scan_path(&path, |scan_path| {
+            // Merging keeps rules configured on this machine
+            if mode == ImportMode::Replace || scan_path.overrides.is_none() {
+                scan_path.overrides = root.overrides.clone();
+            }
+        });
+        if existing.is_none() {
+            memory.put_scan_path(ScanPath {
+                path,
+                last_scanned: None,
+                repository_count: 0,
+                overrides: root.overrides.clone(),
+            });
+            report.scan_paths_added += 1;
+        }
+    }
+
+    for (repo, matched) in workspace.repositories.iter().zip(matches) {
+        let Some(path) = matched else {
+            report.unmatched.push(UnmatchedRepository {
+                name: repo.name.clone(),
+                remote_url: repo.remote_url.clone(),
+                expected_path: expected_path(workspace, repo, home).to_string_lossy().to_string(),
+            });
+            continue;
+        };
+
+        report.repositories_matched += 1;
+        if repo.is_pinned {
+            memory.update_repository(path, |local| {
+                if !local.is_pinned {
+                    local.is_pinned = true;
+                    local.pinned_at = repo.pinned_at.or_else(|| Some(Utc::now()));
+                }
+            });
+        }
+    }
+
+    for collection in &workspace.collections {
+        let mut members: Vec<String> = Vec::new();
+        for path in collection.repositories.iter().filter_map(|&index| matches.get(index).cloned().flatten()) {
+            if !members.contains(&path) {
+                members.push(path);
+            }
+        }
+
+        let existing_id = memory.cache().collections.values()
+            .find(|existing| existing.name == collection.name)
+            .map(|existing| existing.id.clone());
+
+        match existing_id {
+            Some(id) => {
+                memory.update_collection(&id, |existing| {
+                    for path in members {
+                        if !existing.repository_paths.contains(&path) {
+                            existing.repository_paths.push(path);
+                        }
+                    }
+                });
+            }
+            None => memory.put_collection(Collection {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: collection.name.clone(),
+                color: collection.color.clone(),
+                repository_paths: members,
+                created_at: collection.created_at,
+            }),
+        }
+        report.collections_imported += 1;
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo_types::GitRepository;
+
+    fn repo(path: &str, remote_url: Option<&str>, is_pinned: bool) -> GitRepository {
+        GitRepository {
+            remote_url: remote_url.map(str::to_string),
+            is_pinned,
+            pinned_at: is_pinned.then(Utc::now),
+            ..GitRepository::fixture(path)
+        }
+    }
+
+    fn cache_with(repos: Vec<GitRepository>, scan_paths: &[&str]) -> RepositoryCache {
+        let mut cache = RepositoryCache::default();
+        for repo in repos {
+            cache.repositories.insert(repo.path.clone(), repo);
+        }
+        for path in scan_paths {
+            cache.scan_paths.insert(path.to_string(), ScanPath {
+                path: path.to_string(),
+                last_scanned: None,
+                repository_count: 0,
+                overrides: None,
+            });
+        }
+        cache
+    }
+
+    #[test]
+    fn test_export_import_across_home_directories() {
+        let mut old = cache_with(vec![
+            repo("/home/alice/code/app", Some("git@github.com:acme/app.git"), true),
+            repo("/home/alice/code/tools/cli", Some("https://github.com/acme/cli"), false),
+            repo("/home/alice/code/gone", None, false),
+        ], &["/home/alice/code"]);
+        old.collections.insert("c1".to_string(), Collection {
+            id: "c1".to_string(),
+            name: "Work".to_string(),
+            color: "#e5e7eb".to_string(),
+            repository_paths: vec!["/home/alice/code/app".to_string(), "/home/alice/code/tools/cli".to_string()],
+            created_at: Utc::now(),
+        });
+
+        let workspace = export_workspace(&old, Some(Path::new("/home/alice")));
+        assert_eq!(workspace.scan_roots[0].path, "~/code");
+        assert_eq!(workspace.repositories[2].path, "tools/cli");
+
+        // The new machine has app at the same place and cli cloned elsewhere over https
+        let new = cache_with(vec![
+            repo("/Users/alice/code/app", Some("https://github.com/acme/app"), false),
+            repo("/Users/alice/src/cli", Some("git@github.com:ACME/cli.git"), false),
+        ], &[]);
+        let home = Some(Path::new("/Users/alice"));
+        let matches = match_repositories(&workspace, &new, home, |_| false);
+        assert_eq!(matches, vec![
+            Some("/Users/alice/code/app".to_string()),
+            None,
+            Some("/Users/alice/src/cli".to_string()),
+        ]);
+
+        let mut memory = MemoryStore::new(new);
+        let report = import_workspace(&mut memory, &workspace, &matches, ImportMode::Merge, home);
+        assert_eq!(report.scan_paths_added, 1);
+        assert_eq!(report.repositories_matched, 2);
+        assert_eq!(report.unmatched.len(), 1);
+        assert_eq!(report.unmatched[0].expected_path, "/Users/alice/code/gone");
+
+        let cache = memory.cache();
+        assert!(cache.scan_paths.contains_key("/Users/alice/code"));
+        assert!(cache.repositories["/Users/alice/code/app"].is_pinned);
+        let collection = cache.collections.values().next().unwrap();
+        assert_eq!(collection.repository_paths, vec!["/Users/alice/code/app", "/Users/alice/src/cli"]);
+    }
+
+    #[test]
+    fn test_replace_drops_local_pins_and_collections() {
+        let mut local = cache_with(vec![repo("/src/a", None, true)], &["/src", "/other"]);
+        local.collections.insert("c1".to_string(), Collection {
+            id: "c1".to_string(),
+            name: "Old".to_string(),
+            color: "#e5e7eb".to_string(),
+            repository_paths: vec!["/src/a".to_string()],
+            created_at: Utc::now(),
+        });
+        let workspace = export_workspace(&cache_with(vec![], &["/src"]), None);
+
+        let mut memory = MemoryStore::new(local);
+        import_workspace(&mut memory, &workspace, &[], ImportMode::Replace, None);
+
+        let cache = memory.cache();
+        assert!(!cache.repositories["/src/a"].is_pinned);
+        assert!(cache.collections.is_empty());
+        assert_eq!(cache.scan_paths.keys().collect::<Vec<_>>(), vec!["/src"]);
+    }
+}
@@ -120,6 +120,28 @@ export const AdvancedSearchDialog: React.FC<AdvancedSearchDialogProps> = ({
             />
           </div>
 
+          {/* Remote Host / Owner */}
+          <div className="grid grid-cols-2 gap-2">
+            <div className="space-y-2">
+              <Label htmlFor="remoteHost">Remote Host</Label>
+              <Input
+                id="remoteHost"
+                placeholder="e.g., github.com"
+                value={filters.remoteHost || ''}
+                onChange={(e) => handleInputChange('remoteHost', e.target.value)}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="remoteOwner">Owner / Organization</Label>
+              <Input
+                id="remoteOwner"
+                placeholder="e.g., rust-lang"
+                value={filters.remoteOwner || ''}
+                onChange={(e) => handleInputChange('remoteOwner', e.target.value)}
+              />
+            </div>
+          </div>
+
           {/* Path Filter */}
           <div className="space-y-2">
             <Label htmlFor="pathFilter">Path Contains</Label>
@@ -191,12 +213,6 @@ export const AdvancedSearchDialog: React.FC<AdvancedSearchDialogProps> = ({
                     <span>Total Repos:</span>
                     <Badge variant="secondary">{optimizationStats.total_repositories}</Badge>
                   </div>
-                  <div className="flex justify-between">
-                    <span>Cache Size:</span>
-                    <Badge variant="secondary">
-                      {optimizationStats.lru_cache_size}/{optimizationStats.lru_cache_capacity}
-                    </Badge>
-                  </div>
                   <div className="flex justify-between">
                     <span>Name Index:</span>
                     <Badge variant="secondary">{optimizationStats.index_name_entries}</Badge>
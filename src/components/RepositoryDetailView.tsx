@@ -184,17 +184,24 @@ export const RepositoryDetail: React.FC<RepositoryDetailProps> = ({
               <div className="space-y-1 max-h-48 overflow-y-auto">
                 {repository.branches.slice(0, 10).map(branch => (
                   <div
-                    key={branch}
-                    className={`px-2 py-1 rounded text-xs ${
-                      branch === repository.current_branch 
+                    key={`${branch.is_remote ? 'remote' : 'local'}:${branch.name}`}
+                    className={`px-2 py-1 rounded text-xs flex items-center gap-1 ${
+                      branch.is_head
                         ? 'bg-primary/10 text-primary font-medium' 
                         : 'bg-muted text-muted-foreground'
                     }`}
                   >
-                    {branch === repository.current_branch && (
+                    {branch.is_head && (
                       <span className="mr-1">●</span>
                     )}
-                    <span className="truncate block" title={branch}>{branch}</span>
+                    <span className="truncate block" title={branch.upstream ? `${branch.name} → ${branch.upstream}` : branch.name}>{branch.name}</span>
+                    {(branch.ahead > 0 || branch.behind > 0) && (
+                      <span className="ml-auto shrink-0 font-mono">
+                        {branch.ahead > 0 && `↑${branch.ahead}`}
+                        {branch.ahead > 0 && branch.behind > 0 && ' '}
+                        {branch.behind > 0 && `↓${branch.behind}`}
+                      </span>
+                    )}
                   </div>
                 ))}
                 {repository.branches.length > 10 && (
@@ -205,6 +212,34 @@ export const RepositoryDetail: React.FC<RepositoryDetailProps> = ({
               </div>
             </section>
           )}
+
+          {/* Submodules Section */}
+          {repository.submodules?.length > 0 && (
+            <section className="space-y-2 mt-6">
+              <h3 className="font-semibold text-sm md:text-base">
+                Submodules ({repository.submodules.length})
+              </h3>
+              <div className="space-y-1 max-h-48 overflow-y-auto">
+                {repository.submodules.map(submodule => (
+                  <div
+                    key={submodule.path}
+                    className="px-2 py-1 rounded text-xs flex items-center gap-1 bg-muted text-muted-foreground"
+                  >
+                    <span className="truncate block" title={submodule.url || submodule.path}>{submodule.path}</span>
+                    <span className="ml-auto shrink-0">
+                      {submodule.is_uninitialized
+                        ? 'not initialized'
+                        : submodule.is_out_of_sync
+                          ? 'out of sync'
+                          : submodule.is_modified
+                            ? 'modified'
+                            : submodule.checked_out_commit?.slice(0, 7)}
+                    </span>
+                  </div>
+                ))}
+              </div>
+            </section>
+          )}
         </div>
       </div>
 
@@ -1,5 +1,5 @@
 import React, { useState } from 'react';
-import { GitRepository } from '../types/repository';
+import { ArtifactCleanup, ArtifactKind, GitRepository, UndoCleanupResult } from '../types/repository';
 import { Button } from "@/components/ui/button";
 import { Toggle } from "@/components/ui/toggle";
 import { Tooltip, TooltipTrigger, TooltipContent } from "@/components/ui/tooltip";
@@ -12,6 +12,19 @@ import { toast } from "sonner";
 import { HardDrive } from 'lucide-react';
 import { GitCommitVertical } from 'lucide-react';
 
+const ARTIFACT_KIND_LABELS: Record<ArtifactKind, string> = {
+  node_modules: 'node_modules',
+  cargo_target: 'Cargo target',
+  maven_target: 'Maven target',
+  gradle_build: 'Gradle build',
+  gradle_cache: '.gradle',
+  python_venv: 'Python virtualenv',
+  python_cache: 'Python caches',
+  next_build: '.next',
+  nuxt_build: 'Nuxt build',
+  js_dist: 'dist',
+  cmake_build: 'CMake build',
+};
 
 interface RepositoryListProps {
   repositories: GitRepository[];
@@ -23,7 +36,8 @@ interface RepositoryListProps {
   collectionRefreshTrigger?: number;
   isLoading?: boolean;
   isInitialLoad?: boolean; // Add this to distinguish initial load from collection switching
-  onDeleteNodeModules?: (repoPath: string) => Promise<void>;
+  onDeleteBuildArtifacts?: (repoPath: string) => Promise<ArtifactCleanup>;
+  onUndoCleanup?: (repoPath: string, operationId: string) => Promise<UndoCleanupResult | null>;
 }
 
 export const RepositoryList: React.FC<RepositoryListProps> = ({ 
@@ -36,13 +50,14 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
   collectionRefreshTrigger,
   isLoading = false,
   isInitialLoad = false,
-  onDeleteNodeModules
+  onDeleteBuildArtifacts,
+  onUndoCleanup
 }) => {
   const [collectionDialogOpen, setCollectionDialogOpen] = useState(false);
   const [selectedRepository, setSelectedRepository] = useState<GitRepository | null>(null);
-  const [deleteNodeModulesDialogOpen, setDeleteNodeModulesDialogOpen] = useState(false);
-  const [repositoryToDeleteNodeModules, setRepositoryToDeleteNodeModules] = useState<GitRepository | null>(null);
-  const [isDeletingNodeModules, setIsDeletingNodeModules] = useState(false);
+  const [deleteBuildArtifactsDialogOpen, setDeleteBuildArtifactsDialogOpen] = useState(false);
+  const [repositoryToDeleteBuildArtifacts, setRepositoryToDeleteBuildArtifacts] = useState<GitRepository | null>(null);
+  const [isDeletingBuildArtifacts, setIsDeletingBuildArtifacts] = useState(false);
   const [deletingRepositoryPaths, setDeletingRepositoryPaths] = useState<Set<string>>(new Set());
 
   // Debug logging with performance timing
@@ -63,11 +78,11 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
       .join(', ');
   };
 
-  const handleDeleteNodeModules = async (repo: GitRepository) => {
-    if (!onDeleteNodeModules) return;
+  const handleDeleteBuildArtifacts = async (repo: GitRepository) => {
+    if (!onDeleteBuildArtifacts) return;
     
-    setRepositoryToDeleteNodeModules(repo);
-    setDeleteNodeModulesDialogOpen(true);
+    setRepositoryToDeleteBuildArtifacts(repo);
+    setDeleteBuildArtifactsDialogOpen(true);
   };
 
   if (isLoading) {
@@ -138,13 +153,13 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
     const isDeleting = deletingRepositoryPaths.has(repo.path);
     
     return (
-      <li key={repo.path} className="border-b last:border-b-0 hover:shadow-sm transition-shadow bg-white/80 hover:bg-white relative">
-        {/* Loading overlay when deleting node_modules */}
+      <li key={repo.path} className={`border-b last:border-b-0 hover:shadow-sm transition-shadow bg-white/80 hover:bg-white relative ${repo.kind === 'worktree' && repo.parent_path ? 'pl-6' : ''}`}>
+        {/* Loading overlay when deleting build artifacts */}
         {isDeleting && (
           <div className="absolute inset-0 bg-white/80 backdrop-blur-sm flex items-center justify-center z-10 rounded">
             <div className="flex items-center gap-2 bg-white px-3 py-2 rounded-lg shadow-md border">
               <RefreshCw className="h-4 w-4 animate-spin text-blue-500" />
-              <span className="text-sm font-medium text-gray-700">Deleting node_modules...</span>
+              <span className="text-sm font-medium text-gray-700">Deleting build artifacts...</span>
             </div>
           </div>
         )}
@@ -157,6 +172,9 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
             <div className="flex flex-col items-start justify-between flex-1 min-w-0">
               <div className="flex items-baseline gap-2 mb-2">
                 <h3 className="font-semibold truncate">{repo.name}</h3>
+                {repo.kind && repo.kind !== 'normal' && (
+                  <span className="text-[10px] uppercase tracking-wide text-muted-foreground border rounded px-1 shrink-0">{repo.kind}</span>
+                )}
                 {repo.is_pinned && (
                   <Pin className="h-3 w-3 text-amber-500 shrink-0" fill="currentColor" />
                 )}
@@ -229,8 +247,8 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
                 <CollectionBadges repositoryPath={repo.path} refreshTrigger={collectionRefreshTrigger} />
               </div> */}
 
-                  {/* Delete Node Modules Button - Only show if repository has node_modules */}
-                {repo.node_modules_info && repo.node_modules_info.count > 0 && onDeleteNodeModules && (
+                  {/* Delete Build Artifacts Button - Only show if repository has build artifacts */}
+                {repo.build_artifacts && repo.build_artifacts.kinds.length > 0 && onDeleteBuildArtifacts && (
                   <Tooltip>
                     <TooltipTrigger asChild>
                       <Button
@@ -241,20 +259,20 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
                         onClick={(e) => {
                           e.stopPropagation();
                           if (!isDeleting) {
-                            handleDeleteNodeModules(repo);
+                            handleDeleteBuildArtifacts(repo);
                           }
                         }}
                       >
                         {isDeleting ? (
                           <RefreshCw className="h-3 w-3 animate-spin" />
                         ) : (
-                          // <div className='flex px-1 gap-1 items-center justify-center'><Trash2 className="h-3 w-3" /> <span className='text-sm'>node_modules</span></div>
-                          <div className='flex px-1 gap-1 items-center justify-center'><span className='text-sm font-medium'>Free up {formatSize(repo.node_modules_info.total_size_mb)}</span></div>
+                          // <div className='flex px-1 gap-1 items-center justify-center'><Trash2 className="h-3 w-3" /> <span className='text-sm'>build artifacts</span></div>
+                          <div className='flex px-1 gap-1 items-center justify-center'><span className='text-sm font-medium'>Free up {formatSize(repo.build_artifacts.total_size_mb)}</span></div>
                         )}
                       </Button>
                     </TooltipTrigger>
                     <TooltipContent>
-                      <p>{isDeleting ? 'Deleting...' : `Delete node_modules (${formatSize(repo.node_modules_info.total_size_mb)})`}</p>
+                      <p>{isDeleting ? 'Deleting...' : `Delete build artifacts (${formatSize(repo.build_artifacts.total_size_mb)})`}</p>
                     </TooltipContent>
                   </Tooltip>
                 )}
@@ -416,49 +434,71 @@ export const RepositoryList: React.FC<RepositoryListProps> = ({
         />
       )}
 
-      {/* Delete node_modules Confirmation Dialog */}
-      {repositoryToDeleteNodeModules && (
-        <AlertDialog open={deleteNodeModulesDialogOpen} onOpenChange={setDeleteNodeModulesDialogOpen}>
+      {/* Delete build artifacts Confirmation Dialog */}
+      {repositoryToDeleteBuildArtifacts && (
+        <AlertDialog open={deleteBuildArtifactsDialogOpen} onOpenChange={setDeleteBuildArtifactsDialogOpen}>
           <AlertDialogContent>
             <AlertDialogHeader>
-              <AlertDialogTitle>Delete node_modules?</AlertDialogTitle>
+              <AlertDialogTitle>Delete build artifacts?</AlertDialogTitle>
               <AlertDialogDescription>
-                This will permanently delete the <code>node_modules</code> folder(s) in "{repositoryToDeleteNodeModules.name}". 
-                {repositoryToDeleteNodeModules.node_modules_info && (
-                  <span> This will free up {formatSize(repositoryToDeleteNodeModules.node_modules_info.total_size_mb)} of space.</span>
+                This will move the generated folders in "{repositoryToDeleteBuildArtifacts.name}" to the trash: 
+                {repositoryToDeleteBuildArtifacts.build_artifacts && (
+                  <span>
+                    {' '}{repositoryToDeleteBuildArtifacts.build_artifacts.kinds
+                      .map(info => `${ARTIFACT_KIND_LABELS[info.kind]} (${formatSize(info.size_mb)})`)
+                      .join(', ')}.
+                    {' '}This will free up {formatSize(repositoryToDeleteBuildArtifacts.build_artifacts.total_size_mb)} of space.
+                  </span>
                 )}
                 <br /><br />
-                This action cannot be undone, but you can reinstall dependencies by running <code>npm install</code> or <code>yarn install</code>.
+                The cleanup can be undone while the folders are still in the trash. They are also recreated by installing dependencies or building the project again.
               </AlertDialogDescription>
             </AlertDialogHeader>
             <AlertDialogFooter>
-              <AlertDialogCancel disabled={isDeletingNodeModules}>Cancel</AlertDialogCancel>
+              <AlertDialogCancel disabled={isDeletingBuildArtifacts}>Cancel</AlertDialogCancel>
               <AlertDialogAction 
                 onClick={async () => {
-                  if (repositoryToDeleteNodeModules && onDeleteNodeModules) {
-                    setIsDeletingNodeModules(true);
-                    setDeletingRepositoryPaths(prev => new Set(prev).add(repositoryToDeleteNodeModules.path));
+                  if (repositoryToDeleteBuildArtifacts && onDeleteBuildArtifacts) {
+                    setIsDeletingBuildArtifacts(true);
+                    setDeletingRepositoryPaths(prev => new Set(prev).add(repositoryToDeleteBuildArtifacts.path));
                     try {
-                      await onDeleteNodeModules(repositoryToDeleteNodeModules.path);
-                      toast.success(`node_modules deleted successfully from ${repositoryToDeleteNodeModules.name}`);
+                      const repo = repositoryToDeleteBuildArtifacts;
+                      const cleanup = await onDeleteBuildArtifacts(repo.path);
+                      const operationId = cleanup.operation_id;
+                      if (cleanup.errors.length > 0) {
+                        toast.error(`Some build artifacts could not be moved to the trash: ${cleanup.errors.join('; ')}`);
+                      }
+                      toast.success(`Moved ${formatSize(cleanup.total_bytes / (1024 * 1024))} of build artifacts from ${repo.name} to the trash`, {
+                        action: operationId && onUndoCleanup ? {
+                          label: 'Undo',
+                          onClick: async () => {
+                            const result = await onUndoCleanup(repo.path, operationId);
+                            if (result && result.errors.length > 0) {
+                              toast.error(`Failed to restore some build artifacts: ${result.errors.join('; ')}`);
+                            } else if (result) {
+                              toast.success(`Restored build artifacts in ${repo.name}`);
+                            }
+                          },
+                        } : undefined,
+                      });
                     } catch (error) {
-                      toast.error(`Failed to delete node_modules: ${error}`);
+                      toast.error(`Failed to delete build artifacts: ${error}`);
                     } finally {
-                      setIsDeletingNodeModules(false);
+                      setIsDeletingBuildArtifacts(false);
                       setDeletingRepositoryPaths(prev => {
                         const newSet = new Set(prev);
-                        newSet.delete(repositoryToDeleteNodeModules.path);
+                        newSet.delete(repositoryToDeleteBuildArtifacts.path);
                         return newSet;
                       });
-                      setDeleteNodeModulesDialogOpen(false);
-                      setRepositoryToDeleteNodeModules(null);
+                      setDeleteBuildArtifactsDialogOpen(false);
+                      setRepositoryToDeleteBuildArtifacts(null);
                     }
                   }
                 }}
-                disabled={isDeletingNodeModules}
+                disabled={isDeletingBuildArtifacts}
                 className="bg-red-600 hover:bg-red-700"
               >
-                {isDeletingNodeModules ? "Deleting..." : "Yes, delete"}
+                {isDeletingBuildArtifacts ? "Deleting..." : "Yes, delete"}
               </AlertDialogAction>
             </AlertDialogFooter>
           </AlertDialogContent>
@@ -28,7 +28,7 @@ export const useOptimizedSearch = () => {
   
   // Optimized repository search with debouncing
   const debouncedSearch = useDebounce(async (filters: SearchFilters) => {
-    if (!filters.namePrefix && !filters.minSizeMb && !filters.maxSizeMb && !filters.fileType) {
+    if (!filters.namePrefix && !filters.minSizeMb && !filters.maxSizeMb && !filters.fileType && !filters.remoteHost && !filters.remoteOwner) {
       setSearchResults([]);
       return;
     }
@@ -42,6 +42,8 @@ export const useOptimizedSearch = () => {
         minSizeMb: filters.minSizeMb || null,
         maxSizeMb: filters.maxSizeMb || null,
         fileType: filters.fileType || null,
+        remoteHost: filters.remoteHost || null,
+        remoteOwner: filters.remoteOwner || null,
       });
       
       // Update cache with results
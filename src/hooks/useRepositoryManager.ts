@@ -1,12 +1,36 @@
 import { useState, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { describeCacheLoadState } from '../lib/cacheLoadState';
 import {
   GitRepository,
   ScanProgress,
   CacheInfo,
+  CacheBackup,
   ScanPath,
   RepositoriesDiscovered,
   AnalysisProgress,
+  WorkspaceImportMode,
+  WorkspaceImportReport,
+  RestoreProgress,
+  RestoreResult,
+  RepositorySelection,
+  BulkCommandSummary,
+  FetchResult,
+  FastForwardReport,
+  ArtifactCleanup,
+  ArtifactKind,
+  CleanupMode,
+  CleanupOperation,
+  UndoCleanupResult,
+  CleanupPolicy,
+  CleanupPolicySettings,
+  PolicyPreview,
+  PolicyRunReport,
+  DiskUsage,
+  WorkspaceDiskUsage,
+  GitHealth,
+  MaintenanceTask,
+  MaintenanceSummary,
 } from '../types/repository';
 import { useOptimizedSearch } from './useOptimizedSearch';
 import { listen } from '@tauri-apps/api/event';
@@ -19,6 +43,7 @@ export const useRepositoryManager = () => {
   const [cacheInfo, setCacheInfo] = useState<CacheInfo | null>(null);
   const [discoveredRepos, setDiscoveredRepos] = useState<RepositoriesDiscovered | null>(null);
   const [analysisProgress, setAnalysisProgress] = useState<AnalysisProgress | null>(null);
+  const [restoreProgress, setRestoreProgress] = useState<RestoreProgress | null>(null);
 
   // Use optimized search capabilities
   const optimizedSearch = useOptimizedSearch();
@@ -58,6 +83,10 @@ export const useRepositoryManager = () => {
     try {
       const info = await invoke<CacheInfo>('get_cache_info');
       setCacheInfo(info);
+      const loadProblem = describeCacheLoadState(info.load_state);
+      if (loadProblem) {
+        setError(loadProblem);
+      }
     } catch (err) {
       console.warn('Failed to load cache info:', err);
     }
@@ -95,6 +124,14 @@ export const useRepositoryManager = () => {
     }
   }, [loadCacheInfo]);
 
+  const cancelScan = useCallback(async () => {
+    try {
+      await invoke('cancel_scan');
+    } catch (err) {
+      setError(err as string);
+    }
+  }, []);
+
   const clearCache = useCallback(async () => {
     try {
       await invoke('clear_cache');
@@ -106,6 +143,272 @@ export const useRepositoryManager = () => {
     }
   }, [loadCacheInfo]);
 
+  // One-time import of the JSON cache into the SQLite store; returns the number of imported repositories
+  const migrateCacheToSqlite = useCallback(async (): Promise<number | null> => {
+    try {
+      const imported = await invoke<number>('migrate_cache_to_sqlite');
+      await loadCacheInfo();
+      return imported;
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, [loadCacheInfo]);
+
+  const listCacheBackups = useCallback(async (): Promise<CacheBackup[]> => {
+    try {
+      return await invoke<CacheBackup[]>('list_cache_backups');
+    } catch (err) {
+      setError(err as string);
+      return [];
+    }
+  }, []);
+
+  const restoreCacheBackup = useCallback(async (fileName: string) => {
+    try {
+      const repos = await invoke<GitRepository[]>('restore_cache_backup', { fileName });
+      setRepositories(repos);
+      setError(null);
+      await loadCacheInfo();
+    } catch (err) {
+      setError(err as string);
+    }
+  }, [loadCacheInfo]);
+
+  // Write scan paths, pins and collections to a portable file; returns the number of repositories
+  const exportWorkspace = useCallback(async (filePath: string): Promise<number | null> => {
+    try {
+      return await invoke<number>('export_workspace', { filePath });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const importWorkspace = useCallback(async (
+    filePath: string,
+    mode: WorkspaceImportMode,
+  ): Promise<WorkspaceImportReport | null> => {
+    try {
+      const report = await invoke<WorkspaceImportReport>('import_workspace', { filePath, mode });
+      await loadCachedRepositories();
+      await loadCacheInfo();
+      return report;
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, [loadCachedRepositories, loadCacheInfo]);
+
+  // Write a TOML manifest of the repositories under rootPath; returns the number of repositories listed
+  const generateManifest = useCallback(async (rootPath: string, filePath: string): Promise<number | null> => {
+    try {
+      return await invoke<number>('generate_manifest', { rootPath, filePath });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  // Clone the manifest's missing repositories below rootPath; progress arrives as restore-progress events
+  const restoreFromManifest = useCallback(async (filePath: string, rootPath: string): Promise<RestoreResult | null> => {
+    try {
+      const result = await invoke<RestoreResult>('restore_from_manifest', { filePath, rootPath });
+      await loadCachedRepositories();
+      await loadCacheInfo();
+      return result;
+    } catch (err) {
+      setError(err as string);
+      return null;
+    } finally {
+      setRestoreProgress(null);
+    }
+  }, [loadCachedRepositories, loadCacheInfo]);
+
+  // Run a shell command in every selected repository; output arrives as command-output events
+  const runBulkCommand = useCallback(async (
+    runId: string,
+    selection: RepositorySelection,
+    commandLine: string,
+    concurrency?: number,
+  ): Promise<BulkCommandSummary | null> => {
+    try {
+      return await invoke<BulkCommandSummary>('run_bulk_command', { runId, selection, commandLine, concurrency });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const cancelBulkCommand = useCallback(async (runId: string) => {
+    try {
+      await invoke('cancel_bulk_command', { runId });
+    } catch (err) {
+      setError(err as string);
+    }
+  }, []);
+
+  // Fetch all remotes of the selected repositories; updated repositories arrive as repository-updated events
+  const fetchRepositories = useCallback(async (
+    selection: RepositorySelection,
+    timeoutSecs?: number,
+  ): Promise<FetchResult[] | null> => {
+    try {
+      return await invoke<FetchResult[]>('fetch_repositories', { selection, timeoutSecs });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  // Fast-forward the current branch of the selected repositories where that is clean
+  const fastForwardRepositories = useCallback(async (selection: RepositorySelection): Promise<FastForwardReport | null> => {
+    try {
+      return await invoke<FastForwardReport>('fast_forward_repositories', { selection });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const cleanBuildArtifacts = useCallback(async (repoPath: string, mode: CleanupMode, kinds?: ArtifactKind[]): Promise<ArtifactCleanup | null> => {
+    try {
+      return await invoke<ArtifactCleanup>('delete_build_artifacts', { repoPath, mode, kinds });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const listCleanupOperations = useCallback(async (): Promise<CleanupOperation[]> => {
+    try {
+      return await invoke<CleanupOperation[]>('list_cleanup_operations');
+    } catch (err) {
+      setError(err as string);
+      return [];
+    }
+  }, []);
+
+  const undoCleanup = useCallback(async (operationId: string): Promise<UndoCleanupResult | null> => {
+    try {
+      return await invoke<UndoCleanupResult>('undo_cleanup', { operationId });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const getCleanupPolicies = useCallback(async (): Promise<CleanupPolicy[]> => {
+    try {
+      return await invoke<CleanupPolicy[]>('get_cleanup_policies');
+    } catch (err) {
+      setError(err as string);
+      return [];
+    }
+  }, []);
+
+  const createCleanupPolicy = useCallback(async (settings: CleanupPolicySettings): Promise<CleanupPolicy | null> => {
+    try {
+      return await invoke<CleanupPolicy>('create_cleanup_policy', { settings });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const updateCleanupPolicy = useCallback(async (policyId: string, settings: CleanupPolicySettings): Promise<CleanupPolicy | null> => {
+    try {
+      return await invoke<CleanupPolicy>('update_cleanup_policy', { policyId, settings });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const deleteCleanupPolicy = useCallback(async (policyId: string) => {
+    try {
+      await invoke('delete_cleanup_policy', { policyId });
+    } catch (err) {
+      setError(err as string);
+    }
+  }, []);
+
+  const previewCleanupPolicy = useCallback(async (policyId: string): Promise<PolicyPreview | null> => {
+    try {
+      return await invoke<PolicyPreview>('preview_cleanup_policy', { policyId });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const runCleanupPolicy = useCallback(async (policyId: string): Promise<PolicyRunReport | null> => {
+    try {
+      return await invoke<PolicyRunReport>('run_cleanup_policy', { policyId });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const getCleanupReports = useCallback(async (policyId?: string): Promise<PolicyRunReport[]> => {
+    try {
+      return await invoke<PolicyRunReport[]>('get_cleanup_reports', { policyId });
+    } catch (err) {
+      setError(err as string);
+      return [];
+    }
+  }, []);
+
+  // Disk usage of one repository; cancel with cancelBulkCommand
+  const getDiskUsage = useCallback(async (runId: string, repoPath: string): Promise<DiskUsage | null> => {
+    try {
+      return await invoke<DiskUsage>('get_disk_usage', { runId, repoPath });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  // Disk usage across repositories; cancel with cancelBulkCommand
+  const getWorkspaceDiskUsage = useCallback(async (
+    runId: string,
+    selection?: RepositorySelection,
+    topN?: number,
+  ): Promise<WorkspaceDiskUsage | null> => {
+    try {
+      return await invoke<WorkspaceDiskUsage>('get_workspace_disk_usage', { runId, selection, topN });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  const getGitHealth = useCallback(async (repoPath: string): Promise<GitHealth | null> => {
+    try {
+      return await invoke<GitHealth>('get_git_health', { repoPath });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
+  // Run git gc or git maintenance in the selected repositories; steps arrive as maintenance-progress
+  // events and the run is cancelled with cancelBulkCommand
+  const runGitMaintenance = useCallback(async (
+    runId: string,
+    selection: RepositorySelection,
+    task: MaintenanceTask,
+    concurrency?: number,
+  ): Promise<MaintenanceSummary | null> => {
+    try {
+      return await invoke<MaintenanceSummary>('run_git_maintenance', { runId, selection, task, concurrency });
+    } catch (err) {
+      setError(err as string);
+      return null;
+    }
+  }, []);
+
   const getCacheFilePath = useCallback(async (): Promise<string> => {
     try {
       return await invoke<string>('get_cache_file_path');
@@ -251,6 +554,12 @@ export const useRepositoryManager = () => {
   useEffect(() => {
     const unlistenScan = listen<ScanProgress>('scan-progress', (event) => {
       setScanProgress(event.payload);
+      if (event.payload.cancelled) {
+        setIsScanning(false);
+        setAnalysisProgress(null);
+        loadCachedRepositories();
+        loadCacheInfo();
+      }
     });
 
     const unlistenDiscover = listen<RepositoriesDiscovered>('repositories-discovered', async (event) => {
@@ -282,12 +591,32 @@ export const useRepositoryManager = () => {
       setAnalysisProgress(event.payload);
     });
 
+    // The backend watcher re-analyzes repositories when they change on disk
+    const unlistenUpdated = listen<GitRepository>('repository-updated', (event) => {
+      const updated = event.payload;
+      setRepositories(prev => {
+        const index = prev.findIndex(r => r.path === updated.path);
+        if (index === -1) {
+          return [...prev, updated];
+        }
+        const next = [...prev];
+        next[index] = updated;
+        return next;
+      });
+    });
+
+    const unlistenRestore = listen<RestoreProgress>('restore-progress', (event) => {
+      setRestoreProgress(event.payload);
+    });
+
     return () => {
       unlistenScan.then(f => f());
       unlistenDiscover.then(f => f());
       unlistenAnalysis.then(f => f());
+      unlistenUpdated.then(f => f());
+      unlistenRestore.then(f => f());
     };
-  }, [loadCacheInfo]);
+  }, [loadCacheInfo, loadCachedRepositories]);
 
   // Listen for window focus to reload repositories when returning to the app
   useEffect(() => {
@@ -311,10 +640,37 @@ export const useRepositoryManager = () => {
     cacheInfo,
     discoveredRepos,
     analysisProgress,
+    restoreProgress,
     loadCachedRepositories,
     scanRepositories,
     scanCustomPaths,
+    cancelScan,
     clearCache,
+    migrateCacheToSqlite,
+    listCacheBackups,
+    restoreCacheBackup,
+    exportWorkspace,
+    importWorkspace,
+    generateManifest,
+    restoreFromManifest,
+    runBulkCommand,
+    cancelBulkCommand,
+    fetchRepositories,
+    fastForwardRepositories,
+    cleanBuildArtifacts,
+    listCleanupOperations,
+    undoCleanup,
+    getCleanupPolicies,
+    createCleanupPolicy,
+    updateCleanupPolicy,
+    deleteCleanupPolicy,
+    previewCleanupPolicy,
+    runCleanupPolicy,
+    getCleanupReports,
+    getDiskUsage,
+    getWorkspaceDiskUsage,
+    getGitHealth,
+    runGitMaintenance,
     cleanupInvalidRepositories,
     openInVSCode,
     refreshRepository,
@@ -0,0 +1,21 @@
+import { CacheLoadState } from '../types/repository';
+
+/**
+ * Explains a cache that did not load normally at startup
+ * @param state Load state reported with the cache info
+ * @returns Message for the user, or null if the cache loaded normally
+ */
+export const describeCacheLoadState = (state: CacheLoadState): string | null => {
+  switch (state.type) {
+    case 'loaded':
+      return null;
+    case 'restored_from_backup':
+      return `The repository cache could not be read (${state.error}). It was moved aside and backup ${state.backup} was loaded instead.`;
+    case 'empty':
+      return `The repository cache could not be read (${state.error}) and no readable backup was found. It was moved aside and an empty cache was started.`;
+    case 'read_only':
+      return `The repository cache could not be loaded (${state.error}). Changes will not be saved until a backup is restored.`;
+    case 'newer_version':
+      return `${state.error}. The cache is left untouched and changes made now will not be saved.`;
+  }
+};
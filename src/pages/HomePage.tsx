@@ -1,7 +1,7 @@
 import React, { useState, useMemo, useEffect } from 'react';
 import { useNavigate } from 'react-router-dom';
 import { useRepositoryManager } from '../hooks/useRepositoryManager';
-import { GitRepository } from '../types/repository';
+import { ArtifactCleanup, GitRepository } from '../types/repository';
 import { RepositoryList } from '../components/RepositoryList';
 import { ScanProgress, RepositoriesDiscoveredInfo, AnalysisInProgress } from '../components/ScanProgress';
 import { ScanDirectoryManager } from '../components/ScanDirectoryManager';
@@ -38,22 +38,31 @@ export const HomePage: React.FC = () => {
     togglePin,
     loadCachedRepositories,
     refreshRepository,
+    undoCleanup,
     smartFilter,
     // optimizedSearch,  replaced this by smart filter(trie based)
     discoveredRepos,
     analysisProgress,
   } = useRepositoryManager();
 
-  const handleDeleteNodeModules = async (repoPath: string) => {
+  const handleDeleteBuildArtifacts = async (repoPath: string) => {
     try {
-      await invoke('delete_node_modules', { repoPath });
-      // Refresh the specific repository to update node_modules info
+      // Trashed rather than deleted, so the cleanup can be undone
+      const cleanup = await invoke<ArtifactCleanup>('delete_build_artifacts', { repoPath, mode: 'trash' });
+      // Refresh the specific repository to update build artifact sizes
       await refreshRepository(repoPath);
+      return cleanup;
     } catch (error) {
       throw error; // Re-throw so the component can handle it
     }
   };
 
+  const handleUndoCleanup = async (repoPath: string, operationId: string) => {
+    const result = await undoCleanup(operationId);
+    await refreshRepository(repoPath);
+    return result;
+  };
+
   // Refresh repositories when component mounts
   useEffect(() => {
     console.log('HomePage mounted, loading repositories...'); // Debug log
@@ -329,7 +338,8 @@ export const HomePage: React.FC = () => {
                 collectionRefreshTrigger={collectionsRefreshTrigger}
                 isLoading={(isScanning && !scanProgress) || isLoadingCollection || isInitialLoading || analysisProgress !== null}
                 isInitialLoad={isInitialLoading}
-                onDeleteNodeModules={handleDeleteNodeModules}
+                onDeleteBuildArtifacts={handleDeleteBuildArtifacts}
+                onUndoCleanup={handleUndoCleanup}
               />
             </div></div>
       <div className='col-span-1 hidden lg:block w-full border-l h-full flex flex-col'>
@@ -6,6 +6,7 @@ import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { AlertDialog, AlertDialogAction, AlertDialogCancel, AlertDialogContent, AlertDialogDescription, AlertDialogFooter, AlertDialogHeader, AlertDialogTitle, AlertDialogTrigger } from "@/components/ui/alert-dialog";
 import { Trash2, Database, AlertTriangle, FolderOpen } from "lucide-react";
 import { toast } from "sonner";
+import { describeCacheLoadState } from '../lib/cacheLoadState';
 
 export const SettingsPage: React.FC = () => {
   const [isClearing, setIsClearing] = useState(false);
@@ -113,6 +114,12 @@ export const SettingsPage: React.FC = () => {
                 </CardTitle>
               </CardHeader>
               <CardContent className="space-y-4">
+                {cacheInfo && describeCacheLoadState(cacheInfo.load_state) && (
+                  <div className="flex items-center gap-2 p-3 bg-amber-50 border border-amber-200 rounded-md">
+                    <AlertTriangle className="h-4 w-4 text-amber-600" />
+                    <p className="text-sm text-amber-800">{describeCacheLoadState(cacheInfo.load_state)}</p>
+                  </div>
+                )}
                 {cacheInfo ? (
                   <div className="grid grid-cols-2 md:grid-cols-4 gap-4">
                     <div className="space-y-1">
@@ -10,12 +10,82 @@ export interface RepositoriesDiscovered {
   repo_paths: string[];
 }
 
-export interface NodeModulesInfo {
-  total_size_mb: number;
-  count: number;
+export type ArtifactKind =
+  | 'node_modules'
+  | 'cargo_target'
+  | 'maven_target'
+  | 'gradle_build'
+  | 'gradle_cache'
+  | 'python_venv'
+  | 'python_cache'
+  | 'next_build'
+  | 'nuxt_build'
+  | 'js_dist'
+  | 'cmake_build';
+
+export interface ArtifactKindInfo {
+  kind: ArtifactKind;
+  size_mb: number;
   paths: string[];
+}
+
+export interface BuildArtifactsInfo {
+  total_size_mb: number;
+  kinds: ArtifactKindInfo[]; // Largest first
   last_scanned: string;
-  package_json_modified: string;
+  markers_modified: string; // Newest marker file (package.json, Cargo.toml, ...) at the last scan
+}
+
+// What a cleanup does with the directories it finds; 'trash' is only supported on Linux and BSDs
+export type CleanupMode = 'dry_run' | 'trash' | 'delete';
+
+export interface RemovedArtifact {
+  kind: ArtifactKind;
+  path: string;
+  bytes: number;
+  trashed_path?: string; // Where the directory went in trash mode
+}
+
+// In dry-run mode, what the cleanup would remove
+export interface ArtifactCleanup {
+  mode: CleanupMode;
+  removed: RemovedArtifact[];
+  total_bytes: number;
+  errors: string[];
+  operation_id?: string; // Log entry to undo a trash-mode cleanup with
+}
+
+export interface CleanupOperation {
+  id: string;
+  repo_path: string;
+  performed_at: string;
+  artifacts: RemovedArtifact[];
+  undone_at?: string;
+  restorable: boolean; // Not undone yet and all trashed directories still exist
+}
+
+export interface UndoCleanupResult {
+  operation_id: string;
+  restored: string[];
+  errors: string[];
+}
+
+export interface WorkingTreeStatus {
+  modified: number;
+  staged: number;
+  untracked: number;
+  conflicted: number;
+  renamed: number;
+  is_dirty: boolean;
+}
+
+export interface BranchInfo {
+  name: string;
+  is_remote: boolean;
+  is_head: boolean;
+  upstream?: string;
+  ahead: number;
+  behind: number;
 }
 
 export interface FileEntry {
@@ -31,6 +101,33 @@ export interface DirectoryListing {
   entries: FileEntry[];
 }
 
+export interface ParsedRemoteUrl {
+  scheme: string;
+  host?: string;
+  owner?: string;
+  repo_name?: string;
+}
+
+export interface RemoteInfo {
+  name: string;
+  fetch_url?: string;
+  push_url?: string;
+  parsed?: ParsedRemoteUrl;
+}
+
+export interface SubmoduleInfo {
+  name: string;
+  path: string;
+  url?: string;
+  recorded_commit?: string;
+  checked_out_commit?: string;
+  is_uninitialized: boolean;
+  is_out_of_sync: boolean;
+  is_modified: boolean;
+}
+
+export type RepositoryKind = 'normal' | 'bare' | 'worktree' | 'submodule';
+
 export interface GitRepository {
   name: string;
   path: string;
@@ -38,27 +135,175 @@ export interface GitRepository {
   file_types: Record<string, number>;
   last_commit_date?: string;
   current_branch?: string;
-  branches: string[];
+  branches: BranchInfo[];
   remote_url?: string;
+  remotes: RemoteInfo[];
   commit_count: number;
+  head_oid?: string;
+  working_tree_status?: WorkingTreeStatus;
+  kind: RepositoryKind;
+  parent_path?: string; // Main repository of a worktree, or superproject of a submodule
+  worktrees: string[];
+  submodules: SubmoduleInfo[];
   // Persistence metadata
   last_analyzed: string;
+  last_fetched?: string; // Last fetch from gitmap in which at least one remote succeeded
   is_valid: boolean;
   is_pinned: boolean;
   pinned_at?: string;
-  node_modules_info?: NodeModulesInfo;
+  build_artifacts?: BuildArtifactsInfo; // Absent when the repository has no build artifacts
 }
 
 export interface ScanProgress {
   current_path: string;
   repos_found: number;
   completed: boolean;
+  cancelled: boolean;
+}
+
+export interface ScanConfig {
+  include_patterns: string[];
+  exclude_patterns: string[];
+  max_depth?: number;
+  scan_concurrency?: number; // Repositories analyzed in parallel; one per core when unset. Not used in overrides
 }
 
 export interface ScanPath {
   path: string;
   last_scanned?: string;
   repository_count: number;
+  overrides?: ScanConfig;
+}
+
+export interface CacheBackup {
+  file_name: string;
+  created_at: string;
+  size: number; // Bytes
+}
+
+export type RepositorySelection =
+  | { type: 'paths'; paths: string[] }
+  | { type: 'collection'; collection_id: string }
+  | { type: 'search'; filter: SearchFilter };
+
+// Same criteria as advanced_repository_search; all set criteria must match
+export interface SearchFilter {
+  name_prefix?: string;
+  min_size_mb?: number;
+  max_size_mb?: number;
+  file_type?: string;
+  remote_host?: string;
+  remote_owner?: string;
+}
+
+// Payload of command-output events
+export interface CommandOutput {
+  run_id: string;
+  repo_path: string;
+  stream: 'stdout' | 'stderr';
+  line: string;
+}
+
+export type CommandStatus = 'succeeded' | 'failed' | 'cancelled' | 'error' | 'skipped';
+
+// Payload of command-finished events
+export interface CommandResult {
+  run_id: string;
+  repo_path: string;
+  status: CommandStatus;
+  exit_code?: number; // Missing when killed by a signal or not run
+  duration_ms: number;
+  error?: string; // Set when status is 'error'
+}
+
+export interface BulkCommandSummary {
+  run_id: string;
+  results: CommandResult[]; // In selection order
+  succeeded: number;
+  failed: number;
+  cancelled: number;
+}
+
+// Payload of fetch-progress events
+export interface FetchProgress {
+  repo_path: string;
+  remote: string;
+  received_objects: number;
+  total_objects: number;
+  received_bytes: number;
+}
+
+export type FetchStatus = 'fetched' | 'failed' | 'timed_out';
+
+// Payload of fetch-finished events
+export interface FetchResult {
+  repo_path: string;
+  status: FetchStatus;
+  fetched_remotes: string[];
+  errors: string[]; // One per failed remote, prefixed with its name
+  duration_ms: number;
+}
+
+export type FastForwardSkipReason =
+  | 'bare'
+  | 'detached'
+  | 'unborn'
+  | 'no_upstream'
+  | 'dirty' // Uncommitted changes to tracked files
+  | 'up_to_date'
+  | 'diverged'; // Needs a merge or rebase
+
+export interface FastForwardUpdate {
+  repo_path: string;
+  branch: string;
+  upstream: string;
+  from: string; // Commit ids before and after
+  to: string;
+  commits: number;
+}
+
+export interface FastForwardReport {
+  updated: FastForwardUpdate[];
+  skipped: { repo_path: string; reason: FastForwardSkipReason }[];
+  failed: { repo_path: string; error: string }[];
+}
+
+export type RestoreStatus = 'cloning' | 'cloned' | 'skipped' | 'failed';
+
+export interface RestoreProgress {
+  path: string; // Local path the repository is cloned to
+  current: number; // 1-based position in the manifest
+  total: number;
+  status: RestoreStatus;
+  received_objects: number;
+  total_objects: number;
+  error?: string; // Set when status is 'failed'
+}
+
+export interface RestoreFailure {
+  path: string;
+  error: string;
+}
+
+export interface RestoreResult {
+  cloned: string[];
+  skipped: string[];
+  failed: RestoreFailure[];
+}
+
+export type WorkspaceImportMode = 'merge' | 'replace';
+
+export interface UnmatchedRepository {
+  name: string;
+  remote_url?: string;
+  expected_path: string; // Where the repository would be on this machine
+}
+
+export interface WorkspaceImportReport {
+  scan_paths_added: number;
+  repositories_matched: number;
+  collections_imported: number;
+  unmatched: UnmatchedRepository[];
 }
 
 export interface CacheInfo {
@@ -67,8 +312,17 @@ export interface CacheInfo {
   cache_file_size: number;
   valid_repositories: number;
   invalid_repositories: number;
+  load_state: CacheLoadState;
 }
 
+// How the cache was loaded at startup; anything but 'loaded' is shown to the user
+export type CacheLoadState =
+  | { type: 'loaded' }
+  | { type: 'restored_from_backup'; backup: string; error: string } // The cache file was unreadable and moved aside
+  | { type: 'empty'; error: string } // Unreadable cache moved aside, and no readable backup either
+  | { type: 'read_only'; error: string } // Left untouched; changes are not saved
+  | { type: 'newer_version'; error: string }; // Written by a newer app version; nothing is saved
+
 export interface Collection {
   id: string;
   name: string;
@@ -82,16 +336,18 @@ export interface SearchFilters {
   minSizeMb?: number;
   maxSizeMb?: number;
   fileType?: string;
+  remoteHost?: string;
+  remoteOwner?: string;
   pathFilter?: string;
 }
 
 export interface OptimizationStats {
   total_repositories: number;
-  lru_cache_size: number;
-  lru_cache_capacity: number;
   index_name_entries: number;
   index_size_ranges: number;
   index_file_types: number;
+  index_remote_hosts: number;
+  index_remote_owners: number;
 }
 
 export interface AppState {
@@ -102,3 +358,126 @@ export interface AppState {
   error: string | null;
   cacheInfo: CacheInfo | null;
 }
+
+// User-editable part of a cleanup policy
+export interface CleanupPolicySettings {
+  name: string;
+  enabled: boolean; // Disabled policies never run on their schedule
+  inactive_days: number; // Last commit and last file change must both be older than this
+  kinds?: ArtifactKind[]; // Absent: every kind
+  exclude_pinned: boolean;
+  exclude_collections: string[]; // Collection ids
+  mode: CleanupMode;
+  interval_hours?: number; // Run automatically this often; absent: on demand only
+}
+
+export interface CleanupPolicy {
+  id: string;
+  settings: CleanupPolicySettings;
+  last_scheduled_run?: string; // The schedule counts from here
+  last_manual_run?: string; // Runs on demand do not move the schedule
+  created_at: string;
+}
+
+export interface PolicyMatch {
+  repo_path: string;
+  name: string;
+  last_commit_date?: string;
+  last_modified?: string; // Newest file outside .git and build artifacts
+  estimated_bytes: number; // From the cached artifact sizes
+  kinds: ArtifactKind[];
+}
+
+export interface PolicyPreview {
+  policy_id: string;
+  evaluated_at: string;
+  matches: PolicyMatch[];
+  estimated_bytes: number;
+}
+
+export type PolicyTrigger = 'manual' | 'scheduled';
+
+export interface PolicyRepositoryCleanup {
+  repo_path: string;
+  cleanup: ArtifactCleanup;
+}
+
+export interface PolicyRunReport {
+  id: string;
+  policy_id: string;
+  policy_name: string;
+  trigger: PolicyTrigger;
+  mode: CleanupMode;
+  started_at: string;
+  finished_at: string;
+  repositories: PolicyRepositoryCleanup[];
+  total_bytes: number;
+  errors: string[]; // Repositories that could not be cleaned at all
+}
+
+// Space a repository takes on disk, in allocated bytes, by category
+export interface DiskUsage {
+  repo_path: string;
+  working_tree_bytes: number; // Tracked and untracked files that are not ignored
+  git_objects_bytes: number; // Packs and loose objects
+  git_other_bytes: number; // Rest of the git directory: index, refs, logs, submodule git dirs
+  lfs_bytes: number; // Git LFS object store
+  build_artifacts_bytes: number;
+  ignored_bytes: number; // Ignored files outside build artifacts
+  total_bytes: number;
+  hardlinked_bytes: number; // Further links to files already counted, not included in the total
+  file_count: number;
+}
+
+export interface DiskUsageFailure {
+  repo_path: string;
+  error: string;
+}
+
+export interface WorkspaceDiskUsage {
+  totals: DiskUsage; // Sums over all analyzed repositories; repo_path is empty
+  top_repositories: DiskUsage[]; // Largest first
+  repositories_analyzed: number;
+  failures: DiskUsageFailure[];
+  cancelled: boolean; // Stopped by cancelBulkCommand before every repository was analyzed
+}
+
+// State of a repository's object database; worktrees report on the shared git directory
+export interface GitHealth {
+  repo_path: string;
+  git_dir_bytes: number; // Allocated size of the whole git directory
+  loose_objects: number;
+  loose_objects_bytes: number;
+  pack_count: number;
+  packs_bytes: number; // Pack files with their indexes, bitmaps and reverse indexes
+  reflog_bytes: number;
+  gc_log?: string; // Path of a gc.log left by a failed automatic gc
+  has_commit_graph: boolean;
+  needs_maintenance: boolean;
+}
+
+// 'gc' repacks everything; 'incremental' runs the cheap git maintenance tasks
+export type MaintenanceTask = 'gc' | 'incremental';
+
+export interface MaintenanceResult {
+  command: CommandResult;
+  before_bytes: number; // Git directory size before and after the run
+  after_bytes: number;
+  maintained_with?: string; // Set when skipped: the repository sharing this git directory that was maintained
+}
+
+export type MaintenancePhase = 'measuring' | 'running' | 'measuring_after';
+
+// Payload of maintenance-progress events; git prints no progress of its own when not on a terminal
+export interface MaintenanceProgress {
+  run_id: string;
+  repo_path: string;
+  phase: MaintenancePhase;
+}
+
+export interface MaintenanceSummary {
+  run_id: string;
+  task: MaintenanceTask;
+  results: MaintenanceResult[]; // In selection order; worktrees sharing a git directory are skipped
+  freed_bytes: number;
+}